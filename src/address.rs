@@ -0,0 +1,335 @@
+//! Encode the address of a script pubkey, without allocating and without the `bitcoin` crate.
+//!
+//! Pay to public key hash and pay to script hash scripts are encoded with Base58Check, witness
+//! programs with bech32 (version 0) or bech32m (other versions).
+//!
+//! ```
+//! # use bitcoin_slices::{address::{Address, MAINNET, MAX_ADDRESS_LEN}, bsl::TxOut, Parse};
+//! let tx_out = hex_lit::hex!("0000000000000000160014751e76e8199196d454941c45d1b3a323f1433bd6");
+//! let tx_out = TxOut::parse(&tx_out[..]).unwrap().parsed_owned();
+//! let address = tx_out.address(&MAINNET).unwrap();
+//! let mut buffer = [0u8; MAX_ADDRESS_LEN];
+//! let expected = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+//! assert_eq!(address.encode(&mut buffer).unwrap(), expected);
+//! assert_eq!(address.to_string(), expected);
+//! ```
+
+use core::fmt::{self, Write};
+
+use crate::bsl::ScriptType;
+
+/// The maximum length of an address, the one of a bech32 string
+pub const MAX_ADDRESS_LEN: usize = 90;
+
+/// The network specific parameters of address encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressParams {
+    /// The version byte of pay to public key hash addresses
+    pub p2pkh_prefix: u8,
+    /// The version byte of pay to script hash addresses
+    pub p2sh_prefix: u8,
+    /// The human readable part of segwit addresses, must be lowercase
+    pub hrp: &'static str,
+}
+
+/// Address parameters of mainnet
+pub const MAINNET: AddressParams = AddressParams {
+    p2pkh_prefix: 0x00,
+    p2sh_prefix: 0x05,
+    hrp: "bc",
+};
+
+/// Address parameters of testnet (version 3 and 4) and signet
+pub const TESTNET: AddressParams = AddressParams {
+    p2pkh_prefix: 0x6f,
+    p2sh_prefix: 0xc4,
+    hrp: "tb",
+};
+
+/// Address parameters of regtest
+pub const REGTEST: AddressParams = AddressParams {
+    p2pkh_prefix: 0x6f,
+    p2sh_prefix: 0xc4,
+    hrp: "bcrt",
+};
+
+/// The address of a script pubkey, borrowing the hash or the witness program from the script.
+///
+/// Use the [`fmt::Display`] implementation to write it in a [`fmt::Write`] or
+/// [`Address::encode()`] to write it in a fixed buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Address<'a> {
+    payload: Payload<'a>,
+    hrp: &'static str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Payload<'a> {
+    Base58 { prefix: u8, hash: &'a [u8] },
+    Segwit { version: u8, program: &'a [u8] },
+}
+
+impl<'a> Address<'a> {
+    /// Returns the address of `script_pubkey`, or `None` if the script has no address
+    /// representation, for example pay to public key, bare multisig or `OP_RETURN` scripts.
+    pub fn from_script(script_pubkey: &'a [u8], params: &AddressParams) -> Option<Self> {
+        let payload = match ScriptType::classify(script_pubkey) {
+            ScriptType::P2pkh(hash) => Payload::Base58 {
+                prefix: params.p2pkh_prefix,
+                hash,
+            },
+            ScriptType::P2sh(hash) => Payload::Base58 {
+                prefix: params.p2sh_prefix,
+                hash,
+            },
+            ScriptType::P2wpkh(program) | ScriptType::P2wsh(program) => Payload::Segwit {
+                version: 0,
+                program,
+            },
+            ScriptType::P2tr(_) | ScriptType::P2a | ScriptType::WitnessUnknown { .. } => {
+                Payload::Segwit {
+                    version: script_pubkey[0] - 0x50,
+                    program: &script_pubkey[2..],
+                }
+            }
+            _ => return None,
+        };
+        Some(Address {
+            payload,
+            hrp: params.hrp,
+        })
+    }
+
+    /// Write the address in `buffer` and return it as string.
+    ///
+    /// Returns an error only if the address doesn't fit, which happens only with human readable
+    /// parts longer than the ones of known networks.
+    pub fn encode<'b>(&self, buffer: &'b mut [u8; MAX_ADDRESS_LEN]) -> Result<&'b str, fmt::Error> {
+        let mut writer = BufferWriter { buffer, len: 0 };
+        write!(writer, "{}", self)?;
+        let len = writer.len;
+        Ok(core::str::from_utf8(&buffer[..len]).expect("address chars are ascii"))
+    }
+}
+
+impl<'a> fmt::Display for Address<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.payload {
+            Payload::Base58 { prefix, hash } => {
+                let mut data = [0u8; 25];
+                data[0] = prefix;
+                data[1..21].copy_from_slice(hash);
+                let checksum = crate::hashes::sha256d(&[&data[..21]]);
+                data[21..].copy_from_slice(&checksum[..4]);
+                base58(&data, f)
+            }
+            Payload::Segwit { version, program } => bech32(self.hrp, version, program, f),
+        }
+    }
+}
+
+struct BufferWriter<'b> {
+    buffer: &'b mut [u8; MAX_ADDRESS_LEN],
+    len: usize,
+}
+
+impl<'b> Write for BufferWriter<'b> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        self.buffer
+            .get_mut(self.len..end)
+            .ok_or(fmt::Error)?
+            .copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+const BASE58_CHARS: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+fn base58<W: Write>(data: &[u8; 25], w: &mut W) -> fmt::Result {
+    // 25 bytes are at most 35 base58 digits, stored in reverse order
+    let mut digits = [0u8; 35];
+    let mut len = 0;
+    for byte in data {
+        let mut carry = *byte as u32;
+        for digit in digits[..len].iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits[len] = (carry % 58) as u8;
+            len += 1;
+            carry /= 58;
+        }
+    }
+    for _ in data.iter().take_while(|b| **b == 0) {
+        w.write_char('1')?;
+    }
+    for digit in digits[..len].iter().rev() {
+        w.write_char(BASE58_CHARS[*digit as usize] as char)?;
+    }
+    Ok(())
+}
+
+const BECH32_CHARS: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const BECH32_CONST: u32 = 1;
+const BECH32M_CONST: u32 = 0x2bc830a3;
+
+fn polymod(checksum: u32, value: u8) -> u32 {
+    const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+    let top = checksum >> 25;
+    let mut checksum = ((checksum & 0x1ffffff) << 5) ^ value as u32;
+    for (i, g) in GENERATOR.iter().enumerate() {
+        if (top >> i) & 1 == 1 {
+            checksum ^= g;
+        }
+    }
+    checksum
+}
+
+fn bech32<W: Write>(hrp: &str, version: u8, program: &[u8], w: &mut W) -> fmt::Result {
+    let mut checksum = 1;
+    for c in hrp.bytes() {
+        checksum = polymod(checksum, c >> 5);
+    }
+    checksum = polymod(checksum, 0);
+    for c in hrp.bytes() {
+        checksum = polymod(checksum, c & 0x1f);
+    }
+    w.write_str(hrp)?;
+    w.write_char('1')?;
+
+    let mut write_5bits = |value: u8| {
+        checksum = polymod(checksum, value);
+        w.write_char(BECH32_CHARS[value as usize] as char)
+    };
+    write_5bits(version)?;
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for byte in program {
+        acc = (acc << 8) | *byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            write_5bits(((acc >> bits) & 0x1f) as u8)?;
+        }
+    }
+    if bits > 0 {
+        write_5bits(((acc << (5 - bits)) & 0x1f) as u8)?;
+    }
+
+    for _ in 0..6 {
+        checksum = polymod(checksum, 0);
+    }
+    checksum ^= if version == 0 {
+        BECH32_CONST
+    } else {
+        BECH32M_CONST
+    };
+    for i in (0..6).rev() {
+        w.write_char(BECH32_CHARS[((checksum >> (5 * i)) & 0x1f) as usize] as char)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Address, MAINNET, MAX_ADDRESS_LEN, REGTEST, TESTNET};
+    use crate::{bsl::Block, Visit, Visitor};
+    use core::ops::ControlFlow;
+    use hex_lit::hex;
+
+    fn check(script: &[u8], params: &super::AddressParams, expected: &str) {
+        let address = Address::from_script(script, params).unwrap();
+        let mut buffer = [0u8; MAX_ADDRESS_LEN];
+        assert_eq!(address.encode(&mut buffer).unwrap(), expected);
+        assert_eq!(address.to_string(), expected);
+    }
+
+    #[test]
+    fn encode() {
+        // BIP173 and BIP350 test vectors
+        check(
+            &hex!("0014751e76e8199196d454941c45d1b3a323f1433bd6"),
+            &MAINNET,
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+        );
+        check(
+            &hex!("00201863143c14c5166804bd19203356da136c985678cd4d27a1b8c6329604903262"),
+            &TESTNET,
+            "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7",
+        );
+        check(
+            &hex!("5128751e76e8199196d454941c45d1b3a323f1433bd6751e76e8199196d454941c45d1b3a323f1433bd6"),
+            &MAINNET,
+            "bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kt5nd6y",
+        );
+        check(&hex!("6002751e"), &MAINNET, "bc1sw50qgdz25j");
+        check(
+            &hex!("5120a60869f0dbcf1dc659c9cecbaf8050135ea9e8cdc487053f1dc6880949dc684c"),
+            &MAINNET,
+            "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr",
+        );
+        check(&hex!("51024e73"), &MAINNET, "bc1pfeessrawgf");
+        check(
+            &hex!("0014751e76e8199196d454941c45d1b3a323f1433bd6"),
+            &REGTEST,
+            "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080",
+        );
+
+        check(
+            &hex!("76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac"),
+            &MAINNET,
+            "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa",
+        );
+        check(
+            &hex!("76a914000000000000000000000000000000000000000088ac"),
+            &MAINNET,
+            "1111111111111111111114oLvT2",
+        );
+        check(
+            &hex!("a914f815b036d9bbbce5e9f2a00abd1bf3dc91e9551087"),
+            &MAINNET,
+            "3QJmV3qfvL9SuYo34YihAf3sRCW3qSinyC",
+        );
+        let p2sh = hex!("a914f815b036d9bbbce5e9f2a00abd1bf3dc91e9551087");
+        let expected = bitcoin::Address::from_script(
+            bitcoin::Script::from_bytes(&p2sh),
+            bitcoin::Network::Testnet,
+        )
+        .unwrap();
+        check(&p2sh, &TESTNET, &expected.to_string());
+
+        assert!(Address::from_script(&hex!("6a"), &MAINNET).is_none());
+    }
+
+    #[test]
+    fn encode_as_rust_bitcoin() {
+        struct Check(usize);
+        impl Visitor for Check {
+            fn visit_tx_out(
+                &mut self,
+                _vout: usize,
+                tx_out: &crate::bsl::TxOut,
+            ) -> ControlFlow<()> {
+                let script = bitcoin::Script::from_bytes(tx_out.script_pubkey());
+                let expected = bitcoin::Address::from_script(script, bitcoin::Network::Bitcoin);
+                let address = tx_out.address(&MAINNET);
+                match (expected, address) {
+                    (Ok(expected), Some(address)) => {
+                        assert_eq!(expected.to_string(), address.to_string());
+                        self.0 += 1;
+                    }
+                    (Err(_), None) => (),
+                    (expected, address) => panic!("{:?} {:?}", expected, address),
+                }
+                ControlFlow::Continue(())
+            }
+        }
+        let mut check = Check(0);
+        Block::visit(bitcoin_test_data::blocks::mainnet_702861(), &mut check).unwrap();
+        assert!(check.0 > 5000);
+    }
+}
@@ -1,6 +1,7 @@
 use super::len::scan_len;
 use crate::bsl::{BlockHeader, Transaction};
-use crate::{ParseResult, SResult, Visit, Visitor};
+use crate::detailed::{self, DetailedError};
+use crate::{Error, Parse, ParseResult, SResult, Visit, Visitor};
 
 /// A Bitcoin block.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -35,6 +36,29 @@ impl<'a> Visit<'a> for Block<'a> {
 }
 
 impl<'a> Block<'a> {
+    /// Creates the block of `total_txs` transactions from an already validated slice
+    pub(crate) fn from_parsed(slice: &'a [u8], total_txs: usize) -> Self {
+        let header = BlockHeader::parse(slice).expect("granted by parsing");
+        Block {
+            slice,
+            header: header.parsed_owned(),
+            total_txs,
+        }
+    }
+
+    /// Visit the block like [`Visit::visit()`], in case of error returns also the position and
+    /// the context where it happened, see [`DetailedError`].
+    ///
+    /// It's slower than [`Visit::visit()`] because every call to the visitor goes through a
+    /// wrapper keeping track of the position, use it when the details are needed, for example
+    /// to report why a block received from a peer is invalid.
+    pub fn visit_detailed<V: Visitor>(
+        slice: &'a [u8],
+        visit: &mut V,
+    ) -> Result<ParseResult<'a, Self>, DetailedError> {
+        detailed::visit_detailed(slice, visit)
+    }
+
     /// Returns the hash of this block
     #[cfg(feature = "bitcoin_hashes")]
     pub fn block_hash(&self) -> crate::bitcoin_hashes::sha256d::Hash {
@@ -51,6 +75,15 @@ impl<'a> Block<'a> {
         self.header.block_hash_sha2()
     }
 
+    /// Verify the merkle root in the header matches the transactions of this block and the
+    /// witness commitment, if any, matches their witnesses, see [`crate::bsl::MerkleVerifier`].
+    #[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+    pub fn verify_merkle(&self) -> Result<(), crate::Error> {
+        let mut verifier = crate::bsl::MerkleVerifier::new();
+        Self::visit(self.slice, &mut verifier)?;
+        verifier.verify(&self.header)
+    }
+
     /// Returns the total transactions in this block
     pub fn total_transactions(&self) -> usize {
         self.total_txs
@@ -60,6 +93,122 @@ impl<'a> Block<'a> {
     pub fn header(&self) -> &BlockHeader {
         &self.header
     }
+
+    /// Returns an iterator over the transactions in this block
+    ///
+    /// If possible is better to use [`Visitor::visit_transaction`] to avoid double pass, however,
+    /// it may be convenient to iterate in case you already have validated the slice, for example
+    /// some data in a db.
+    pub fn transactions(&self) -> TransactionIterator<'a> {
+        let mut consumed = 80;
+        scan_len(&self.slice[80..], &mut consumed).expect("len granted by parsing");
+        TransactionIterator {
+            remaining: self.total_txs,
+            slice: &self.slice[consumed..],
+        }
+    }
+
+    /// Returns an index of the transactions in this block, using `offsets` to store the position
+    /// of every transaction in the block.
+    ///
+    /// Returns [`Error::BufferTooSmall`] if `offsets` is shorter than the number of transactions.
+    pub fn index_into<'b>(
+        &self,
+        offsets: &'b mut [u32],
+    ) -> Result<BlockTxIndex<'a, &'b [u32]>, Error> {
+        let offsets = offsets
+            .get_mut(..self.total_txs)
+            .ok_or(Error::BufferTooSmall)?;
+        self.fill_offsets(offsets);
+        Ok(BlockTxIndex {
+            slice: self.slice,
+            offsets,
+        })
+    }
+
+    /// Returns an index of the transactions in this block, allocating the position of every
+    /// transaction in the block.
+    #[cfg(feature = "alloc")]
+    pub fn index(&self) -> BlockTxIndex<'a, alloc::vec::Vec<u32>> {
+        let mut offsets = alloc::vec![0u32; self.total_txs];
+        self.fill_offsets(&mut offsets);
+        BlockTxIndex {
+            slice: self.slice,
+            offsets,
+        }
+    }
+
+    fn fill_offsets(&self, offsets: &mut [u32]) {
+        let mut offset = self.slice.len() - self.transactions().slice.len();
+        for (tx, o) in self.transactions().zip(offsets.iter_mut()) {
+            *o = offset as u32;
+            offset += tx.as_ref().len();
+        }
+    }
+}
+
+/// Iterator over the [`Transaction`] of a [`Block`], returned by [`Block::transactions()`]
+#[derive(Debug, Clone)]
+pub struct TransactionIterator<'a> {
+    remaining: usize,
+    slice: &'a [u8],
+}
+
+impl<'a> Iterator for TransactionIterator<'a> {
+    type Item = Transaction<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let tx = Transaction::parse(self.slice).expect("granted from parsing");
+        self.remaining -= 1;
+        self.slice = tx.remaining();
+        Some(tx.parsed_owned())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for TransactionIterator<'a> {}
+
+/// The positions of the transactions in a [`Block`], giving access to any transaction without
+/// visiting the preceding ones, created with [`Block::index_into()`] or [`Block::index()`].
+///
+/// The offsets are stored in `O`, a caller buffer or an allocated vector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockTxIndex<'a, O> {
+    slice: &'a [u8],
+    offsets: O,
+}
+
+impl<'a, O: AsRef<[u32]>> BlockTxIndex<'a, O> {
+    /// Returns the number of transactions in the block
+    pub fn len(&self) -> usize {
+        self.offsets.as_ref().len()
+    }
+
+    /// Returns if there are no transactions in the block, which is never the case for a valid block
+    pub fn is_empty(&self) -> bool {
+        self.offsets.as_ref().is_empty()
+    }
+
+    /// Returns the offsets of the transactions in the block
+    pub fn offsets(&self) -> &[u32] {
+        self.offsets.as_ref()
+    }
+
+    /// Returns the transaction at position `i` in the block, `None` if out of range.
+    ///
+    /// The transaction is found in constant time, only the transaction itself is parsed again
+    /// to compute its accessors.
+    pub fn tx(&self, i: usize) -> Option<Transaction<'a>> {
+        let start = *self.offsets.as_ref().get(i)? as usize;
+        let tx = Transaction::parse(&self.slice[start..]).expect("granted from parsing");
+        Some(tx.parsed_owned())
+    }
 }
 
 impl<'a> AsRef<[u8]> for Block<'a> {
@@ -114,7 +263,7 @@ mod test {
 
     use crate::{
         bsl::{Block, BlockHeader},
-        test_common::GENESIS_BLOCK,
+        test_common::{GENESIS_BLOCK, GENESIS_TX},
         Parse,
     };
 
@@ -158,10 +307,44 @@ mod test {
 
         let block = Block::parse(&FUZZ_DATA).unwrap_err();
         assert_eq!(block, crate::Error::MoreBytesNeeded);
-        // let mut iter = block.parsed.transactions();
-        // let genesis_tx = iter.next().unwrap();
-        // assert_eq!(genesis_tx.as_ref(), GENESIS_TX);
-        // assert!(iter.next().is_none())
+
+        let block = Block::parse(&GENESIS_BLOCK).unwrap();
+        let mut iter = block.parsed().transactions();
+        let genesis_tx = iter.next().unwrap();
+        assert_eq!(genesis_tx.as_ref(), GENESIS_TX);
+        assert!(iter.next().is_none())
+    }
+
+    #[cfg(feature = "bitcoin")]
+    #[test]
+    fn transactions() {
+        use bitcoin::consensus::{deserialize, serialize};
+
+        let expected: bitcoin::Block = deserialize(mainnet_702861()).unwrap();
+        let block = Block::parse(mainnet_702861()).unwrap().parsed_owned();
+        let iter = block.transactions();
+        assert_eq!(iter.len(), 2500);
+        for (tx, expected) in iter.zip(expected.txdata.iter()) {
+            assert_eq!(tx.as_ref(), &serialize(expected)[..]);
+        }
+
+        let mut offsets = [0u32; 2500];
+        assert_eq!(
+            block.index_into(&mut offsets[..2499]),
+            Err(crate::Error::BufferTooSmall)
+        );
+        let index = block.index_into(&mut offsets).unwrap();
+        assert_eq!(index.len(), 2500);
+        for i in [0, 1, 1234, 2499] {
+            assert_eq!(
+                index.tx(i).unwrap().as_ref(),
+                &serialize(&expected.txdata[i])[..]
+            );
+        }
+        assert!(index.tx(2500).is_none());
+
+        #[cfg(feature = "alloc")]
+        assert_eq!(block.index().offsets(), index.offsets());
     }
 
     #[cfg(all(feature = "bitcoin", feature = "sha2"))]
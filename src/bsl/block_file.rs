@@ -0,0 +1,329 @@
+use crate::{bsl::Block, number::read_u32, Error, Parse};
+
+/// Iterator over the blocks contained in a Bitcoin Core block file (`blk*.dat`), for example a
+/// memory-mapped file.
+///
+/// Block files are a sequence of `magic | u32 size | block` records. Since Bitcoin Core
+/// pre-allocates the files, the last record is usually followed by zero bytes which terminate the
+/// iteration.
+///
+/// Every item is the [`Block`] with the offset in the file of its first byte (after the magic and
+/// the size), which is the position Bitcoin Core stores in its block index.
+///
+/// Block files written by Bitcoin Core 28 or later may be obfuscated with the key in the
+/// `blocks/xor.dat` file, in this case use [`ObfuscatedBlockFile`], or [`deobfuscate`] the data
+/// before iterating if it can be modified in place.
+///
+/// After an error is returned the iteration ends.
+#[derive(Debug, Clone)]
+pub struct BlockFile<'a> {
+    slice: &'a [u8],
+    magic: [u8; 4],
+    offset: usize,
+}
+
+impl<'a> BlockFile<'a> {
+    /// Creates the iterator over the block file data `slice` where every block is expected to be
+    /// prefixed by `magic`, see [`crate::bsl::p2p::magic`] for known values.
+    pub fn new(slice: &'a [u8], magic: [u8; 4]) -> Self {
+        BlockFile {
+            slice,
+            magic,
+            offset: 0,
+        }
+    }
+
+    fn next_block(&mut self) -> Result<Option<(usize, Block<'a>)>, Error> {
+        let (block_offset, block_slice) = match next_record(self.slice, self.offset, self.magic)? {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+        let block = Block::parse(block_slice)?;
+        if !block.remaining().is_empty() {
+            return Err(Error::BlockSizeMismatch);
+        }
+        self.offset = block_offset + block_slice.len();
+        Ok(Some((block_offset, block.parsed_owned())))
+    }
+}
+
+/// Iterator over the blocks contained in a Bitcoin Core block file obfuscated with `key`, like
+/// [`BlockFile`] but without modifying the data, for example a read-only memory-mapped file.
+///
+/// Every record is deobfuscated into a buffer given by the caller to
+/// [`ObfuscatedBlockFile::next_block()`], since the returned [`Block`] borrows the buffer this is
+/// not an [`Iterator`].
+///
+/// Zero bytes terminate the iteration both before and after deobfuscation, because the file is
+/// pre-allocated with zeros which are not obfuscated.
+///
+/// After an error is returned the iteration ends.
+#[derive(Debug, Clone)]
+pub struct ObfuscatedBlockFile<'a> {
+    slice: &'a [u8],
+    magic: [u8; 4],
+    key: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> ObfuscatedBlockFile<'a> {
+    /// Creates the iterator over the block file data `slice` obfuscated with `key`, the content of
+    /// the `blocks/xor.dat` file, where every block is expected to be prefixed by `magic`.
+    pub fn new(slice: &'a [u8], magic: [u8; 4], key: &'a [u8]) -> Self {
+        ObfuscatedBlockFile {
+            slice,
+            magic,
+            key,
+            offset: 0,
+        }
+    }
+
+    /// Deobfuscates the next block in `buffer` and returns it with the offset in the file of its
+    /// first byte, `None` if there are no more blocks.
+    ///
+    /// Returns [`Error::BufferTooSmall`] if the block doesn't fit in `buffer`, 4 MB are enough for
+    /// any valid block.
+    pub fn next_block<'b>(
+        &mut self,
+        buffer: &'b mut [u8],
+    ) -> Option<Result<(usize, Block<'b>), Error>> {
+        match self.next_block_into(buffer) {
+            Ok(Some(block)) => Some(Ok(block)),
+            Ok(None) => {
+                self.offset = self.slice.len();
+                None
+            }
+            Err(e) => {
+                self.offset = self.slice.len();
+                Some(Err(e))
+            }
+        }
+    }
+
+    fn next_block_into<'b>(
+        &mut self,
+        buffer: &'b mut [u8],
+    ) -> Result<Option<(usize, Block<'b>)>, Error> {
+        let (block_offset, size) =
+            match record_bounds(self.slice, self.offset, self.magic, self.key)? {
+                Some(bounds) => bounds,
+                None => return Ok(None),
+            };
+        let buffer = buffer.get_mut(..size).ok_or(Error::BufferTooSmall)?;
+        buffer.copy_from_slice(&self.slice[block_offset..block_offset + size]);
+        deobfuscate(buffer, self.key, block_offset);
+        let buffer: &'b [u8] = buffer;
+        let block = Block::parse(buffer)?;
+        if !block.remaining().is_empty() {
+            return Err(Error::BlockSizeMismatch);
+        }
+        self.offset = block_offset + size;
+        Ok(Some((block_offset, block.parsed_owned())))
+    }
+}
+
+/// Returns the offset and the content of the `magic | u32 size | content` record starting at
+/// `offset`, or `None` if the data is terminated by zero bytes.
+pub(crate) fn next_record(
+    slice: &[u8],
+    offset: usize,
+    magic: [u8; 4],
+) -> Result<Option<(usize, &[u8])>, Error> {
+    let bounds = record_bounds(slice, offset, magic, &[])?;
+    Ok(bounds.map(|(start, size)| (start, &slice[start..start + size])))
+}
+
+/// Returns the offset and the size of the content of the `magic | u32 size | content` record
+/// starting at `offset`, where magic and size are obfuscated with `key`, or `None` if the data is
+/// terminated by zero bytes.
+fn record_bounds(
+    slice: &[u8],
+    offset: usize,
+    magic: [u8; 4],
+    key: &[u8],
+) -> Result<Option<(usize, usize)>, Error> {
+    let remaining = &slice[offset..];
+    let mut head = [0u8; 8];
+    let available = remaining.len().min(8);
+    head[..available].copy_from_slice(&remaining[..available]);
+    deobfuscate(&mut head[..available], key, offset);
+    let zeros = |bytes: &[u8]| bytes.iter().take(4).all(|b| *b == 0);
+    if zeros(remaining) || zeros(&head[..available]) {
+        return Ok(None);
+    }
+    if available < 8 {
+        return Err(Error::MoreBytesNeeded);
+    }
+    if head[..4] != magic {
+        return Err(Error::UnexpectedMagic);
+    }
+    let size = read_u32(&head[4..]).expect("checked 8 bytes") as usize;
+    let end = 8usize.checked_add(size).ok_or(Error::MoreBytesNeeded)?;
+    if remaining.len() < end {
+        return Err(Error::MoreBytesNeeded);
+    }
+    Ok(Some((offset + 8, size)))
+}
+
+impl<'a> Iterator for BlockFile<'a> {
+    type Item = Result<(usize, Block<'a>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_block() {
+            Ok(Some(block)) => Some(Ok(block)),
+            Ok(None) => {
+                self.offset = self.slice.len();
+                None
+            }
+            Err(e) => {
+                self.offset = self.slice.len();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// XOR `data` in place with the obfuscation `key` as Bitcoin Core does, where `offset` is the
+/// position of the first byte of `data` in the file (or in the value for chainstate entries).
+///
+/// Since XOR is its own inverse, the same function obfuscates and deobfuscates. An empty key or a
+/// key of all zeros leave the data unchanged.
+pub fn deobfuscate(data: &mut [u8], key: &[u8], offset: usize) {
+    if key.is_empty() {
+        return;
+    }
+    for (i, byte) in data.iter_mut().enumerate() {
+        *byte ^= key[(offset + i) % key.len()];
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        bsl::{deobfuscate, p2p::magic, BlockFile, ObfuscatedBlockFile},
+        test_common::GENESIS_BLOCK,
+        Error,
+    };
+    use bitcoin_test_data::blocks::mainnet_702861;
+
+    fn blk_file(padding: usize) -> Vec<u8> {
+        let mut data = vec![];
+        for block in [&GENESIS_BLOCK[..], mainnet_702861()] {
+            data.extend(magic::MAINNET);
+            data.extend((block.len() as u32).to_le_bytes());
+            data.extend(block);
+        }
+        data.extend(vec![0u8; padding]);
+        data
+    }
+
+    fn check(data: &[u8]) {
+        let blocks: Vec<_> = BlockFile::new(data, magic::MAINNET)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].0, 8);
+        assert_eq!(blocks[0].1.as_ref(), &GENESIS_BLOCK[..]);
+        assert_eq!(blocks[1].0, 8 + GENESIS_BLOCK.len() + 8);
+        assert_eq!(blocks[1].1.as_ref(), mainnet_702861());
+        assert_eq!(blocks[1].1.total_transactions(), 2500);
+    }
+
+    #[test]
+    fn block_file() {
+        for padding in [0, 1, 3, 4, 9, 1000] {
+            check(&blk_file(padding));
+        }
+    }
+
+    #[test]
+    fn block_file_errors() {
+        let data = blk_file(0);
+        let mut iter = BlockFile::new(&data, magic::TESTNET3);
+        assert_eq!(iter.next(), Some(Err(Error::UnexpectedMagic)));
+        assert!(iter.next().is_none());
+
+        let mut iter = BlockFile::new(&data[..data.len() - 1], magic::MAINNET);
+        assert!(iter.next().unwrap().is_ok());
+        assert_eq!(iter.next(), Some(Err(Error::MoreBytesNeeded)));
+        assert!(iter.next().is_none());
+
+        let mut iter = BlockFile::new(&data[..GENESIS_BLOCK.len() + 13], magic::MAINNET);
+        assert!(iter.next().unwrap().is_ok());
+        assert_eq!(iter.next(), Some(Err(Error::MoreBytesNeeded)));
+
+        let mut data = data;
+        data[4] += 1; // size of the first block is one byte more
+        let mut iter = BlockFile::new(&data, magic::MAINNET);
+        assert_eq!(iter.next(), Some(Err(Error::BlockSizeMismatch)));
+    }
+
+    #[test]
+    fn obfuscated_block_file() {
+        let key = [0x12u8, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0];
+        let plain = blk_file(100);
+        let mut data = plain.clone();
+        deobfuscate(&mut data, &key, 0);
+        assert_ne!(data, plain);
+        assert_eq!(
+            BlockFile::new(&data, magic::MAINNET).next(),
+            Some(Err(Error::UnexpectedMagic))
+        );
+
+        // deobfuscating in two chunks with the right offset
+        let (first, second) = data.split_at_mut(13);
+        deobfuscate(first, &key, 0);
+        deobfuscate(second, &key, 13);
+        assert_eq!(data, plain);
+        check(&data);
+    }
+
+    #[test]
+    fn obfuscated_block_file_read_only() {
+        let key = [0x12u8, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0];
+        let plain = blk_file(0);
+        let mut obfuscated = plain.clone();
+        deobfuscate(&mut obfuscated, &key, 0);
+        let mut buffer = vec![0u8; 4_000_000];
+
+        // padding obfuscated with the rest of the data, or not obfuscated as pre-allocated
+        let mut padded = obfuscated.clone();
+        padded.extend(vec![0u8; 100]);
+        let mut padding_obfuscated = padded.clone();
+        deobfuscate(&mut padding_obfuscated[plain.len()..], &key, plain.len());
+
+        for data in [&obfuscated, &padded, &padding_obfuscated] {
+            let mut iter = ObfuscatedBlockFile::new(data, magic::MAINNET, &key);
+            let (offset, block) = iter.next_block(&mut buffer).unwrap().unwrap();
+            assert_eq!(offset, 8);
+            assert_eq!(block.as_ref(), &GENESIS_BLOCK[..]);
+            let (offset, block) = iter.next_block(&mut buffer).unwrap().unwrap();
+            assert_eq!(offset, 8 + GENESIS_BLOCK.len() + 8);
+            assert_eq!(block.as_ref(), mainnet_702861());
+            assert!(iter.next_block(&mut buffer).is_none());
+        }
+
+        let mut iter = ObfuscatedBlockFile::new(&obfuscated, magic::MAINNET, &key);
+        assert!(iter.next_block(&mut buffer[..285]).unwrap().is_ok());
+        assert_eq!(
+            iter.next_block(&mut buffer[..1000]),
+            Some(Err(Error::BufferTooSmall))
+        );
+        assert!(iter.next_block(&mut buffer).is_none());
+
+        let mut iter = ObfuscatedBlockFile::new(&plain, magic::MAINNET, &key);
+        assert_eq!(
+            iter.next_block(&mut buffer),
+            Some(Err(Error::UnexpectedMagic))
+        );
+    }
+
+    #[test]
+    fn huge_record_size() {
+        let mut data = magic::MAINNET.to_vec();
+        data.extend(u32::MAX.to_le_bytes());
+        data.extend(&GENESIS_BLOCK[..]);
+        let mut iter = BlockFile::new(&data, magic::MAINNET);
+        assert_eq!(iter.next(), Some(Err(Error::MoreBytesNeeded)));
+    }
+}
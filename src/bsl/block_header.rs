@@ -2,7 +2,7 @@ use core::ops::ControlFlow;
 
 use crate::{
     number::{read_i32, read_u32},
-    Error, ParseResult, SResult, Visit, Visitor,
+    Error, ParseResult, SResult, Visit, Visitor, U256,
 };
 
 /// The block header.
@@ -60,6 +60,44 @@ impl<'a> BlockHeader<'a> {
         self.time
     }
 
+    /// Returns the target of the proof of work in compact form, also known as `nBits`.
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    /// Returns the target of the proof of work, the hash of this block header must not be
+    /// greater than it. See [`U256::from_compact()`] for the errors.
+    pub fn target(&self) -> Result<U256, Error> {
+        U256::from_compact(self.bits)
+    }
+
+    /// Returns the work of this block header, the expected number of hashes needed to meet its
+    /// target. It is zero if the target is invalid, like in Bitcoin Core.
+    pub fn work(&self) -> U256 {
+        match self.target() {
+            Ok(target) if target != U256::ZERO => target.target_to_work(),
+            _ => U256::ZERO,
+        }
+    }
+
+    /// Check the hash of this block header is not greater than its target.
+    ///
+    /// Returns [`Error::InvalidTarget`] if the target is negative, overflowing or zero and
+    /// [`Error::InsufficientProofOfWork`] if the hash is greater than the target.
+    /// Note the target is not checked against the proof of work limit of the network.
+    #[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+    pub fn validate_pow(&self) -> Result<(), Error> {
+        let target = self.target()?;
+        if target == U256::ZERO {
+            return Err(Error::InvalidTarget);
+        }
+        let hash = U256::from_le_bytes(crate::hashes::sha256d(&[self.slice]));
+        if hash > target {
+            return Err(Error::InsufficientProofOfWork);
+        }
+        Ok(())
+    }
+
     /// Returns the nonce of this block header.
     pub fn nonce(&self) -> u32 {
         self.nonce
@@ -138,6 +176,63 @@ mod test {
         );
     }
 
+    #[test]
+    fn pow() {
+        let block_header = BlockHeader::parse(&GENESIS_BLOCK_HEADER)
+            .unwrap()
+            .parsed_owned();
+        assert_eq!(block_header.bits(), 0x1d00ffff);
+        assert_eq!(
+            block_header.target().unwrap().to_be_bytes(),
+            hex!("00000000ffff0000000000000000000000000000000000000000000000000000")
+        );
+        assert_eq!(block_header.work().to_u128(), Some(0x100010001));
+
+        let header: bitcoin::block::Header =
+            bitcoin::consensus::deserialize(&bitcoin_test_data::blocks::mainnet_702861()[..80])
+                .unwrap();
+        let block_header = BlockHeader::parse(&bitcoin_test_data::blocks::mainnet_702861()[..80])
+            .unwrap()
+            .parsed_owned();
+        assert_eq!(
+            block_header.work().to_le_bytes(),
+            header.work().to_le_bytes()
+        );
+        assert_eq!(
+            block_header.target().unwrap().to_le_bytes(),
+            header.target().to_le_bytes()
+        );
+    }
+
+    #[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+    #[test]
+    fn validate_pow() {
+        use crate::Error;
+
+        BlockHeader::parse(&GENESIS_BLOCK_HEADER)
+            .unwrap()
+            .parsed()
+            .validate_pow()
+            .unwrap();
+
+        let mut header = GENESIS_BLOCK_HEADER;
+        header[76] ^= 1; // nonce
+        let block_header = BlockHeader::parse(&header).unwrap().parsed_owned();
+        assert_eq!(
+            block_header.validate_pow(),
+            Err(Error::InsufficientProofOfWork)
+        );
+
+        header[72..76].copy_from_slice(&0x04923456u32.to_le_bytes()); // negative target
+        let block_header = BlockHeader::parse(&header).unwrap().parsed_owned();
+        assert_eq!(block_header.validate_pow(), Err(Error::InvalidTarget));
+        assert_eq!(block_header.work(), crate::U256::ZERO);
+
+        header[72..76].copy_from_slice(&0u32.to_le_bytes());
+        let block_header = BlockHeader::parse(&header).unwrap().parsed_owned();
+        assert_eq!(block_header.validate_pow(), Err(Error::InvalidTarget));
+    }
+
     #[cfg(target_pointer_width = "64")]
     #[test]
     fn size_of() {
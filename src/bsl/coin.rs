@@ -0,0 +1,196 @@
+use crate::{
+    bsl::{scan_len, CompressedScript, CompressedTxOut, SPECIAL_SCRIPT_MAX_LEN},
+    number::scan_var_int,
+    slice::split_at_checked,
+    Error, Parse, ParseResult, SResult,
+};
+
+/// An unspent transaction output as stored by Bitcoin Core in the chainstate database, the value
+/// of the entries whose key is a [`CoinKey`].
+///
+/// It contains the height of the block creating the output, whether it was created by a coinbase
+/// and the output itself, compressed as a [`CompressedTxOut`].
+///
+/// Values in the chainstate database are obfuscated, use [`crate::bsl::deobfuscate`] with offset
+/// `0` and the key returned by [`obfuscation_key`] before parsing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coin<'a> {
+    slice: &'a [u8],
+    code: u32,
+    tx_out: CompressedTxOut<'a>,
+}
+
+impl<'a> Parse<'a> for Coin<'a> {
+    #[inline(always)]
+    fn parse(slice: &'a [u8]) -> SResult<Self> {
+        let mut consumed = 0;
+        let code = scan_var_int(slice, &mut consumed)?;
+        let code = u32::try_from(code).map_err(|_| Error::VarIntOverflow)?;
+        let tx_out = CompressedTxOut::parse(&slice[consumed..])?;
+        consumed += tx_out.consumed();
+        Ok(ParseResult::new(
+            tx_out.remaining(),
+            Coin {
+                slice: &slice[..consumed],
+                code,
+                tx_out: tx_out.parsed_owned(),
+            },
+        ))
+    }
+}
+
+impl<'a> Coin<'a> {
+    /// Returns the height of the block containing the transaction creating this output
+    pub fn height(&self) -> u32 {
+        self.code >> 1
+    }
+    /// Returns `true` if this output was created by a coinbase transaction
+    pub fn is_coinbase(&self) -> bool {
+        self.code & 1 == 1
+    }
+    /// Returns the amount of this output (satoshi)
+    pub fn value(&self) -> u64 {
+        self.tx_out.value()
+    }
+    /// Returns the script pubkey of this output, see [`CompressedScript::script_pubkey()`]
+    pub fn script_pubkey<'b>(
+        &'b self,
+        buffer: &'b mut [u8; SPECIAL_SCRIPT_MAX_LEN],
+    ) -> Result<&'b [u8], Error> {
+        self.tx_out.script_pubkey(buffer)
+    }
+    /// Returns the compressed script pubkey of this output
+    pub fn compressed_script(&self) -> &CompressedScript<'a> {
+        self.tx_out.compressed_script()
+    }
+}
+
+impl<'a> AsRef<[u8]> for Coin<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.slice
+    }
+}
+
+/// The key of a [`Coin`] in the Bitcoin Core chainstate database: the `C` prefix, the txid and
+/// the output index encoded as VARINT.
+///
+/// Keys are not obfuscated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoinKey<'a> {
+    slice: &'a [u8],
+    vout: u32,
+}
+
+impl<'a> Parse<'a> for CoinKey<'a> {
+    #[inline(always)]
+    fn parse(slice: &'a [u8]) -> SResult<Self> {
+        let (prefix_txid, _) = split_at_checked(slice, 33)?;
+        if prefix_txid[0] != b'C' {
+            return Err(Error::UnexpectedPrefix);
+        }
+        let mut consumed = 33;
+        let vout = scan_var_int(&slice[33..], &mut consumed)?;
+        let vout = u32::try_from(vout).map_err(|_| Error::VarIntOverflow)?;
+        let (slice, remaining) = slice.split_at(consumed);
+        Ok(ParseResult::new(remaining, CoinKey { slice, vout }))
+    }
+}
+
+impl<'a> CoinKey<'a> {
+    /// Returns the txid of the transaction creating the output
+    pub fn txid(&self) -> &'a [u8] {
+        &self.slice[1..33]
+    }
+    /// Returns the index of the output in the transaction creating it
+    pub fn vout(&self) -> u32 {
+        self.vout
+    }
+}
+
+impl<'a> AsRef<[u8]> for CoinKey<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.slice
+    }
+}
+
+/// Key of the chainstate database entry containing the obfuscation key
+pub const OBFUSCATION_KEY_KEY: &[u8] = b"\x0e\x00obfuscate_key";
+
+/// Returns the obfuscation key contained in `value`, the value of the chainstate database entry
+/// with key [`OBFUSCATION_KEY_KEY`].
+pub fn obfuscation_key(value: &[u8]) -> Result<&[u8], Error> {
+    let mut consumed = 0;
+    let len = scan_len(value, &mut consumed)? as usize;
+    let (key, _) = split_at_checked(&value[consumed..], len)?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{obfuscation_key, Coin, CoinKey};
+    use crate::{
+        bsl::{
+            compressed::test::{compress_amount, var_int},
+            deobfuscate, SPECIAL_SCRIPT_MAX_LEN,
+        },
+        Error, Parse,
+    };
+    use hex_lit::hex;
+
+    #[test]
+    fn parse_coin() {
+        let script = hex!("5120a60869f0dbcf1dc659c9cecbaf8050135ea9e8cdc487053f1dc6880949dc684c");
+        let mut value = var_int((840_000 << 1) | 1);
+        value.extend(var_int(compress_amount(3_1250_0000)));
+        value.extend(var_int(script.len() as u64 + 6));
+        value.extend(script);
+
+        let key = obfuscation_key(&hex!("08b12dcefd8f872536")).unwrap();
+        assert_eq!(key, hex!("b12dcefd8f872536"));
+        let mut obfuscated = value.clone();
+        deobfuscate(&mut obfuscated, key, 0);
+        assert_ne!(obfuscated, value);
+        deobfuscate(&mut obfuscated, key, 0);
+
+        let coin = Coin::parse(&obfuscated).unwrap();
+        assert_eq!(coin.consumed(), value.len());
+        let coin = coin.parsed();
+        assert_eq!(coin.height(), 840_000);
+        assert!(coin.is_coinbase());
+        assert_eq!(coin.value(), 3_1250_0000);
+        let mut buffer = [0u8; SPECIAL_SCRIPT_MAX_LEN];
+        assert_eq!(coin.script_pubkey(&mut buffer).unwrap(), &script[..]);
+        assert!(!coin.compressed_script().is_special());
+
+        assert_eq!(
+            Coin::parse(&value[..value.len() - 1]),
+            Err(Error::MoreBytesNeeded)
+        );
+    }
+
+    #[test]
+    fn parse_coin_key() {
+        let mut key = vec![b'C'];
+        key.extend([0xABu8; 32]);
+        key.extend(var_int(300));
+        let coin_key = CoinKey::parse(&key).unwrap();
+        assert_eq!(coin_key.consumed(), key.len());
+        assert_eq!(coin_key.parsed().txid(), &[0xABu8; 32]);
+        assert_eq!(coin_key.parsed().vout(), 300);
+
+        // the txid borrows the key bytes, not the temporary CoinKey
+        let txid = CoinKey::parse(&key).unwrap().parsed_owned().txid();
+        assert_eq!(txid, &[0xABu8; 32]);
+
+        assert_eq!(CoinKey::parse(&key[..33]), Err(Error::MoreBytesNeeded));
+        key[0] = b'B';
+        assert_eq!(CoinKey::parse(&key), Err(Error::UnexpectedPrefix));
+        assert_eq!(obfuscation_key(&[8, 0]), Err(Error::MoreBytesNeeded));
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn size_of() {
+        assert_eq!(std::mem::size_of::<Coin>(), 80);
+    }
+}
@@ -0,0 +1,349 @@
+use crate::{number::scan_var_int, slice::split_at_checked, Error, Parse, ParseResult, SResult};
+
+/// Maximum length of a script decompressed from one of the special forms of [`CompressedScript`],
+/// which is the length of a pay to uncompressed public key script.
+pub const SPECIAL_SCRIPT_MAX_LEN: usize = 67;
+
+/// Number of special script forms, sizes below this value encode a special form.
+const SPECIAL_SCRIPTS: u64 = 6;
+
+/// Maximum size of a script, bigger scripts are replaced with `OP_RETURN`.
+const MAX_SCRIPT_SIZE: usize = 10_000;
+
+/// A script pubkey compressed as Bitcoin Core does in its on-disk formats (undo data and
+/// chainstate).
+///
+/// It begins with a VARINT, values from 0 to 5 identify special forms of common scripts:
+///
+/// * 0: pay to public key hash, followed by the 20 bytes hash
+/// * 1: pay to script hash, followed by the 20 bytes hash
+/// * 2, 3: pay to compressed public key, followed by the 32 bytes x coordinate
+/// * 4, 5: pay to uncompressed public key, followed by the 32 bytes x coordinate
+///
+/// Other values are followed by the script of length `value - 6`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressedScript<'a> {
+    slice: &'a [u8],
+    kind: u64,
+    from: usize,
+}
+
+impl<'a> Parse<'a> for CompressedScript<'a> {
+    #[inline(always)]
+    fn parse(slice: &'a [u8]) -> SResult<Self> {
+        let mut consumed = 0;
+        let kind = scan_var_int(slice, &mut consumed)?;
+        if kind > u32::MAX as u64 {
+            return Err(Error::VarIntOverflow);
+        }
+        let len = match kind {
+            0 | 1 => 20,
+            2..=5 => 32,
+            _ => (kind - SPECIAL_SCRIPTS) as usize,
+        };
+        let (slice, remaining) = split_at_checked(slice, consumed.saturating_add(len))?;
+        Ok(ParseResult::new(
+            remaining,
+            CompressedScript {
+                slice,
+                kind,
+                from: consumed,
+            },
+        ))
+    }
+}
+
+impl<'a> CompressedScript<'a> {
+    /// Returns `true` if the script is compressed in one of the special forms, thus
+    /// [`CompressedScript::script_pubkey()`] needs to write in the given buffer.
+    pub fn is_special(&self) -> bool {
+        self.kind < SPECIAL_SCRIPTS
+    }
+
+    /// Returns the decompressed script pubkey.
+    ///
+    /// Special forms are decompressed in the given `buffer`, while other scripts are returned
+    /// without copying. Decompressing a pay to uncompressed public key requires elliptic curve
+    /// operations and returns [`Error::InvalidPublicKey`] if the stored key is not valid.
+    pub fn script_pubkey<'b>(
+        &'b self,
+        buffer: &'b mut [u8; SPECIAL_SCRIPT_MAX_LEN],
+    ) -> Result<&'b [u8], Error> {
+        let data = &self.slice[self.from..];
+        let len = match self.kind {
+            0 => {
+                // OP_DUP OP_HASH160 <20 bytes> OP_EQUALVERIFY OP_CHECKSIG
+                buffer[..3].copy_from_slice(&[0x76, 0xa9, 20]);
+                buffer[3..23].copy_from_slice(data);
+                buffer[23..25].copy_from_slice(&[0x88, 0xac]);
+                25
+            }
+            1 => {
+                // OP_HASH160 <20 bytes> OP_EQUAL
+                buffer[..2].copy_from_slice(&[0xa9, 20]);
+                buffer[2..22].copy_from_slice(data);
+                buffer[22] = 0x87;
+                23
+            }
+            2 | 3 => {
+                // <33 bytes compressed key> OP_CHECKSIG
+                buffer[0] = 33;
+                buffer[1] = self.kind as u8;
+                buffer[2..34].copy_from_slice(data);
+                buffer[34] = 0xac;
+                35
+            }
+            4 | 5 => {
+                // <65 bytes uncompressed key> OP_CHECKSIG
+                let mut compressed = [0u8; 33];
+                compressed[0] = self.kind as u8 - 2;
+                compressed[1..].copy_from_slice(data);
+                let uncompressed = crate::secp256k1::decompress_pubkey(&compressed)
+                    .ok_or(Error::InvalidPublicKey)?;
+                buffer[0] = 65;
+                buffer[1..66].copy_from_slice(&uncompressed);
+                buffer[66] = 0xac;
+                67
+            }
+            _ if data.len() > MAX_SCRIPT_SIZE => {
+                // like Bitcoin Core, overly long scripts are replaced with OP_RETURN
+                buffer[0] = 0x6a;
+                1
+            }
+            _ => return Ok(data),
+        };
+        Ok(&buffer[..len])
+    }
+}
+
+impl<'a> AsRef<[u8]> for CompressedScript<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.slice
+    }
+}
+
+/// A transaction output compressed as Bitcoin Core does in its on-disk formats: the amount
+/// compressed and encoded as VARINT followed by the [`CompressedScript`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressedTxOut<'a> {
+    slice: &'a [u8],
+    value: u64,
+    script_pubkey: CompressedScript<'a>,
+}
+
+impl<'a> Parse<'a> for CompressedTxOut<'a> {
+    #[inline(always)]
+    fn parse(slice: &'a [u8]) -> SResult<Self> {
+        let mut consumed = 0;
+        let value = decompress_amount(scan_var_int(slice, &mut consumed)?);
+        let script = CompressedScript::parse(&slice[consumed..])?;
+        consumed += script.consumed();
+        Ok(ParseResult::new(
+            script.remaining(),
+            CompressedTxOut {
+                slice: &slice[..consumed],
+                value,
+                script_pubkey: script.parsed_owned(),
+            },
+        ))
+    }
+}
+
+impl<'a> CompressedTxOut<'a> {
+    /// Return the amount of this output (satoshi)
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+    /// Return the decompressed script pubkey of this output, see
+    /// [`CompressedScript::script_pubkey()`]
+    pub fn script_pubkey<'b>(
+        &'b self,
+        buffer: &'b mut [u8; SPECIAL_SCRIPT_MAX_LEN],
+    ) -> Result<&'b [u8], Error> {
+        self.script_pubkey.script_pubkey(buffer)
+    }
+    /// Return the compressed script pubkey of this output
+    pub fn compressed_script(&self) -> &CompressedScript<'a> {
+        &self.script_pubkey
+    }
+}
+
+impl<'a> AsRef<[u8]> for CompressedTxOut<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.slice
+    }
+}
+
+/// Decompress an amount compressed with Bitcoin Core `CompressAmount`, which exploits the fact
+/// that amounts are usually round numbers.
+pub(crate) fn decompress_amount(x: u64) -> u64 {
+    if x == 0 {
+        return 0;
+    }
+    let mut x = x - 1;
+    let mut e = x % 10;
+    x /= 10;
+    let mut n = if e < 9 {
+        let d = (x % 9) + 1;
+        x /= 9;
+        x.wrapping_mul(10).wrapping_add(d)
+    } else {
+        x.wrapping_add(1)
+    };
+    while e > 0 {
+        n = n.wrapping_mul(10);
+        e -= 1;
+    }
+    n
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::{decompress_amount, CompressedScript, CompressedTxOut, SPECIAL_SCRIPT_MAX_LEN};
+    use crate::{Error, Parse};
+    use bitcoin::hashes::Hash;
+    use hex_lit::hex;
+
+    /// Bitcoin Core `CompressAmount`, used to create test vectors
+    pub(crate) fn compress_amount(mut n: u64) -> u64 {
+        if n == 0 {
+            return 0;
+        }
+        let mut e = 0;
+        while (n % 10) == 0 && e < 9 {
+            n /= 10;
+            e += 1;
+        }
+        if e < 9 {
+            let d = n % 10;
+            n /= 10;
+            1 + (n * 9 + d - 1) * 10 + e
+        } else {
+            1 + (n - 1) * 10 + 9
+        }
+    }
+
+    /// Bitcoin Core VARINT encoding, used to create test vectors
+    pub(crate) fn var_int(mut n: u64) -> Vec<u8> {
+        let mut tmp = vec![];
+        loop {
+            tmp.push((n & 0x7F) as u8 | if tmp.is_empty() { 0x00 } else { 0x80 });
+            if n <= 0x7F {
+                break;
+            }
+            n = (n >> 7) - 1;
+        }
+        tmp.reverse();
+        tmp
+    }
+
+    #[test]
+    fn amounts() {
+        // from bitcoin core compress_tests
+        const COIN: u64 = 100_000_000;
+        for (amount, compressed) in [
+            (0, 0x0),
+            (1, 0x1),
+            (1_000_000, 0x7),
+            (COIN, 0x9),
+            (50 * COIN, 0x32),
+            (21_000_000 * COIN, 0x1406f40),
+        ] {
+            assert_eq!(decompress_amount(compressed), amount);
+            assert_eq!(compress_amount(amount), compressed);
+        }
+        for amount in (0..100_000u64).chain((0..100_000).map(|i| i * 1_000_000 + 7)) {
+            assert_eq!(decompress_amount(compress_amount(amount)), amount);
+        }
+    }
+
+    #[test]
+    fn special_scripts() {
+        let mut buffer = [0u8; SPECIAL_SCRIPT_MAX_LEN];
+        let hash = [0x11u8; 20];
+
+        let bytes = [&[0u8][..], &hash[..]].concat();
+        let script = CompressedScript::parse(&bytes).unwrap();
+        assert_eq!(script.remaining(), &[]);
+        assert!(script.parsed().is_special());
+        let p2pkh = bitcoin::ScriptBuf::new_p2pkh(&bitcoin::PubkeyHash::from_byte_array(hash));
+        assert_eq!(
+            script.parsed().script_pubkey(&mut buffer).unwrap(),
+            p2pkh.as_bytes()
+        );
+
+        let bytes = [&[1u8][..], &hash[..]].concat();
+        let script = CompressedScript::parse(&bytes).unwrap().parsed_owned();
+        let p2sh = bitcoin::ScriptBuf::new_p2sh(&bitcoin::ScriptHash::from_byte_array(hash));
+        assert_eq!(script.script_pubkey(&mut buffer).unwrap(), p2sh.as_bytes());
+
+        let secp = bitcoin::secp256k1::Secp256k1::new();
+        let secret = bitcoin::secp256k1::SecretKey::from_slice(&[3u8; 32]).unwrap();
+        let public = bitcoin::secp256k1::PublicKey::from_secret_key(&secp, &secret);
+        let compressed = public.serialize();
+        for key in [
+            bitcoin::PublicKey::new(public),
+            bitcoin::PublicKey::new_uncompressed(public),
+        ] {
+            let kind = compressed[0] + if key.compressed { 0 } else { 2 };
+            let bytes = [&[kind][..], &compressed[1..]].concat();
+            let script = CompressedScript::parse(&bytes).unwrap().parsed_owned();
+            let p2pk = bitcoin::ScriptBuf::new_p2pk(&key);
+            assert_eq!(script.script_pubkey(&mut buffer).unwrap(), p2pk.as_bytes());
+        }
+
+        let bytes = hex!("04ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff");
+        let script = CompressedScript::parse(&bytes).unwrap().parsed_owned();
+        assert_eq!(
+            script.script_pubkey(&mut buffer),
+            Err(Error::InvalidPublicKey)
+        );
+
+        assert_eq!(
+            CompressedScript::parse(&bytes[..32]),
+            Err(Error::MoreBytesNeeded)
+        );
+    }
+
+    #[test]
+    fn other_scripts() {
+        let mut buffer = [0u8; SPECIAL_SCRIPT_MAX_LEN];
+        let script_bytes = hex!("0014751e76e8199196d454941c45d1b3a323f1433bd6");
+        let bytes = [
+            &var_int(script_bytes.len() as u64 + 6)[..],
+            &script_bytes[..],
+        ]
+        .concat();
+        let script = CompressedScript::parse(&bytes).unwrap().parsed_owned();
+        assert!(!script.is_special());
+        assert_eq!(
+            script.script_pubkey(&mut buffer).unwrap(),
+            &script_bytes[..]
+        );
+
+        let long_script = vec![0x51u8; 10_001];
+        let bytes = [&var_int(long_script.len() as u64 + 6)[..], &long_script[..]].concat();
+        let script = CompressedScript::parse(&bytes).unwrap();
+        assert_eq!(script.consumed(), bytes.len());
+        assert_eq!(script.parsed().script_pubkey(&mut buffer).unwrap(), &[0x6a]);
+
+        assert_eq!(
+            CompressedScript::parse(&bytes[..bytes.len() - 1]),
+            Err(Error::MoreBytesNeeded)
+        );
+    }
+
+    #[test]
+    fn compressed_tx_out() {
+        let mut buffer = [0u8; SPECIAL_SCRIPT_MAX_LEN];
+        let mut bytes = var_int(compress_amount(50_0000_0000));
+        bytes.push(0);
+        bytes.extend([0x22u8; 20]);
+        bytes.push(0xFF);
+        let tx_out = CompressedTxOut::parse(&bytes).unwrap();
+        assert_eq!(tx_out.remaining(), &[0xFF]);
+        let tx_out = tx_out.parsed_owned();
+        assert_eq!(tx_out.value(), 50_0000_0000);
+        assert_eq!(tx_out.script_pubkey(&mut buffer).unwrap().len(), 25);
+    }
+}
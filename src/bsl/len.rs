@@ -85,6 +85,26 @@ pub fn scan_len(slice: &[u8], consumed: &mut usize) -> Result<u64, Error> {
     }
 }
 
+/// Returns the length of the compact size encoding `n`
+pub(crate) fn compact_size_len(n: usize) -> usize {
+    match n {
+        0..=0xfc => 1,
+        0xfd..=0xffff => 3,
+        0x10000..=0xffff_ffff => 5,
+        _ => 9,
+    }
+}
+
+/// Returns the length of the compact size starting with `first`
+pub(crate) fn compact_size_len_of_prefix(first: &u8) -> usize {
+    match first {
+        0xfd => 3,
+        0xfe => 5,
+        0xff => 9,
+        _ => 1,
+    }
+}
+
 impl Len {
     /// The value encoded in this compact int
     pub fn n(&self) -> u64 {
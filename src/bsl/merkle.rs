@@ -0,0 +1,286 @@
+use core::ops::ControlFlow;
+
+use crate::{
+    bsl::{BlockHeader, Transaction, TxOut},
+    hashes::sha256d,
+    Error, Visitor,
+};
+
+/// Prefix of the script pubkey of the coinbase output containing the witness commitment:
+/// `OP_RETURN`, a push of 36 bytes and the commitment header `0xaa21a9ed`.
+const WITNESS_COMMITMENT_PREFIX: [u8; 6] = [0x6a, 0x24, 0xaa, 0x21, 0xa9, 0xed];
+
+/// Computes a merkle root adding one leaf at a time, as the Bitcoin Core `MerkleComputation`.
+///
+/// Only the pending inner hashes of the rightmost branch are kept, thus 32 levels are enough for
+/// any number of leaves representable with a `u32` and no allocation is needed.
+#[derive(Debug, Clone)]
+pub(crate) struct MerkleComputation {
+    inner: [[u8; 32]; 32],
+    count: u32,
+    mutated: bool,
+}
+
+impl MerkleComputation {
+    pub(crate) fn new() -> Self {
+        MerkleComputation {
+            inner: [[0u8; 32]; 32],
+            count: 0,
+            mutated: false,
+        }
+    }
+
+    pub(crate) fn push(&mut self, leaf: [u8; 32]) {
+        let mut hash = leaf;
+        self.count += 1;
+        let mut level = 0;
+        while self.count & (1 << level) == 0 {
+            self.mutated |= self.inner[level] == hash;
+            hash = sha256d(&[&self.inner[level], &hash]);
+            level += 1;
+        }
+        self.inner[level] = hash;
+    }
+
+    /// Returns the merkle root and whether two identical hashes were combined, which happens in
+    /// trees mutated as in CVE-2012-2459. An empty tree has a root of zeros.
+    pub(crate) fn finalize(&self) -> ([u8; 32], bool) {
+        if self.count == 0 {
+            return ([0u8; 32], false);
+        }
+        let mut count = self.count;
+        let mut level = count.trailing_zeros() as usize;
+        let mut hash = self.inner[level];
+        while count != 1 << level {
+            // odd number of elements at this level, combine the hash with itself
+            hash = sha256d(&[&hash, &hash]);
+            count += 1 << level;
+            level += 1;
+            while count & (1 << level) == 0 {
+                hash = sha256d(&[&self.inner[level], &hash]);
+                level += 1;
+            }
+        }
+        (hash, self.mutated)
+    }
+}
+
+/// A visitor computing the merkle root of the txids and of the wtxids of a block, to be verified
+/// against the block header with [`MerkleVerifier::verify()`] after visiting the block.
+///
+/// See [`crate::bsl::Block::verify_merkle()`] for a ready-made verification.
+#[derive(Debug, Clone)]
+pub struct MerkleVerifier {
+    txids: MerkleComputation,
+    wtxids: MerkleComputation,
+    /// Number of transactions completely visited
+    tx_count: usize,
+    witness_commitment: Option<[u8; 32]>,
+    /// The witness of the coinbase if it has a single element of 32 bytes
+    witness_reserved_value: Option<[u8; 32]>,
+    coinbase_witness_elements: usize,
+    has_witness: bool,
+}
+
+impl Default for MerkleVerifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MerkleVerifier {
+    /// Creates a verifier to be used as visitor of a single block
+    pub fn new() -> Self {
+        MerkleVerifier {
+            txids: MerkleComputation::new(),
+            wtxids: MerkleComputation::new(),
+            tx_count: 0,
+            witness_commitment: None,
+            witness_reserved_value: None,
+            coinbase_witness_elements: 0,
+            has_witness: false,
+        }
+    }
+
+    /// Returns the merkle root of the txids visited
+    pub fn merkle_root(&self) -> [u8; 32] {
+        self.txids.finalize().0
+    }
+
+    /// Returns the merkle root of the wtxids visited, where the coinbase wtxid is replaced with
+    /// zeros as specified in BIP141
+    pub fn witness_root(&self) -> [u8; 32] {
+        self.wtxids.finalize().0
+    }
+
+    /// Verify the visited transactions against the merkle root in `header`, the tree is not
+    /// mutated (CVE-2012-2459) and the witness commitment in the coinbase, if any, is valid.
+    ///
+    /// Returns [`Error::MutatedMerkleTree`], [`Error::MerkleRootMismatch`] or
+    /// [`Error::WitnessCommitmentMismatch`] respectively. The last one is returned also if
+    /// transactions have witnesses but the coinbase contains no witness commitment.
+    pub fn verify(&self, header: &BlockHeader) -> Result<(), Error> {
+        let (merkle_root, mutated) = self.txids.finalize();
+        if mutated {
+            return Err(Error::MutatedMerkleTree);
+        }
+        if header.merkle_root() != merkle_root {
+            return Err(Error::MerkleRootMismatch);
+        }
+        match self.witness_commitment {
+            Some(commitment) => {
+                let reserved_value = match self.witness_reserved_value {
+                    Some(value) if self.coinbase_witness_elements == 1 => value,
+                    _ => return Err(Error::WitnessCommitmentMismatch),
+                };
+                if sha256d(&[&self.witness_root(), &reserved_value]) != commitment {
+                    return Err(Error::WitnessCommitmentMismatch);
+                }
+            }
+            None if self.has_witness => return Err(Error::WitnessCommitmentMismatch),
+            None => (),
+        }
+        Ok(())
+    }
+}
+
+impl Visitor for MerkleVerifier {
+    fn visit_transaction(&mut self, tx: &Transaction) -> ControlFlow<()> {
+        let (a, b, c) = tx.txid_preimage();
+        self.txids.push(sha256d(&[a, b, c]));
+        let wtxid = if self.tx_count == 0 {
+            [0u8; 32]
+        } else {
+            sha256d(&[tx.wtxid_preimage()])
+        };
+        self.wtxids.push(wtxid);
+        self.tx_count += 1;
+        ControlFlow::Continue(())
+    }
+
+    fn visit_tx_out(&mut self, _vout: usize, tx_out: &TxOut) -> ControlFlow<()> {
+        let script = tx_out.script_pubkey();
+        if self.tx_count == 0
+            && script.len() >= 38
+            && script.starts_with(&WITNESS_COMMITMENT_PREFIX)
+        {
+            // if more outputs match, the last one is the commitment
+            let commitment = script[6..38].try_into().expect("checked length");
+            self.witness_commitment = Some(commitment);
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn visit_witness(&mut self, _vin: usize) -> ControlFlow<()> {
+        self.has_witness = true;
+        ControlFlow::Continue(())
+    }
+
+    fn visit_witness_total_element(&mut self, witness_total: usize) {
+        if self.tx_count == 0 {
+            self.coinbase_witness_elements = witness_total;
+        }
+    }
+
+    fn visit_witness_element(&mut self, _witness_i: usize, witness_element: &[u8]) {
+        if self.tx_count == 0 {
+            self.witness_reserved_value = witness_element.try_into().ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MerkleComputation, MerkleVerifier};
+    use crate::{bsl::Block, test_common::GENESIS_BLOCK, Error, Parse};
+    use bitcoin::{consensus::serialize, hashes::Hash};
+    use bitcoin_test_data::blocks::mainnet_702861;
+
+    #[test]
+    fn merkle_computation() {
+        let block: bitcoin::Block = bitcoin::consensus::deserialize(mainnet_702861()).unwrap();
+        for n in [1, 2, 3, 5, 8, 13, 100] {
+            let txids = block.txdata[..n].iter().map(|tx| tx.compute_txid());
+            let expected = bitcoin::merkle_tree::calculate_root(txids.clone()).unwrap();
+            let mut computation = MerkleComputation::new();
+            txids.for_each(|txid| computation.push(txid.to_byte_array()));
+            assert_eq!(computation.finalize(), (expected.to_byte_array(), false));
+        }
+        assert_eq!(MerkleComputation::new().finalize(), ([0u8; 32], false));
+    }
+
+    #[test]
+    fn verify_merkle() {
+        Block::parse(&GENESIS_BLOCK)
+            .unwrap()
+            .parsed()
+            .verify_merkle()
+            .unwrap();
+        let block = Block::parse(mainnet_702861()).unwrap().parsed_owned();
+        block.verify_merkle().unwrap();
+
+        let mut visitor = MerkleVerifier::new();
+        crate::Visit::self_visit(&block, &mut visitor).unwrap();
+        assert_eq!(visitor.merkle_root(), block.header().merkle_root());
+        let bitcoin_block: bitcoin::Block =
+            bitcoin::consensus::deserialize(mainnet_702861()).unwrap();
+        let witness_root = bitcoin_block.witness_root().unwrap();
+        assert_eq!(visitor.witness_root(), witness_root.to_byte_array());
+    }
+
+    #[test]
+    fn verify_merkle_errors() {
+        let block: bitcoin::Block = bitcoin::consensus::deserialize(mainnet_702861()).unwrap();
+
+        // a different transaction
+        let mut changed = block.clone();
+        changed.txdata[1].lock_time = bitcoin::absolute::LockTime::from_consensus(1);
+        let bytes = serialize(&changed);
+        let parsed = Block::parse(&bytes).unwrap().parsed_owned();
+        assert_eq!(parsed.verify_merkle(), Err(Error::MerkleRootMismatch));
+
+        // CVE-2012-2459, duplicating the last transactions gives the same merkle root
+        let mut mutated = block.clone();
+        mutated.txdata.truncate(3);
+        mutated.header.merkle_root = mutated.compute_merkle_root().unwrap();
+        let tx = mutated.txdata[2].clone();
+        mutated.txdata.push(tx);
+        assert_eq!(
+            mutated.compute_merkle_root(),
+            Some(mutated.header.merkle_root)
+        );
+        let bytes = serialize(&mutated);
+        let parsed = Block::parse(&bytes).unwrap().parsed_owned();
+        assert_eq!(parsed.verify_merkle(), Err(Error::MutatedMerkleTree));
+
+        // a different witness
+        let mut changed = block.clone();
+        let i = changed
+            .txdata
+            .iter()
+            .position(|tx| !tx.input[0].witness.is_empty())
+            .unwrap();
+        changed.txdata[i].input[0].witness.push([1u8]);
+        let bytes = serialize(&changed);
+        let parsed = Block::parse(&bytes).unwrap().parsed_owned();
+        assert_eq!(
+            parsed.verify_merkle(),
+            Err(Error::WitnessCommitmentMismatch)
+        );
+
+        // witness without commitment
+        let mut changed = block.clone();
+        changed.txdata[0].output.retain(|o| {
+            !o.script_pubkey
+                .as_bytes()
+                .starts_with(&super::WITNESS_COMMITMENT_PREFIX)
+        });
+        changed.header.merkle_root = changed.compute_merkle_root().unwrap();
+        let bytes = serialize(&changed);
+        let parsed = Block::parse(&bytes).unwrap().parsed_owned();
+        assert_eq!(
+            parsed.verify_merkle(),
+            Err(Error::WitnessCommitmentMismatch)
+        );
+    }
+}
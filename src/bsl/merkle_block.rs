@@ -0,0 +1,404 @@
+use crate::{
+    bsl::{scan_len, BlockHeader},
+    number::read_u32,
+    slice::split_at_checked,
+    Error, Parse, ParseResult, SResult,
+};
+
+#[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+use crate::Visitor;
+#[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+use core::ops::ControlFlow;
+
+#[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+/// Maximum number of transactions in a block, given the maximum block weight and the minimum
+/// transaction weight.
+const MAX_TRANSACTIONS: u32 = 4_000_000 / 240;
+
+/// A merkle block: a block header and a partial merkle tree proving the inclusion of some
+/// transactions in the block.
+///
+/// It is the payload of the `merkleblock` p2p message and the result of the Bitcoin Core
+/// `gettxoutproof` RPC. The proof is verified with [`MerkleBlock::verify()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleBlock<'a> {
+    slice: &'a [u8],
+    header: BlockHeader<'a>,
+    hashes_from: usize,
+    total_hashes: usize,
+    flags_from: usize,
+}
+
+impl<'a> Parse<'a> for MerkleBlock<'a> {
+    #[inline(always)]
+    fn parse(slice: &'a [u8]) -> SResult<Self> {
+        let header = BlockHeader::parse(slice)?;
+        read_u32(header.remaining())?;
+        let mut consumed = 84;
+        let total_hashes = scan_len(&slice[consumed..], &mut consumed)? as usize;
+        let hashes_from = consumed;
+        consumed = total_hashes
+            .checked_mul(32)
+            .and_then(|len| len.checked_add(consumed))
+            .ok_or(Error::MoreBytesNeeded)?;
+        if consumed > slice.len() {
+            return Err(Error::MoreBytesNeeded);
+        }
+        let total_flags = scan_len(&slice[consumed..], &mut consumed)? as usize;
+        let flags_from = consumed;
+        let (slice, remaining) = split_at_checked(slice, consumed.saturating_add(total_flags))?;
+        Ok(ParseResult::new(
+            remaining,
+            MerkleBlock {
+                slice,
+                header: header.parsed_owned(),
+                hashes_from,
+                total_hashes,
+                flags_from,
+            },
+        ))
+    }
+}
+
+impl<'a> MerkleBlock<'a> {
+    /// Returns the header of the block
+    pub fn header(&self) -> &BlockHeader<'a> {
+        &self.header
+    }
+    /// Returns the number of transactions in the block
+    pub fn total_transactions(&self) -> u32 {
+        read_u32(&self.slice[80..]).expect("slice length ensured by parsing")
+    }
+    /// Returns the number of hashes in the partial merkle tree
+    pub fn total_hashes(&self) -> usize {
+        self.total_hashes
+    }
+    /// Returns the hashes of the partial merkle tree in depth-first order
+    pub fn hashes(&self) -> core::slice::ChunksExact<'a, u8> {
+        self.slice[self.hashes_from..self.hashes_from + self.total_hashes * 32].chunks_exact(32)
+    }
+    /// Returns the flag bits of the partial merkle tree, packed in bytes, least significant bit
+    /// first
+    pub fn flags(&self) -> &'a [u8] {
+        &self.slice[self.flags_from..]
+    }
+
+    /// Verify the partial merkle tree, calling [`Visitor::visit_merkle_match()`] for every
+    /// matched transaction, and check its root is the merkle root of the header.
+    ///
+    /// Returns [`Error::InvalidMerkleProof`] if the partial merkle tree is malformed and
+    /// [`Error::MerkleRootMismatch`] if its root doesn't match the header.
+    #[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+    pub fn verify<V: Visitor>(&self, visit: &mut V) -> Result<(), Error> {
+        let total_transactions = self.total_transactions();
+        let flags = self.flags();
+        if total_transactions == 0
+            || total_transactions > MAX_TRANSACTIONS
+            || self.total_hashes > total_transactions as usize
+            || flags.len() * 8 < self.total_hashes
+        {
+            return Err(Error::InvalidMerkleProof);
+        }
+        let mut height = 0;
+        while tree_width(total_transactions, height) > 1 {
+            height += 1;
+        }
+        let mut traversal = Traversal {
+            total_transactions,
+            hashes: &self.slice[self.hashes_from..self.flags_from],
+            flags,
+            bits_used: 0,
+            hashes_used: 0,
+        };
+        let root = traversal.extract(height, 0, visit)?;
+        if (traversal.bits_used + 7) / 8 != flags.len()
+            || traversal.hashes_used != self.total_hashes
+        {
+            return Err(Error::InvalidMerkleProof);
+        }
+        if root != self.header.merkle_root() {
+            return Err(Error::MerkleRootMismatch);
+        }
+        Ok(())
+    }
+}
+
+impl<'a> AsRef<[u8]> for MerkleBlock<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.slice
+    }
+}
+
+#[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+/// Number of nodes at `height` of a merkle tree with `total_transactions` leaves
+fn tree_width(total_transactions: u32, height: u32) -> u32 {
+    ((total_transactions as u64 + (1 << height) - 1) >> height) as u32
+}
+
+#[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+struct Traversal<'a> {
+    total_transactions: u32,
+    hashes: &'a [u8],
+    flags: &'a [u8],
+    bits_used: usize,
+    hashes_used: usize,
+}
+
+#[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+impl<'a> Traversal<'a> {
+    fn extract<V: Visitor>(
+        &mut self,
+        height: u32,
+        pos: u32,
+        visit: &mut V,
+    ) -> Result<[u8; 32], Error> {
+        let byte = self
+            .flags
+            .get(self.bits_used / 8)
+            .ok_or(Error::InvalidMerkleProof)?;
+        let parent_of_match = (byte >> (self.bits_used % 8)) & 1 == 1;
+        self.bits_used += 1;
+        if height == 0 || !parent_of_match {
+            let start = self.hashes_used * 32;
+            let hash = self
+                .hashes
+                .get(start..start + 32)
+                .ok_or(Error::InvalidMerkleProof)?;
+            self.hashes_used += 1;
+            if height == 0 && parent_of_match {
+                if let ControlFlow::Break(_) = visit.visit_merkle_match(pos as usize, hash) {
+                    return Err(Error::VisitBreak);
+                }
+            }
+            return Ok(hash.try_into().expect("32 bytes"));
+        }
+        let left = self.extract(height - 1, pos * 2, visit)?;
+        let right = if pos * 2 + 1 < tree_width(self.total_transactions, height - 1) {
+            let right = self.extract(height - 1, pos * 2 + 1, visit)?;
+            if right == left {
+                // CVE-2012-2459
+                return Err(Error::InvalidMerkleProof);
+            }
+            right
+        } else {
+            left
+        };
+        Ok(crate::hashes::sha256d(&[&left, &right]))
+    }
+}
+
+/// Builds the merkle block of `block` proving the inclusion of the transactions with the given
+/// `txids`. Txids not in the block are ignored.
+#[cfg(all(feature = "alloc", any(feature = "bitcoin_hashes", feature = "sha2")))]
+pub fn build_merkle_block(block: &crate::bsl::Block, txids: &[[u8; 32]]) -> alloc::vec::Vec<u8> {
+    use crate::Visit;
+    #[cfg(not(any(test, feature = "std")))]
+    use alloc::vec::Vec;
+
+    struct Txids(Vec<[u8; 32]>);
+    impl Visitor for Txids {
+        fn visit_transaction(&mut self, tx: &crate::bsl::Transaction) -> ControlFlow<()> {
+            let (a, b, c) = tx.txid_preimage();
+            self.0.push(crate::hashes::sha256d(&[a, b, c]));
+            ControlFlow::Continue(())
+        }
+    }
+
+    struct Builder {
+        leaves: Vec<[u8; 32]>,
+        matches: Vec<bool>,
+        hashes: Vec<[u8; 32]>,
+        bits: Vec<bool>,
+    }
+    impl Builder {
+        fn width(&self, height: u32) -> u32 {
+            tree_width(self.leaves.len() as u32, height)
+        }
+        fn hash(&self, height: u32, pos: u32) -> [u8; 32] {
+            if height == 0 {
+                return self.leaves[pos as usize];
+            }
+            let left = self.hash(height - 1, pos * 2);
+            let right = if pos * 2 + 1 < self.width(height - 1) {
+                self.hash(height - 1, pos * 2 + 1)
+            } else {
+                left
+            };
+            crate::hashes::sha256d(&[&left, &right])
+        }
+        fn build(&mut self, height: u32, pos: u32) {
+            let start = (pos as usize) << height;
+            let end = ((pos as usize + 1) << height).min(self.leaves.len());
+            let parent_of_match = self.matches[start..end].iter().any(|m| *m);
+            self.bits.push(parent_of_match);
+            if height == 0 || !parent_of_match {
+                let hash = self.hash(height, pos);
+                self.hashes.push(hash);
+            } else {
+                self.build(height - 1, pos * 2);
+                if pos * 2 + 1 < self.width(height - 1) {
+                    self.build(height - 1, pos * 2 + 1);
+                }
+            }
+        }
+    }
+
+    let mut leaves = Txids(Vec::with_capacity(block.total_transactions()));
+    crate::bsl::Block::visit(block.as_ref(), &mut leaves).expect("block already validated");
+    let matches = leaves.0.iter().map(|txid| txids.contains(txid)).collect();
+    let mut builder = Builder {
+        leaves: leaves.0,
+        matches,
+        hashes: Vec::new(),
+        bits: Vec::new(),
+    };
+    let mut height = 0;
+    while builder.width(height) > 1 {
+        height += 1;
+    }
+    builder.build(height, 0);
+
+    let mut result = Vec::new();
+    result.extend_from_slice(block.header().as_ref());
+    result.extend_from_slice(&(builder.leaves.len() as u32).to_le_bytes());
+    write_len(&mut result, builder.hashes.len());
+    for hash in builder.hashes.iter() {
+        result.extend_from_slice(hash);
+    }
+    let mut flags = alloc::vec![0u8; (builder.bits.len() + 7) / 8];
+    for (i, bit) in builder.bits.iter().enumerate() {
+        flags[i / 8] |= (*bit as u8) << (i % 8);
+    }
+    write_len(&mut result, flags.len());
+    result.extend_from_slice(&flags);
+    result
+}
+
+#[cfg(all(feature = "alloc", any(feature = "bitcoin_hashes", feature = "sha2")))]
+fn write_len(result: &mut alloc::vec::Vec<u8>, len: usize) {
+    match len {
+        0..=0xFC => result.push(len as u8),
+        0xFD..=0xFFFF => {
+            result.push(0xFD);
+            result.extend_from_slice(&(len as u16).to_le_bytes());
+        }
+        _ => {
+            result.push(0xFE);
+            result.extend_from_slice(&(len as u32).to_le_bytes());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MerkleBlock;
+    use crate::{Error, Parse};
+    use bitcoin::consensus::serialize;
+    use bitcoin_test_data::blocks::mainnet_702861;
+
+    fn merkle_block(indexes: &[usize]) -> (Vec<u8>, Vec<bitcoin::Txid>) {
+        let block: bitcoin::Block = bitcoin::consensus::deserialize(mainnet_702861()).unwrap();
+        let txids: Vec<_> = indexes
+            .iter()
+            .map(|i| block.txdata[*i].compute_txid())
+            .collect();
+        let merkle_block =
+            bitcoin::MerkleBlock::from_block_with_predicate(&block, |t| txids.contains(t));
+        (serialize(&merkle_block), txids)
+    }
+
+    #[test]
+    fn parse_merkle_block() {
+        let (bytes, _) = merkle_block(&[0, 7, 2499]);
+        let merkle_block = MerkleBlock::parse(&bytes).unwrap();
+        assert_eq!(merkle_block.consumed(), bytes.len());
+        let merkle_block = merkle_block.parsed();
+        assert_eq!(merkle_block.header().as_ref(), &mainnet_702861()[..80]);
+        assert_eq!(merkle_block.total_transactions(), 2500);
+        assert_eq!(merkle_block.hashes().len(), merkle_block.total_hashes());
+
+        for i in 0..bytes.len() {
+            assert_eq!(MerkleBlock::parse(&bytes[..i]), Err(Error::MoreBytesNeeded));
+        }
+    }
+
+    #[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+    #[test]
+    fn verify_merkle_block() {
+        use crate::Visitor;
+        use bitcoin::hashes::Hash;
+        use core::ops::ControlFlow;
+
+        #[derive(Default)]
+        struct Matches(Vec<(usize, Vec<u8>)>);
+        impl Visitor for Matches {
+            fn visit_merkle_match(&mut self, position: usize, txid: &[u8]) -> ControlFlow<()> {
+                self.0.push((position, txid.to_vec()));
+                ControlFlow::Continue(())
+            }
+        }
+
+        for indexes in [&[0usize][..], &[1, 2, 3], &[0, 7, 1000, 2499], &[]] {
+            let (bytes, txids) = merkle_block(indexes);
+            let merkle_block = MerkleBlock::parse(&bytes).unwrap().parsed_owned();
+            let mut matches = Matches::default();
+            merkle_block.verify(&mut matches).unwrap();
+            let expected: Vec<_> = indexes
+                .iter()
+                .zip(txids.iter())
+                .map(|(i, t)| (*i, t.to_byte_array().to_vec()))
+                .collect();
+            assert_eq!(matches.0, expected);
+        }
+
+        let (bytes, _) = merkle_block(&[1, 2, 3]);
+        let mut wrong = bytes.clone();
+        wrong[100] ^= 1; // first hash
+        let merkle_block = MerkleBlock::parse(&wrong).unwrap().parsed_owned();
+        assert_eq!(
+            merkle_block.verify(&mut Matches::default()),
+            Err(Error::MerkleRootMismatch)
+        );
+
+        let flags_len = MerkleBlock::parse(&bytes).unwrap().parsed().flags().len();
+        let mut wrong = bytes.clone();
+        wrong[bytes.len() - flags_len] ^= 1; // the root flag bit
+        let merkle_block = MerkleBlock::parse(&wrong).unwrap().parsed_owned();
+        assert_eq!(
+            merkle_block.verify(&mut Matches::default()),
+            Err(Error::InvalidMerkleProof)
+        );
+
+        let mut wrong = bytes.clone();
+        wrong[80..84].copy_from_slice(&0u32.to_le_bytes());
+        let merkle_block = MerkleBlock::parse(&wrong).unwrap().parsed_owned();
+        assert_eq!(
+            merkle_block.verify(&mut Matches::default()),
+            Err(Error::InvalidMerkleProof)
+        );
+
+        let mut wrong = bytes.clone();
+        wrong[bytes.len() - flags_len - 1] += 1;
+        wrong.push(0); // one more unused flag byte
+        let merkle_block = MerkleBlock::parse(&wrong).unwrap().parsed_owned();
+        assert_eq!(
+            merkle_block.verify(&mut Matches::default()),
+            Err(Error::InvalidMerkleProof)
+        );
+    }
+
+    #[cfg(all(feature = "alloc", any(feature = "bitcoin_hashes", feature = "sha2")))]
+    #[test]
+    fn build_merkle_block() {
+        use crate::bsl::Block;
+        use bitcoin::hashes::Hash;
+
+        let block = Block::parse(mainnet_702861()).unwrap().parsed_owned();
+        for indexes in [&[0usize][..], &[1, 2, 3], &[0, 7, 1000, 2499], &[]] {
+            let (expected, txids) = merkle_block(indexes);
+            let txids: Vec<_> = txids.iter().map(|t| t.to_byte_array()).collect();
+            let built = super::build_merkle_block(&block, &txids);
+            assert_eq!(built, expected);
+        }
+    }
+}
@@ -11,35 +11,73 @@
 //! from the caller without requiring re-parsing.
 
 mod block;
+mod block_file;
 mod block_header;
+mod coin;
+mod compressed;
 mod len;
+#[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+mod merkle;
+mod merkle_block;
+pub mod opcodes;
 mod out_point;
+pub mod p2p;
+mod resumable;
 mod script;
+mod script_type;
+mod snapshot;
+mod taproot;
 mod transaction;
 mod tx_in;
 mod tx_ins;
 mod tx_out;
 mod tx_outs;
+mod undo;
 mod witness;
 mod witnesses;
 
-pub use block::Block;
+pub use block::{Block, BlockTxIndex, TransactionIterator};
+pub use block_file::{deobfuscate, BlockFile, ObfuscatedBlockFile};
 pub use block_header::BlockHeader;
+pub use coin::{obfuscation_key, Coin, CoinKey, OBFUSCATION_KEY_KEY};
+pub use compressed::{CompressedScript, CompressedTxOut, SPECIAL_SCRIPT_MAX_LEN};
 
 #[allow(deprecated)]
 pub use len::parse_len;
 
 pub use len::scan_len;
 pub use len::Len;
+pub(crate) use len::{compact_size_len, compact_size_len_of_prefix};
+pub use merkle_block::MerkleBlock;
 pub use out_point::OutPoint;
-pub use script::Script;
+pub use resumable::{BlockParser, TransactionParser};
+pub use script::{Instruction, Instructions, Script};
+pub use script_type::{MultisigKeys, ScriptType};
+pub use snapshot::{
+    SnapshotCoin, SnapshotCoinIterator, SnapshotMetadata, SnapshotTxCoins, UtxoSnapshot,
+    SNAPSHOT_MAGIC,
+};
+pub use taproot::{ControlBlock, TaprootSpend, TAPROOT_ANNEX_TAG, TAPROOT_LEAF_TAPSCRIPT};
 pub use transaction::Transaction;
 pub use tx_in::TxIn;
-pub use tx_ins::TxIns;
+pub use tx_ins::{TxInIterator, TxIns};
 pub use tx_out::TxOut;
 pub use tx_outs::TxOuts;
-pub use witness::Witness;
-pub use witnesses::Witnesses;
+pub use undo::{BlockUndo, TxInUndo, TxInUndoIterator, TxUndo, TxUndoIterator, UndoFile};
+pub use witness::{Witness, WitnessElementIterator};
+pub use witnesses::{WitnessIterator, Witnesses};
 
 #[cfg(all(feature = "bitcoin", feature = "sha2"))]
 pub use block::visitor::FindTransaction;
+
+#[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+pub use merkle::MerkleVerifier;
+
+#[cfg(all(feature = "alloc", any(feature = "bitcoin_hashes", feature = "sha2")))]
+pub use merkle_block::build_merkle_block;
+
+#[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+pub use snapshot::HashSerialized;
+
+#[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+pub use taproot::tap_leaf_hash;
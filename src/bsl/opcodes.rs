@@ -0,0 +1,45 @@
+//! Script opcodes used by the crate, see [`crate::bsl::Instructions`].
+
+/// Push an empty array
+pub const OP_0: u8 = 0x00;
+/// The next byte contains the number of bytes to push
+pub const OP_PUSHDATA1: u8 = 0x4c;
+/// The next two bytes contain the number of bytes to push (little endian)
+pub const OP_PUSHDATA2: u8 = 0x4d;
+/// The next four bytes contain the number of bytes to push (little endian)
+pub const OP_PUSHDATA4: u8 = 0x4e;
+/// Push the number -1
+pub const OP_1NEGATE: u8 = 0x4f;
+/// Reserved, makes the script invalid if executed
+pub const OP_RESERVED: u8 = 0x50;
+/// Push the number 1
+pub const OP_1: u8 = 0x51;
+/// Push the number 16
+pub const OP_16: u8 = 0x60;
+/// Fail the script immediately, used to mark outputs as unspendable
+pub const OP_RETURN: u8 = 0x6a;
+/// Duplicate the top stack element
+pub const OP_DUP: u8 = 0x76;
+/// Push 1 if the two top stack elements are equal, 0 otherwise
+pub const OP_EQUAL: u8 = 0x87;
+/// Same as [`OP_EQUAL`] followed by `OP_VERIFY`
+pub const OP_EQUALVERIFY: u8 = 0x88;
+/// Hash the top stack element with sha256 and then with ripemd160
+pub const OP_HASH160: u8 = 0xa9;
+/// Signatures checked after it only commit to the script following it
+pub const OP_CODESEPARATOR: u8 = 0xab;
+/// Check a signature against a public key
+pub const OP_CHECKSIG: u8 = 0xac;
+/// Check m signatures against n public keys
+pub const OP_CHECKMULTISIG: u8 = 0xae;
+/// Check a signature and add the result to a counter, tapscript only
+pub const OP_CHECKSIGADD: u8 = 0xba;
+
+/// Returns the number pushed by `OP_1` to `OP_16`, if `opcode` is one of them
+pub fn small_int(opcode: u8) -> Option<u8> {
+    if (OP_1..=OP_16).contains(&opcode) {
+        Some(opcode - OP_1 + 1)
+    } else {
+        None
+    }
+}
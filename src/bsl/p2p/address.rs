@@ -0,0 +1,272 @@
+use core::ops::ControlFlow;
+
+use crate::{
+    bsl::scan_len,
+    number::{read_u32, read_u64},
+    slice::split_at_checked,
+    Parse, ParseResult, SResult, Visit, Visitor,
+};
+
+/// Length of a serialized [`NetAddr`]: 8 bytes services, 16 bytes ip, 2 bytes port.
+const NET_ADDR_LEN: usize = 26;
+
+/// Length of a serialized [`Address`]: 4 bytes time followed by a [`NetAddr`].
+const ADDRESS_LEN: usize = 4 + NET_ADDR_LEN;
+
+/// A network address without the time field, as found in the `version` message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetAddr<'a> {
+    slice: &'a [u8],
+}
+
+impl<'a> Parse<'a> for NetAddr<'a> {
+    #[inline(always)]
+    fn parse(slice: &'a [u8]) -> SResult<Self> {
+        let (slice, remaining) = split_at_checked(slice, NET_ADDR_LEN)?;
+        Ok(ParseResult::new(remaining, NetAddr { slice }))
+    }
+}
+
+impl<'a> NetAddr<'a> {
+    /// Returns the service bits advertised for this address
+    pub fn services(&self) -> u64 {
+        read_u64(self.slice).expect("slice length ensured by parsing")
+    }
+    /// Returns the 16 bytes of the IPv6 address, IPv4 addresses are IPv4-mapped IPv6 addresses
+    pub fn ip(&self) -> &'a [u8] {
+        &self.slice[8..24]
+    }
+    /// Returns the port of this address (encoded big-endian)
+    pub fn port(&self) -> u16 {
+        u16::from_be_bytes([self.slice[24], self.slice[25]])
+    }
+}
+
+impl<'a> AsRef<[u8]> for NetAddr<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.slice
+    }
+}
+
+/// A network address with the time it was last seen, an entry of the `addr` message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Address<'a> {
+    slice: &'a [u8],
+}
+
+impl<'a> Parse<'a> for Address<'a> {
+    #[inline(always)]
+    fn parse(slice: &'a [u8]) -> SResult<Self> {
+        let (slice, remaining) = split_at_checked(slice, ADDRESS_LEN)?;
+        Ok(ParseResult::new(remaining, Address { slice }))
+    }
+}
+
+impl<'a> Address<'a> {
+    /// Returns the UNIX timestamp this address was last seen
+    pub fn time(&self) -> u32 {
+        read_u32(self.slice).expect("slice length ensured by parsing")
+    }
+    /// Returns the address without the time
+    pub fn net_addr(&self) -> NetAddr<'a> {
+        NetAddr {
+            slice: &self.slice[4..],
+        }
+    }
+    /// Returns the service bits advertised for this address
+    pub fn services(&self) -> u64 {
+        self.net_addr().services()
+    }
+    /// Returns the 16 bytes of the IPv6 address, IPv4 addresses are IPv4-mapped IPv6 addresses
+    pub fn ip(&self) -> &'a [u8] {
+        &self.slice[12..28]
+    }
+    /// Returns the port of this address
+    pub fn port(&self) -> u16 {
+        self.net_addr().port()
+    }
+}
+
+impl<'a> AsRef<[u8]> for Address<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.slice
+    }
+}
+
+/// The payload of the `addr` message, a list of [`Address`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Addrs<'a> {
+    slice: &'a [u8],
+    n: usize,
+    from: usize,
+}
+
+impl<'a> Visit<'a> for Addrs<'a> {
+    #[inline(always)]
+    fn visit<'b, V: Visitor>(slice: &'a [u8], visit: &'b mut V) -> SResult<'a, Self> {
+        let mut consumed = 0;
+        let total_addrs = scan_len(slice, &mut consumed)? as usize;
+        let from = consumed;
+        visit.visit_addrs(total_addrs);
+        for i in 0..total_addrs {
+            let address = Address::parse(&slice[consumed..])?;
+            consumed += ADDRESS_LEN;
+            if let ControlFlow::Break(_) = visit.visit_addr(i, address.parsed()) {
+                return Err(crate::Error::VisitBreak);
+            }
+        }
+        Ok(ParseResult::new(
+            &slice[consumed..],
+            Addrs {
+                slice: &slice[..consumed],
+                n: total_addrs,
+                from,
+            },
+        ))
+    }
+}
+
+impl<'a> Addrs<'a> {
+    /// If there are no addresses.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+    /// The number of addresses.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+    /// Returns the address at position `i` if it exists.
+    ///
+    /// Since addresses have fixed length, this doesn't require a pass on the slice.
+    pub fn get(&self, i: usize) -> Option<Address<'a>> {
+        if i >= self.n {
+            return None;
+        }
+        let begin = self.from + i * ADDRESS_LEN;
+        Some(Address {
+            slice: &self.slice[begin..begin + ADDRESS_LEN],
+        })
+    }
+    /// Returns an iterator over [`Address`]
+    pub fn iter(&self) -> AddressIterator<'a> {
+        AddressIterator {
+            chunks: self.slice[self.from..].chunks_exact(ADDRESS_LEN),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &Addrs<'a> {
+    type Item = Address<'a>;
+    type IntoIter = AddressIterator<'a>;
+
+    fn into_iter(self) -> AddressIterator<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over the [`Address`] contained in [`Addrs`], returned by [`Addrs::iter()`]
+pub struct AddressIterator<'a> {
+    chunks: core::slice::ChunksExact<'a, u8>,
+}
+
+impl<'a> Iterator for AddressIterator<'a> {
+    type Item = Address<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.chunks.next().map(|slice| Address { slice })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chunks.size_hint()
+    }
+}
+
+impl<'a> ExactSizeIterator for AddressIterator<'a> {}
+
+impl<'a> AsRef<[u8]> for Addrs<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.slice
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::ops::ControlFlow;
+
+    use crate::{
+        bsl::p2p::{Address, Addrs},
+        Error, Parse, Visit, Visitor,
+    };
+    use bitcoin::p2p::{address, ServiceFlags};
+
+    pub(crate) fn addrs_bytes() -> Vec<u8> {
+        let addrs: Vec<(u32, address::Address)> = vec![
+            (
+                1_700_000_000,
+                address::Address::new(&"1.2.3.4:8333".parse().unwrap(), ServiceFlags::NETWORK),
+            ),
+            (
+                1_700_000_001,
+                address::Address::new(
+                    &"[2001:db8::1]:18333".parse().unwrap(),
+                    ServiceFlags::WITNESS,
+                ),
+            ),
+        ];
+        bitcoin::consensus::serialize(&addrs)
+    }
+
+    #[test]
+    fn parse_addrs() {
+        let bytes = addrs_bytes();
+        let addrs = Addrs::parse(&bytes[..]).unwrap();
+        assert_eq!(addrs.remaining(), &[]);
+        let addrs = addrs.parsed_owned();
+        assert_eq!(addrs.n(), 2);
+
+        let first = addrs.get(0).unwrap();
+        assert_eq!(first.time(), 1_700_000_000);
+        assert_eq!(first.services(), ServiceFlags::NETWORK.to_u64());
+        assert_eq!(
+            first.ip(),
+            &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 1, 2, 3, 4]
+        );
+        assert_eq!(first.port(), 8333);
+
+        let second = addrs.get(1).unwrap();
+        assert_eq!(second.time(), 1_700_000_001);
+        assert_eq!(second.services(), ServiceFlags::WITNESS.to_u64());
+        assert_eq!(
+            second.ip(),
+            &[0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]
+        );
+        assert_eq!(second.net_addr().port(), 18333);
+
+        assert_eq!(addrs.get(2), None);
+        assert_eq!(addrs.iter().collect::<Vec<_>>(), vec![first, second]);
+
+        for i in 0..bytes.len() {
+            assert_eq!(Addrs::parse(&bytes[..i]), Err(Error::MoreBytesNeeded));
+        }
+    }
+
+    #[test]
+    fn visit_addrs() {
+        let bytes = addrs_bytes();
+        struct Ports(Vec<u16>);
+        impl Visitor for Ports {
+            fn visit_addrs(&mut self, total_addrs: usize) {
+                assert_eq!(total_addrs, 2);
+            }
+            fn visit_addr(&mut self, i: usize, address: &Address) -> ControlFlow<()> {
+                assert_eq!(i, self.0.len());
+                self.0.push(address.port());
+                ControlFlow::Continue(())
+            }
+        }
+        let mut ports = Ports(vec![]);
+        Addrs::visit(&bytes[..], &mut ports).unwrap();
+        assert_eq!(ports.0, vec![8333, 18333]);
+    }
+}
@@ -0,0 +1,319 @@
+use core::ops::ControlFlow;
+
+use crate::{
+    bsl::scan_len, number::read_u32, slice::split_at_checked, Error, Parse, ParseResult, SResult,
+    Visit, Visitor,
+};
+
+/// Maximum length of the address bytes, as defined in BIP155.
+const MAX_ADDRESS_LEN: usize = 512;
+
+/// Network identifiers defined in BIP155, see [`AddressV2::network_id()`]
+pub mod network_id {
+    /// IPv4, 4 bytes address
+    pub const IPV4: u8 = 1;
+    /// IPv6, 16 bytes address
+    pub const IPV6: u8 = 2;
+    /// Tor v2 onion service, 10 bytes address (deprecated)
+    pub const TORV2: u8 = 3;
+    /// Tor v3 onion service, 32 bytes address
+    pub const TORV3: u8 = 4;
+    /// I2P overlay network, 32 bytes address
+    pub const I2P: u8 = 5;
+    /// CJDNS overlay network, 16 bytes address
+    pub const CJDNS: u8 = 6;
+
+    /// Returns the required address length for the known network ids
+    pub(crate) fn address_len(network_id: u8) -> Option<usize> {
+        match network_id {
+            IPV4 => Some(4),
+            IPV6 | CJDNS => Some(16),
+            TORV2 => Some(10),
+            TORV3 | I2P => Some(32),
+            _ => None,
+        }
+    }
+}
+
+/// A network address as defined in BIP155, an entry of the `addrv2` message.
+///
+/// Addresses of unknown networks are parsed as long as they are not longer than 512 bytes, so
+/// that they can be ignored by the caller as specified in the BIP.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressV2<'a> {
+    slice: &'a [u8],
+    services: u64,
+    network_id: u8,
+    address_from: usize,
+}
+
+impl<'a> Parse<'a> for AddressV2<'a> {
+    #[inline(always)]
+    fn parse(slice: &'a [u8]) -> SResult<Self> {
+        let mut consumed = 4;
+        let services = scan_len(slice.get(4..).ok_or(Error::MoreBytesNeeded)?, &mut consumed)?;
+        let network_id = *slice.get(consumed).ok_or(Error::MoreBytesNeeded)?;
+        consumed += 1;
+        let address_len = scan_len(&slice[consumed..], &mut consumed)? as usize;
+        if address_len > MAX_ADDRESS_LEN {
+            return Err(Error::InvalidNetworkAddress);
+        }
+        if let Some(expected) = network_id::address_len(network_id) {
+            if expected != address_len {
+                return Err(Error::InvalidNetworkAddress);
+            }
+        }
+        let address_from = consumed;
+        let (slice, remaining) = split_at_checked(slice, consumed + address_len + 2)?;
+        Ok(ParseResult::new(
+            remaining,
+            AddressV2 {
+                slice,
+                services,
+                network_id,
+                address_from,
+            },
+        ))
+    }
+}
+
+impl<'a> AddressV2<'a> {
+    /// Returns the UNIX timestamp this address was last seen
+    pub fn time(&self) -> u32 {
+        read_u32(self.slice).expect("slice length ensured by parsing")
+    }
+    /// Returns the service bits advertised for this address
+    pub fn services(&self) -> u64 {
+        self.services
+    }
+    /// Returns the network of this address, see [`network_id`]
+    pub fn network_id(&self) -> u8 {
+        self.network_id
+    }
+    /// Returns the address bytes, their meaning depends on the [`AddressV2::network_id()`]
+    pub fn address(&self) -> &'a [u8] {
+        &self.slice[self.address_from..self.slice.len() - 2]
+    }
+    /// Returns the port of this address (encoded big-endian)
+    pub fn port(&self) -> u16 {
+        let len = self.slice.len();
+        u16::from_be_bytes([self.slice[len - 2], self.slice[len - 1]])
+    }
+}
+
+impl<'a> AsRef<[u8]> for AddressV2<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.slice
+    }
+}
+
+/// The payload of the `addrv2` message (BIP155), a list of [`AddressV2`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddrsV2<'a> {
+    slice: &'a [u8],
+    n: usize,
+}
+
+impl<'a> Visit<'a> for AddrsV2<'a> {
+    #[inline(always)]
+    fn visit<'b, V: Visitor>(slice: &'a [u8], visit: &'b mut V) -> SResult<'a, Self> {
+        let mut consumed = 0;
+        let total_addrs = scan_len(slice, &mut consumed)? as usize;
+        visit.visit_addrs(total_addrs);
+        for i in 0..total_addrs {
+            let address = AddressV2::parse(&slice[consumed..])?;
+            consumed += address.consumed();
+            if let ControlFlow::Break(_) = visit.visit_addr_v2(i, address.parsed()) {
+                return Err(Error::VisitBreak);
+            }
+        }
+        Ok(ParseResult::new(
+            &slice[consumed..],
+            AddrsV2 {
+                slice: &slice[..consumed],
+                n: total_addrs,
+            },
+        ))
+    }
+}
+
+impl<'a> AddrsV2<'a> {
+    /// If there are no addresses.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+    /// The number of addresses.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+    /// Returns an iterator over [`AddressV2`]
+    ///
+    /// If possible is better to use [`Visitor::visit_addr_v2`] to avoid double pass, however, it
+    /// may be convenient to iterate in case you already have validated the slice.
+    pub fn iter(&self) -> AddressV2Iterator<'a> {
+        let mut offset = 0;
+        scan_len(self.slice, &mut offset).expect("len granted by parsing");
+        AddressV2Iterator {
+            remaining: self.n,
+            slice: &self.slice[offset..],
+        }
+    }
+}
+
+impl<'a> IntoIterator for &AddrsV2<'a> {
+    type Item = AddressV2<'a>;
+    type IntoIter = AddressV2Iterator<'a>;
+
+    fn into_iter(self) -> AddressV2Iterator<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over the [`AddressV2`] contained in [`AddrsV2`], returned by [`AddrsV2::iter()`]
+pub struct AddressV2Iterator<'a> {
+    remaining: usize,
+    slice: &'a [u8],
+}
+
+impl<'a> Iterator for AddressV2Iterator<'a> {
+    type Item = AddressV2<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            None
+        } else {
+            self.remaining -= 1;
+            let address = AddressV2::parse(self.slice).expect("granted from parsing");
+            self.slice = address.remaining();
+            Some(address.parsed_owned())
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for AddressV2Iterator<'a> {}
+
+impl<'a> AsRef<[u8]> for AddrsV2<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.slice
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::ops::ControlFlow;
+
+    use crate::{
+        bsl::p2p::{address_v2::network_id, AddressV2, AddrsV2},
+        Error, Parse, Visit, Visitor,
+    };
+    use bitcoin::p2p::{
+        address::{AddrV2, AddrV2Message},
+        ServiceFlags,
+    };
+    use hex_lit::hex;
+
+    fn addrs_v2_bytes() -> Vec<u8> {
+        let addrs = vec![
+            AddrV2Message {
+                time: 1_700_000_000,
+                services: ServiceFlags::NETWORK | ServiceFlags::WITNESS,
+                addr: AddrV2::Ipv4("1.2.3.4".parse().unwrap()),
+                port: 8333,
+            },
+            AddrV2Message {
+                time: 1_700_000_001,
+                services: ServiceFlags::from(u64::MAX),
+                addr: AddrV2::TorV3([4u8; 32]),
+                port: 9050,
+            },
+            AddrV2Message {
+                time: 1_700_000_002,
+                services: ServiceFlags::NONE,
+                addr: AddrV2::Unknown(42, vec![1, 2, 3]),
+                port: 1,
+            },
+        ];
+        bitcoin::consensus::serialize(&addrs)
+    }
+
+    #[test]
+    fn parse_addrs_v2() {
+        let bytes = addrs_v2_bytes();
+        let addrs = AddrsV2::parse(&bytes[..]).unwrap();
+        assert_eq!(addrs.remaining(), &[]);
+        let addrs = addrs.parsed_owned();
+        assert_eq!(addrs.n(), 3);
+
+        let mut iter = addrs.iter();
+        assert_eq!(iter.len(), 3);
+
+        let address = iter.next().unwrap();
+        assert_eq!(address.time(), 1_700_000_000);
+        assert_eq!(address.services(), 9);
+        assert_eq!(address.network_id(), network_id::IPV4);
+        assert_eq!(address.address(), &[1, 2, 3, 4]);
+        assert_eq!(address.port(), 8333);
+
+        let address = iter.next().unwrap();
+        assert_eq!(address.services(), u64::MAX);
+        assert_eq!(address.network_id(), network_id::TORV3);
+        assert_eq!(address.address(), &[4u8; 32]);
+        assert_eq!(address.port(), 9050);
+
+        let address = iter.next().unwrap();
+        assert_eq!(address.network_id(), 42);
+        assert_eq!(address.address(), &[1, 2, 3]);
+        assert_eq!(address.port(), 1);
+
+        assert!(iter.next().is_none());
+
+        for i in 0..bytes.len() {
+            assert_eq!(AddrsV2::parse(&bytes[..i]), Err(Error::MoreBytesNeeded));
+        }
+    }
+
+    #[test]
+    fn invalid_address_v2() {
+        // ipv4 with 3 bytes address
+        let bytes = hex!("00f1536501010301020304208d");
+        assert_eq!(
+            AddressV2::parse(&bytes[..]),
+            Err(Error::InvalidNetworkAddress)
+        );
+        // unknown network with 513 bytes address
+        let bytes = hex!("00f15365012afd0102");
+        assert_eq!(
+            AddressV2::parse(&bytes[..]),
+            Err(Error::InvalidNetworkAddress)
+        );
+    }
+
+    #[test]
+    fn visit_addrs_v2() {
+        let bytes = addrs_v2_bytes();
+        struct Networks(Vec<u8>);
+        impl Visitor for Networks {
+            fn visit_addrs(&mut self, total_addrs: usize) {
+                assert_eq!(total_addrs, 3);
+            }
+            fn visit_addr_v2(&mut self, _i: usize, address: &AddressV2) -> ControlFlow<()> {
+                self.0.push(address.network_id());
+                ControlFlow::Continue(())
+            }
+        }
+        let mut networks = Networks(vec![]);
+        AddrsV2::visit(&bytes[..], &mut networks).unwrap();
+        assert_eq!(networks.0, vec![network_id::IPV4, network_id::TORV3, 42]);
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn size_of() {
+        assert_eq!(std::mem::size_of::<AddressV2>(), 40);
+    }
+}
@@ -0,0 +1,562 @@
+use core::ops::ControlFlow;
+
+use crate::{
+    bsl::{scan_len, BlockHeader, Transaction},
+    detailed::{self, DetailedError},
+    number::read_u64,
+    Error, Parse, ParseResult, SResult, Visit, Visitor,
+};
+
+/// Length of a short transaction id.
+const SHORT_ID_LEN: usize = 6;
+
+/// The payload of the `cmpctblock` message (BIP152), also known as `HeaderAndShortIDs`.
+///
+/// It contains the block header, a nonce, the short ids of the transactions not included and the
+/// prefilled transactions, at least the coinbase.
+/// Transactions not included are expected to be found in the mempool of the receiver by matching
+/// the short ids, see `ShortIdHasher`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompactBlock<'a> {
+    slice: &'a [u8],
+    short_ids_from: usize,
+    total_short_ids: usize,
+    total_prefilled: usize,
+}
+
+impl<'a> Visit<'a> for CompactBlock<'a> {
+    #[inline(always)]
+    fn visit<'b, V: Visitor>(slice: &'a [u8], visit: &'b mut V) -> SResult<'a, Self> {
+        let header = BlockHeader::visit(slice, visit)?;
+        read_u64(header.remaining())?;
+        let mut consumed = 88;
+        let total_short_ids = scan_len(&slice[consumed..], &mut consumed)? as usize;
+        let short_ids_from = consumed;
+        consumed = total_short_ids
+            .checked_mul(SHORT_ID_LEN)
+            .and_then(|len| len.checked_add(consumed))
+            .filter(|end| *end <= slice.len())
+            .ok_or(Error::MoreBytesNeeded)?;
+
+        let total_prefilled = scan_len(&slice[consumed..], &mut consumed)? as usize;
+        if total_short_ids.saturating_add(total_prefilled) > u16::MAX as usize {
+            return Err(Error::DifferentialIndexOverflow);
+        }
+        let mut next = 0;
+        for _ in 0..total_prefilled {
+            let index = scan_index(&slice[consumed..], &mut consumed, &mut next)?;
+            if let ControlFlow::Break(_) = visit.visit_prefilled_transaction(index) {
+                return Err(Error::VisitBreak);
+            }
+            let tx = Transaction::visit(&slice[consumed..], visit)?;
+            consumed += tx.consumed();
+        }
+
+        Ok(ParseResult::new(
+            &slice[consumed..],
+            CompactBlock {
+                slice: &slice[..consumed],
+                short_ids_from,
+                total_short_ids,
+                total_prefilled,
+            },
+        ))
+    }
+}
+
+impl<'a> CompactBlock<'a> {
+    /// Visit the compact block like [`Visit::visit()`], in case of error returns also the
+    /// position and the context where it happened, with the prefilled transactions identified by
+    /// their index in the block, see [`crate::bsl::Block::visit_detailed()`].
+    pub fn visit_detailed<V: Visitor>(
+        slice: &'a [u8],
+        visit: &mut V,
+    ) -> Result<ParseResult<'a, Self>, DetailedError> {
+        detailed::visit_detailed(slice, visit)
+    }
+
+    /// Returns the header of the block
+    pub fn header(&self) -> BlockHeader<'a> {
+        BlockHeader::parse(self.slice)
+            .expect("slice length ensured by parsing")
+            .parsed_owned()
+    }
+    /// Returns the nonce used to compute the short ids
+    pub fn nonce(&self) -> u64 {
+        read_u64(&self.slice[80..]).expect("slice length ensured by parsing")
+    }
+    /// Returns the number of transactions in the block, short ids plus prefilled transactions
+    pub fn total_transactions(&self) -> usize {
+        self.total_short_ids + self.total_prefilled
+    }
+    /// Returns the number of short transaction ids
+    pub fn total_short_ids(&self) -> usize {
+        self.total_short_ids
+    }
+    /// Returns the `i`th short transaction id if it exists.
+    ///
+    /// Note `i` is the position in the short ids list, not the position in the block.
+    pub fn short_id(&self, i: usize) -> Option<&'a [u8]> {
+        if i >= self.total_short_ids {
+            return None;
+        }
+        let begin = self.short_ids_from + i * SHORT_ID_LEN;
+        Some(&self.slice[begin..begin + SHORT_ID_LEN])
+    }
+    /// Returns an iterator over the 6 bytes short transaction ids
+    pub fn short_ids(&self) -> core::slice::ChunksExact<'a, u8> {
+        let end = self.short_ids_from + self.total_short_ids * SHORT_ID_LEN;
+        self.slice[self.short_ids_from..end].chunks_exact(SHORT_ID_LEN)
+    }
+    /// Returns the number of prefilled transactions
+    pub fn total_prefilled(&self) -> usize {
+        self.total_prefilled
+    }
+    /// Returns an iterator over the prefilled transactions and their absolute index in the block
+    ///
+    /// If possible is better to use [`Visitor::visit_prefilled_transaction`] to avoid double pass.
+    pub fn prefilled_transactions(&self) -> PrefilledIterator<'a> {
+        let mut consumed = self.short_ids_from + self.total_short_ids * SHORT_ID_LEN;
+        scan_len(&self.slice[consumed..], &mut consumed).expect("len granted by parsing");
+        PrefilledIterator {
+            remaining: self.total_prefilled,
+            next: 0,
+            slice: &self.slice[consumed..],
+        }
+    }
+
+    /// Returns the hasher to compute the short ids of the transactions of this block
+    #[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+    pub fn short_id_hasher(&self) -> ShortIdHasher {
+        ShortIdHasher::new(&self.header(), self.nonce())
+    }
+}
+
+impl<'a> AsRef<[u8]> for CompactBlock<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.slice
+    }
+}
+
+/// Iterator over the prefilled transactions of a [`CompactBlock`], returned by
+/// [`CompactBlock::prefilled_transactions()`]
+pub struct PrefilledIterator<'a> {
+    remaining: usize,
+    next: u64,
+    slice: &'a [u8],
+}
+
+impl<'a> Iterator for PrefilledIterator<'a> {
+    type Item = (usize, Transaction<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            None
+        } else {
+            self.remaining -= 1;
+            let mut consumed = 0;
+            let index =
+                scan_index(self.slice, &mut consumed, &mut self.next).expect("granted by parsing");
+            let tx = Transaction::parse(&self.slice[consumed..]).expect("granted by parsing");
+            self.slice = tx.remaining();
+            Some((index, tx.parsed_owned()))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for PrefilledIterator<'a> {}
+
+/// Computes BIP152 short transaction ids, which are the first 6 bytes of SipHash-2-4 of the
+/// transaction hash, keyed with the single sha256 of the block header and the nonce.
+#[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShortIdHasher {
+    k0: u64,
+    k1: u64,
+}
+
+#[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+impl ShortIdHasher {
+    /// Creates the hasher for the block with the given `header` and the compact block `nonce`
+    pub fn new(header: &BlockHeader, nonce: u64) -> Self {
+        let hash = crate::hashes::sha256(&[header.as_ref(), &nonce.to_le_bytes()[..]]);
+        let k0 = read_u64(&hash[..8]).expect("32 bytes hash");
+        let k1 = read_u64(&hash[8..16]).expect("32 bytes hash");
+        Self { k0, k1 }
+    }
+
+    /// Returns the short id of the given transaction `hash`, the txid for compact blocks version
+    /// 1 or the wtxid for version 2.
+    pub fn short_id(&self, hash: &[u8]) -> [u8; SHORT_ID_LEN] {
+        let siphash = crate::hashes::siphash24(self.k0, self.k1, hash).to_le_bytes();
+        let mut short_id = [0u8; SHORT_ID_LEN];
+        short_id.copy_from_slice(&siphash[..SHORT_ID_LEN]);
+        short_id
+    }
+
+    /// Returns the short id of the given transaction as in compact blocks version 2, computed
+    /// from its wtxid.
+    pub fn tx_short_id(&self, tx: &Transaction) -> [u8; SHORT_ID_LEN] {
+        #[cfg(feature = "bitcoin_hashes")]
+        let wtxid = crate::bitcoin_hashes::Hash::to_byte_array(tx.wtxid());
+        #[cfg(all(feature = "sha2", not(feature = "bitcoin_hashes")))]
+        let wtxid = tx.wtxid_sha2();
+        self.short_id(&wtxid[..])
+    }
+}
+
+/// The payload of the `getblocktxn` message (BIP152), also known as `BlockTransactionsRequest`,
+/// requesting the transactions at the given indexes of the block with the given hash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockTxnRequest<'a> {
+    slice: &'a [u8],
+    n: usize,
+}
+
+impl<'a> Parse<'a> for BlockTxnRequest<'a> {
+    fn parse(slice: &'a [u8]) -> SResult<'a, Self> {
+        if slice.len() < 32 {
+            return Err(Error::MoreBytesNeeded);
+        }
+        let mut consumed = 32;
+        let n = scan_len(&slice[consumed..], &mut consumed)? as usize;
+        let mut next = 0;
+        for _ in 0..n {
+            scan_index(&slice[consumed..], &mut consumed, &mut next)?;
+        }
+        Ok(ParseResult::new(
+            &slice[consumed..],
+            BlockTxnRequest {
+                slice: &slice[..consumed],
+                n,
+            },
+        ))
+    }
+}
+
+impl<'a> BlockTxnRequest<'a> {
+    /// Returns the hash of the block
+    pub fn block_hash(&self) -> &'a [u8] {
+        &self.slice[..32]
+    }
+    /// Returns the number of transactions requested
+    pub fn n(&self) -> usize {
+        self.n
+    }
+    /// Returns an iterator over the indexes of the requested transactions in the block, the
+    /// differential encoding is already decoded.
+    pub fn indexes(&self) -> IndexIterator<'a> {
+        let mut consumed = 32;
+        scan_len(&self.slice[consumed..], &mut consumed).expect("len granted by parsing");
+        IndexIterator {
+            remaining: self.n,
+            next: 0,
+            slice: &self.slice[consumed..],
+        }
+    }
+}
+
+impl<'a> AsRef<[u8]> for BlockTxnRequest<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.slice
+    }
+}
+
+/// Iterator over the indexes of a [`BlockTxnRequest`], returned by [`BlockTxnRequest::indexes()`]
+pub struct IndexIterator<'a> {
+    remaining: usize,
+    next: u64,
+    slice: &'a [u8],
+}
+
+impl<'a> Iterator for IndexIterator<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            None
+        } else {
+            self.remaining -= 1;
+            let mut consumed = 0;
+            let index =
+                scan_index(self.slice, &mut consumed, &mut self.next).expect("granted by parsing");
+            self.slice = &self.slice[consumed..];
+            Some(index)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for IndexIterator<'a> {}
+
+/// The payload of the `blocktxn` message (BIP152), also known as `BlockTransactions`, containing
+/// the transactions requested with a [`BlockTxnRequest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockTxn<'a> {
+    slice: &'a [u8],
+    n: usize,
+}
+
+impl<'a> Visit<'a> for BlockTxn<'a> {
+    fn visit<'b, V: Visitor>(slice: &'a [u8], visit: &'b mut V) -> SResult<'a, Self> {
+        if slice.len() < 32 {
+            return Err(Error::MoreBytesNeeded);
+        }
+        let mut consumed = 32;
+        let n = scan_len(&slice[consumed..], &mut consumed)? as usize;
+        for _ in 0..n {
+            let tx = Transaction::visit(&slice[consumed..], visit)?;
+            consumed += tx.consumed();
+        }
+        Ok(ParseResult::new(
+            &slice[consumed..],
+            BlockTxn {
+                slice: &slice[..consumed],
+                n,
+            },
+        ))
+    }
+}
+
+impl<'a> BlockTxn<'a> {
+    /// Returns the hash of the block
+    pub fn block_hash(&self) -> &'a [u8] {
+        &self.slice[..32]
+    }
+    /// Returns the number of transactions
+    pub fn n(&self) -> usize {
+        self.n
+    }
+}
+
+impl<'a> AsRef<[u8]> for BlockTxn<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.slice
+    }
+}
+
+/// Read a differentially encoded index, where `next` is the index following the previous one.
+/// Like in Bitcoin Core indexes must fit in 16 bits.
+#[inline(always)]
+fn scan_index(slice: &[u8], consumed: &mut usize, next: &mut u64) -> Result<usize, Error> {
+    let diff = scan_len(slice, consumed)?;
+    let index = next.saturating_add(diff);
+    if index > u16::MAX as u64 {
+        return Err(Error::DifferentialIndexOverflow);
+    }
+    *next = index + 1;
+    Ok(index as usize)
+}
+
+#[cfg(test)]
+mod test {
+    use core::ops::ControlFlow;
+
+    use crate::{
+        bsl::{
+            p2p::{BlockTxn, BlockTxnRequest, CompactBlock},
+            Transaction,
+        },
+        EmptyVisitor, Error, ErrorContext, Parse, Visit, Visitor,
+    };
+    use bitcoin::{bip152, consensus::serialize, hashes::Hash};
+    use bitcoin_test_data::blocks::mainnet_702861;
+
+    fn block() -> bitcoin::Block {
+        bitcoin::consensus::deserialize(mainnet_702861()).unwrap()
+    }
+
+    #[test]
+    fn parse_compact_block() {
+        let block = block();
+        let prefill = [5, 6, 100];
+        let expected = bip152::HeaderAndShortIds::from_block(&block, 42, 2, &prefill).unwrap();
+        let bytes = serialize(&expected);
+
+        let compact = CompactBlock::parse(&bytes[..]).unwrap();
+        assert_eq!(compact.remaining(), &[]);
+        let compact = compact.parsed_owned();
+        assert_eq!(compact.header().as_ref(), &mainnet_702861()[..80]);
+        assert_eq!(compact.nonce(), 42);
+        assert_eq!(compact.total_transactions(), block.txdata.len());
+        assert_eq!(compact.total_prefilled(), 4);
+        assert_eq!(compact.total_short_ids(), block.txdata.len() - 4);
+        assert_eq!(compact.short_ids().len(), compact.total_short_ids());
+        for (i, short_id) in compact.short_ids().enumerate() {
+            assert_eq!(short_id, &expected.short_ids[i][..]);
+            assert_eq!(compact.short_id(i), Some(short_id));
+        }
+        assert_eq!(compact.short_id(compact.total_short_ids()), None);
+
+        let prefilled: Vec<_> = compact.prefilled_transactions().collect();
+        assert_eq!(prefilled.len(), 4);
+        for ((index, tx), expected_index) in prefilled.iter().zip([0, 5, 6, 100]) {
+            assert_eq!(*index, expected_index);
+            assert_eq!(tx.as_ref(), &serialize(&block.txdata[expected_index])[..]);
+        }
+
+        assert_eq!(
+            CompactBlock::parse(&bytes[..bytes.len() - 1]),
+            Err(Error::MoreBytesNeeded)
+        );
+        assert_eq!(
+            CompactBlock::parse(&bytes[..100]),
+            Err(Error::MoreBytesNeeded)
+        );
+    }
+
+    #[test]
+    fn visit_compact_block() {
+        let block = block();
+        let compact = bip152::HeaderAndShortIds::from_block(&block, 42, 2, &[3]).unwrap();
+        let bytes = serialize(&compact);
+
+        struct Prefilled(Vec<usize>, usize);
+        impl Visitor for Prefilled {
+            fn visit_prefilled_transaction(&mut self, index: usize) -> ControlFlow<()> {
+                self.0.push(index);
+                ControlFlow::Continue(())
+            }
+            fn visit_transaction(&mut self, _tx: &Transaction) -> ControlFlow<()> {
+                self.1 += 1;
+                ControlFlow::Continue(())
+            }
+        }
+        let mut visitor = Prefilled(vec![], 0);
+        CompactBlock::visit(&bytes[..], &mut visitor).unwrap();
+        assert_eq!(visitor.0, vec![0, 3]);
+        assert_eq!(visitor.1, 2);
+    }
+
+    #[test]
+    fn truncated_compact_block() {
+        let block = block();
+        let compact = bip152::HeaderAndShortIds::from_block(&block, 42, 2, &[5, 100]).unwrap();
+        let bytes = serialize(&compact);
+        let parsed = CompactBlock::parse(&bytes[..]).unwrap().parsed_owned();
+
+        for (index, tx) in parsed.prefilled_transactions() {
+            let tx_start = tx.as_ref().as_ptr() as usize - bytes.as_ptr() as usize;
+            for len in [3, 10, 60, tx.as_ref().len() - 2] {
+                let expected =
+                    Transaction::visit_detailed(&tx.as_ref()[..len], &mut EmptyVisitor {})
+                        .unwrap_err();
+                let err =
+                    CompactBlock::visit_detailed(&bytes[..tx_start + len], &mut EmptyVisitor {})
+                        .unwrap_err();
+                assert_eq!(err.error(), &Error::MoreBytesNeeded);
+                assert_eq!(err.offset(), tx_start + expected.offset());
+                assert_eq!(err.available(), expected.available());
+                assert_eq!(err.expected(), expected.expected());
+                let context = match expected.context() {
+                    ErrorContext::Root => ErrorContext::Transaction { tx: index },
+                    ErrorContext::Transaction { .. } => ErrorContext::Transaction { tx: index },
+                    ErrorContext::TxIn { vin, .. } => ErrorContext::TxIn { tx: index, vin },
+                    ErrorContext::TxOut { vout, .. } => ErrorContext::TxOut { tx: index, vout },
+                    ErrorContext::Witness { vin, element, .. } => ErrorContext::Witness {
+                        tx: index,
+                        vin,
+                        element,
+                    },
+                };
+                assert_eq!(err.context(), context);
+            }
+        }
+    }
+
+    #[test]
+    fn index_overflow() {
+        // block header, nonce, no short ids, 2 prefilled with index 0xFFFF and 0x10000
+        let mut bytes = mainnet_702861()[..80].to_vec();
+        bytes.extend([0u8; 8]);
+        bytes.extend([0u8, 2, 0xFD, 0xFF, 0xFF]);
+        bytes.extend(&crate::test_common::GENESIS_TX[..]);
+        bytes.push(0);
+        bytes.extend(&crate::test_common::GENESIS_TX[..]);
+        assert_eq!(
+            CompactBlock::parse(&bytes[..]),
+            Err(Error::DifferentialIndexOverflow)
+        );
+
+        let mut bytes = [0u8; 32].to_vec();
+        bytes.extend([2u8, 0xFD, 0xFF, 0xFF, 0]);
+        assert_eq!(
+            BlockTxnRequest::parse(&bytes[..]),
+            Err(Error::DifferentialIndexOverflow)
+        );
+        bytes.pop();
+        bytes[32] = 1;
+        let request = BlockTxnRequest::parse(&bytes[..]).unwrap();
+        assert_eq!(request.parsed().indexes().collect::<Vec<_>>(), vec![0xFFFF]);
+    }
+
+    #[test]
+    fn parse_block_txn() {
+        let block = block();
+        let request = bip152::BlockTransactionsRequest {
+            block_hash: block.block_hash(),
+            indexes: vec![1, 2, 10, 2499],
+        };
+        let bytes = serialize(&request);
+        let parsed = BlockTxnRequest::parse(&bytes[..]).unwrap();
+        assert_eq!(parsed.remaining(), &[]);
+        let parsed = parsed.parsed_owned();
+        assert_eq!(parsed.block_hash(), block.block_hash().as_byte_array());
+        assert_eq!(parsed.n(), 4);
+        assert_eq!(parsed.indexes().collect::<Vec<_>>(), vec![1, 2, 10, 2499]);
+
+        let response = bip152::BlockTransactions::from_request(&request, &block).unwrap();
+        let bytes = serialize(&response);
+        struct Count(usize);
+        impl Visitor for Count {
+            fn visit_transaction(&mut self, _tx: &Transaction) -> ControlFlow<()> {
+                self.0 += 1;
+                ControlFlow::Continue(())
+            }
+        }
+        let mut count = Count(0);
+        let parsed = BlockTxn::visit(&bytes[..], &mut count).unwrap();
+        assert_eq!(parsed.remaining(), &[]);
+        assert_eq!(parsed.parsed().n(), 4);
+        assert_eq!(
+            parsed.parsed().block_hash(),
+            block.block_hash().as_byte_array()
+        );
+        assert_eq!(count.0, 4);
+    }
+
+    #[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+    #[test]
+    fn short_ids() {
+        let block = block();
+        let compact = bip152::HeaderAndShortIds::from_block(&block, 7, 2, &[]).unwrap();
+        let bytes = serialize(&compact);
+        let compact = CompactBlock::parse(&bytes[..]).unwrap().parsed_owned();
+        let hasher = compact.short_id_hasher();
+
+        struct ShortIds(crate::bsl::p2p::ShortIdHasher, Vec<[u8; 6]>);
+        impl Visitor for ShortIds {
+            fn visit_transaction(&mut self, tx: &Transaction) -> ControlFlow<()> {
+                self.1.push(self.0.tx_short_id(tx));
+                ControlFlow::Continue(())
+            }
+        }
+        let mut visitor = ShortIds(hasher.clone(), vec![]);
+        crate::bsl::Block::visit(mainnet_702861(), &mut visitor).unwrap();
+        let expected: Vec<_> = compact.short_ids().collect();
+        assert_eq!(
+            &visitor.1[1..].iter().map(|e| &e[..]).collect::<Vec<_>>(),
+            &expected
+        );
+
+        let wtxid = block.txdata[1].compute_wtxid();
+        assert_eq!(&hasher.short_id(wtxid.as_byte_array())[..], expected[0]);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{
+    bsl::{scan_len, BlockHeader},
+    Parse, ParseResult, SResult, Visit, Visitor,
+};
+
+/// The payload of the `headers` message, a list of block headers each one followed by the
+/// number of transactions, which is always 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Headers<'a> {
+    slice: &'a [u8],
+    n: usize,
+}
+
+impl<'a> Visit<'a> for Headers<'a> {
+    #[inline(always)]
+    fn visit<'b, V: Visitor>(slice: &'a [u8], visit: &'b mut V) -> SResult<'a, Self> {
+        let mut consumed = 0;
+        let total_headers = scan_len(slice, &mut consumed)? as usize;
+        visit.visit_headers(total_headers);
+        for _ in 0..total_headers {
+            let header = BlockHeader::visit(&slice[consumed..], visit)?;
+            consumed += 80;
+            // like Bitcoin Core, the transaction count is read but ignored
+            scan_len(header.remaining(), &mut consumed)?;
+        }
+        Ok(ParseResult::new(
+            &slice[consumed..],
+            Headers {
+                slice: &slice[..consumed],
+                n: total_headers,
+            },
+        ))
+    }
+}
+
+impl<'a> Headers<'a> {
+    /// If there are no block headers.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+    /// The number of block headers.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+    /// Returns an iterator over [`BlockHeader`]
+    ///
+    /// If possible is better to use [`Visitor::visit_block_header`] to avoid double pass, however,
+    /// it may be convenient to iterate in case you already have validated the slice.
+    pub fn iter(&self) -> HeaderIterator<'a> {
+        let mut offset = 0;
+        scan_len(self.slice, &mut offset).expect("len granted by parsing");
+        HeaderIterator {
+            remaining: self.n,
+            slice: &self.slice[offset..],
+        }
+    }
+}
+
+impl<'a> IntoIterator for &Headers<'a> {
+    type Item = BlockHeader<'a>;
+    type IntoIter = HeaderIterator<'a>;
+
+    fn into_iter(self) -> HeaderIterator<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over the [`BlockHeader`] contained in [`Headers`], returned by [`Headers::iter()`]
+pub struct HeaderIterator<'a> {
+    remaining: usize,
+    slice: &'a [u8],
+}
+
+impl<'a> Iterator for HeaderIterator<'a> {
+    type Item = BlockHeader<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            None
+        } else {
+            self.remaining -= 1;
+            let header = BlockHeader::parse(self.slice).expect("granted from parsing");
+            let mut consumed = 0;
+            scan_len(header.remaining(), &mut consumed).expect("granted from parsing");
+            self.slice = &header.remaining()[consumed..];
+            Some(header.parsed_owned())
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for HeaderIterator<'a> {}
+
+impl<'a> AsRef<[u8]> for Headers<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.slice
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::ops::ControlFlow;
+
+    use crate::{
+        bsl::{p2p::Headers, BlockHeader},
+        test_common::GENESIS_BLOCK_HEADER,
+        Error, Parse, ParseResult, Visit, Visitor,
+    };
+    use bitcoin_test_data::blocks::mainnet_702861;
+
+    fn headers_bytes() -> Vec<u8> {
+        let headers: Vec<bitcoin::block::Header> = vec![
+            bitcoin::consensus::deserialize(&GENESIS_BLOCK_HEADER).unwrap(),
+            bitcoin::consensus::deserialize(&mainnet_702861()[..80]).unwrap(),
+        ];
+        bitcoin::consensus::serialize(&bitcoin::p2p::message::NetworkMessage::Headers(headers))
+    }
+
+    #[test]
+    fn parse_headers() {
+        let bytes = headers_bytes();
+        assert_eq!(bytes.len(), 1 + 2 * 81);
+        let headers = Headers::parse(&bytes[..]).unwrap();
+        assert_eq!(
+            headers,
+            ParseResult::new_exact(Headers {
+                slice: &bytes[..],
+                n: 2
+            })
+        );
+        let headers = headers.parsed_owned();
+        let mut iter = headers.iter();
+        assert_eq!(iter.len(), 2);
+        assert_eq!(iter.next().unwrap().as_ref(), &GENESIS_BLOCK_HEADER[..]);
+        assert_eq!(iter.next().unwrap().as_ref(), &mainnet_702861()[..80]);
+        assert!(iter.next().is_none());
+
+        for i in 0..bytes.len() {
+            assert_eq!(Headers::parse(&bytes[..i]), Err(Error::MoreBytesNeeded));
+        }
+        assert!(Headers::parse(&[0u8]).unwrap().parsed().is_empty());
+    }
+
+    #[test]
+    fn visit_headers() {
+        let bytes = headers_bytes();
+        struct Times(Vec<u32>);
+        impl Visitor for Times {
+            fn visit_headers(&mut self, total_headers: usize) {
+                assert_eq!(total_headers, 2);
+            }
+            fn visit_block_header(&mut self, header: &BlockHeader) -> ControlFlow<()> {
+                self.0.push(header.time());
+                ControlFlow::Continue(())
+            }
+        }
+        let mut times = Times(vec![]);
+        Headers::visit(&bytes[..], &mut times).unwrap();
+        assert_eq!(times.0, vec![1231006505, 1633002641]);
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn size_of() {
+        assert_eq!(std::mem::size_of::<Headers>(), 24);
+    }
+}
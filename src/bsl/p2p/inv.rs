@@ -0,0 +1,248 @@
+use core::ops::ControlFlow;
+
+use crate::{
+    bsl::scan_len, number::read_u32, slice::split_at_checked, Parse, ParseResult, SResult, Visit,
+    Visitor,
+};
+
+/// Length of a serialized inventory vector: 4 bytes type and 32 bytes hash.
+const INV_LEN: usize = 36;
+
+/// Known values of the inventory vector type, see [`Inv::kind()`]
+pub mod kind {
+    /// Any data of with this number may be ignored
+    pub const ERROR: u32 = 0;
+    /// Hash is related to a transaction
+    pub const TX: u32 = 1;
+    /// Hash is related to a data block
+    pub const BLOCK: u32 = 2;
+    /// Hash of a block header, reply with a `merkleblock` message
+    pub const FILTERED_BLOCK: u32 = 3;
+    /// Hash of a block header, reply with a `cmpctblock` message (BIP152)
+    pub const CMPCT_BLOCK: u32 = 4;
+    /// Hash is a wtxid (BIP339)
+    pub const WTX: u32 = 5;
+    /// Flag added to [`TX`] or [`BLOCK`] to request witness data (BIP144)
+    pub const WITNESS_FLAG: u32 = 1 << 30;
+    /// Transaction with witness data
+    pub const WITNESS_TX: u32 = TX | WITNESS_FLAG;
+    /// Block with witness data
+    pub const WITNESS_BLOCK: u32 = BLOCK | WITNESS_FLAG;
+    /// Filtered block with witness data
+    pub const FILTERED_WITNESS_BLOCK: u32 = FILTERED_BLOCK | WITNESS_FLAG;
+}
+
+/// An inventory vector, identifying a transaction or a block by its hash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inv<'a> {
+    slice: &'a [u8],
+}
+
+impl<'a> Parse<'a> for Inv<'a> {
+    #[inline(always)]
+    fn parse(slice: &'a [u8]) -> SResult<Self> {
+        let (slice, remaining) = split_at_checked(slice, INV_LEN)?;
+        Ok(ParseResult::new(remaining, Inv { slice }))
+    }
+}
+
+impl<'a> Inv<'a> {
+    /// Returns the type of the object identified by this inventory vector, see [`kind`]
+    pub fn kind(&self) -> u32 {
+        read_u32(self.slice).expect("slice length ensured by parsing")
+    }
+    /// Returns the hash of the object identified by this inventory vector
+    pub fn hash(&self) -> &'a [u8] {
+        &self.slice[4..]
+    }
+}
+
+impl<'a> AsRef<[u8]> for Inv<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.slice
+    }
+}
+
+/// The inventory vectors list, which is the payload of the `inv`, `getdata` and `notfound`
+/// messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invs<'a> {
+    slice: &'a [u8],
+    n: usize,
+    from: usize,
+}
+
+impl<'a> Visit<'a> for Invs<'a> {
+    #[inline(always)]
+    fn visit<'b, V: Visitor>(slice: &'a [u8], visit: &'b mut V) -> SResult<'a, Self> {
+        let mut consumed = 0;
+        let total_invs = scan_len(slice, &mut consumed)? as usize;
+        let from = consumed;
+        visit.visit_invs(total_invs);
+        for i in 0..total_invs {
+            let inv = Inv::parse(&slice[consumed..])?;
+            consumed += INV_LEN;
+            let inv = inv.parsed();
+            if let ControlFlow::Break(_) = visit.visit_inv(i, inv.kind(), inv.hash()) {
+                return Err(crate::Error::VisitBreak);
+            }
+        }
+        Ok(ParseResult::new(
+            &slice[consumed..],
+            Invs {
+                slice: &slice[..consumed],
+                n: total_invs,
+                from,
+            },
+        ))
+    }
+}
+
+impl<'a> Invs<'a> {
+    /// If there are no inventory vectors.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+    /// The number of inventory vectors.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+    /// Returns the inventory vector at position `i` if it exists.
+    ///
+    /// Since inventory vectors have fixed length, this doesn't require a pass on the slice.
+    pub fn get(&self, i: usize) -> Option<Inv<'a>> {
+        if i >= self.n {
+            return None;
+        }
+        let begin = self.from + i * INV_LEN;
+        Some(Inv {
+            slice: &self.slice[begin..begin + INV_LEN],
+        })
+    }
+    /// Returns an iterator over [`Inv`]
+    pub fn iter(&self) -> InvIterator<'a> {
+        InvIterator {
+            chunks: self.slice[self.from..].chunks_exact(INV_LEN),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &Invs<'a> {
+    type Item = Inv<'a>;
+    type IntoIter = InvIterator<'a>;
+
+    fn into_iter(self) -> InvIterator<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over the [`Inv`] contained in [`Invs`], returned by [`Invs::iter()`]
+pub struct InvIterator<'a> {
+    chunks: core::slice::ChunksExact<'a, u8>,
+}
+
+impl<'a> Iterator for InvIterator<'a> {
+    type Item = Inv<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.chunks.next().map(|slice| Inv { slice })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chunks.size_hint()
+    }
+}
+
+impl<'a> ExactSizeIterator for InvIterator<'a> {}
+
+impl<'a> AsRef<[u8]> for Invs<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.slice
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::ops::ControlFlow;
+
+    use crate::{
+        bsl::p2p::{inv::kind, Inv, Invs},
+        Error, Parse, ParseResult, Visit, Visitor,
+    };
+    use bitcoin::{hashes::Hash, p2p::message_blockdata::Inventory};
+
+    fn invs_bytes() -> Vec<u8> {
+        let invs = vec![
+            Inventory::WitnessTransaction(bitcoin::Txid::from_byte_array([1u8; 32])),
+            Inventory::Block(bitcoin::BlockHash::from_byte_array([2u8; 32])),
+            Inventory::WTx(bitcoin::Wtxid::from_byte_array([3u8; 32])),
+        ];
+        bitcoin::consensus::serialize(&invs)
+    }
+
+    #[test]
+    fn parse_invs() {
+        let bytes = invs_bytes();
+        let invs = Invs::parse(&bytes[..]).unwrap();
+        assert_eq!(
+            invs,
+            ParseResult::new_exact(Invs {
+                slice: &bytes[..],
+                n: 3,
+                from: 1
+            })
+        );
+        let invs = invs.parsed_owned();
+        let expected = [(kind::WITNESS_TX, 1u8), (kind::BLOCK, 2), (kind::WTX, 3)];
+        for (i, inv) in invs.iter().enumerate() {
+            assert_eq!(inv.kind(), expected[i].0);
+            assert_eq!(inv.hash(), &[expected[i].1; 32]);
+            assert_eq!(invs.get(i), Some(inv));
+        }
+        assert_eq!(invs.iter().len(), 3);
+        assert_eq!(invs.get(3), None);
+
+        assert_eq!(
+            Invs::parse(&bytes[..bytes.len() - 1]),
+            Err(Error::MoreBytesNeeded)
+        );
+        assert!(Invs::parse(&[0u8]).unwrap().parsed().is_empty());
+
+        assert_eq!(Inv::parse(&bytes[1..36]), Err(Error::MoreBytesNeeded));
+        assert_eq!(Inv::parse(&bytes[1..]).unwrap().remaining().len(), 72);
+    }
+
+    #[test]
+    fn visit_invs() {
+        let bytes = invs_bytes();
+
+        struct FindBlock(Option<usize>);
+        impl Visitor for FindBlock {
+            fn visit_invs(&mut self, total_invs: usize) {
+                assert_eq!(total_invs, 3);
+            }
+            fn visit_inv(&mut self, i: usize, kind: u32, hash: &[u8]) -> ControlFlow<()> {
+                if kind == kind::BLOCK {
+                    assert_eq!(hash, &[2u8; 32]);
+                    self.0 = Some(i);
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            }
+        }
+        let mut visitor = FindBlock(None);
+        assert_eq!(
+            Invs::visit(&bytes[..], &mut visitor),
+            Err(Error::VisitBreak)
+        );
+        assert_eq!(visitor.0, Some(1));
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn size_of() {
+        assert_eq!(std::mem::size_of::<Invs>(), 32);
+    }
+}
@@ -0,0 +1,237 @@
+use core::ops::ControlFlow;
+
+use crate::{number::read_u32, Error, ParseResult, SResult, Visit, Visitor};
+
+/// Length of the message header: magic, command, payload length and checksum.
+const HEADER_LEN: usize = 24;
+
+/// Maximum payload length accepted, same as `MAX_SIZE` in Bitcoin Core.
+const MAX_PAYLOAD_LEN: usize = 0x0200_0000;
+
+/// A message of the Bitcoin peer to peer network protocol, it contains a 24 bytes header followed
+/// by the payload:
+///
+/// * 4 bytes magic, identifying the network
+/// * 12 bytes command, ASCII string padded with NUL bytes
+/// * 4 bytes payload length
+/// * 4 bytes checksum, the first 4 bytes of the double sha256 of the payload
+///
+/// The checksum is verified during parsing only if a hashing feature (`bitcoin_hashes` or `sha2`)
+/// is enabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message<'a> {
+    slice: &'a [u8],
+}
+
+impl<'a> Visit<'a> for Message<'a> {
+    #[inline(always)]
+    fn visit<'b, V: Visitor>(slice: &'a [u8], visit: &'b mut V) -> SResult<'a, Self> {
+        if slice.len() < HEADER_LEN {
+            return Err(Error::MoreBytesNeeded);
+        }
+        if !is_command_valid(&slice[4..16]) {
+            return Err(Error::InvalidCommand);
+        }
+        let payload_len = read_u32(&slice[16..20]).expect("initial check 24") as usize;
+        if payload_len > MAX_PAYLOAD_LEN {
+            return Err(Error::MessageTooLarge);
+        }
+        let consumed = HEADER_LEN + payload_len;
+        if slice.len() < consumed {
+            return Err(Error::MoreBytesNeeded);
+        }
+        let message = Message {
+            slice: &slice[..consumed],
+        };
+
+        #[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+        if crate::hashes::sha256d(&[message.payload()])[..4] != *message.checksum() {
+            return Err(Error::InvalidChecksum);
+        }
+
+        if let ControlFlow::Break(_) = visit.visit_message(&message) {
+            return Err(Error::VisitBreak);
+        }
+        Ok(ParseResult::new(&slice[consumed..], message))
+    }
+}
+
+impl<'a> Message<'a> {
+    /// Visit the message like [`Message::visit()`] but return [`Error::UnexpectedMagic`] if the
+    /// message doesn't start with the given `magic`, see [`super::magic`] for known values.
+    ///
+    /// The magic is checked as soon as the first 4 bytes are available, so that a peer speaking
+    /// another network is detected without waiting for a full message.
+    pub fn visit_with_magic<'b, V: Visitor>(
+        slice: &'a [u8],
+        magic: [u8; 4],
+        visit: &'b mut V,
+    ) -> SResult<'a, Self> {
+        match slice.get(..4) {
+            Some(found) if found != magic => Err(Error::UnexpectedMagic),
+            _ => Self::visit(slice, visit),
+        }
+    }
+
+    /// Parse the message like [`crate::Parse::parse()`] checking the network `magic`, see
+    /// [`Message::visit_with_magic()`].
+    pub fn parse_with_magic(slice: &'a [u8], magic: [u8; 4]) -> SResult<'a, Self> {
+        Self::visit_with_magic(slice, magic, &mut crate::EmptyVisitor {})
+    }
+
+    /// Returns the 4 bytes identifying the network
+    pub fn magic(&self) -> &[u8] {
+        &self.slice[..4]
+    }
+
+    /// Returns the command of this message without the padding NUL bytes, eg `b"block"`.
+    pub fn command(&self) -> &[u8] {
+        let command = &self.slice[4..16];
+        let len = command.iter().position(|b| *b == 0).unwrap_or(12);
+        &command[..len]
+    }
+
+    /// Returns the checksum of this message, the first 4 bytes of the double sha256 of the payload
+    pub fn checksum(&self) -> &[u8] {
+        &self.slice[20..24]
+    }
+
+    /// Returns the payload of this message, to be parsed according to the [`Message::command()`]
+    pub fn payload(&self) -> &'a [u8] {
+        &self.slice[HEADER_LEN..]
+    }
+}
+
+impl<'a> AsRef<[u8]> for Message<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.slice
+    }
+}
+
+/// Valid commands are made of printable ASCII chars, followed only by NUL bytes.
+fn is_command_valid(command: &[u8]) -> bool {
+    let len = command
+        .iter()
+        .position(|b| *b == 0)
+        .unwrap_or(command.len());
+    command[..len].iter().all(|b| (0x20..=0x7E).contains(b))
+        && command[len..].iter().all(|b| *b == 0)
+}
+
+#[cfg(test)]
+mod test {
+    use core::ops::ControlFlow;
+
+    use crate::{
+        bsl::{
+            p2p::{magic, Message},
+            Block,
+        },
+        test_common::GENESIS_BLOCK,
+        Error, Parse, ParseResult, Visit, Visitor,
+    };
+    use hex_lit::hex;
+
+    const VERACK: [u8; 24] = hex!("f9beb4d976657261636b000000000000000000005df6e0e2");
+
+    fn block_message() -> Vec<u8> {
+        use bitcoin::hashes::{sha256d, Hash};
+        let mut message = vec![];
+        message.extend(magic::MAINNET);
+        message.extend(b"block\0\0\0\0\0\0\0");
+        message.extend((GENESIS_BLOCK.len() as u32).to_le_bytes());
+        message.extend(&sha256d::Hash::hash(&GENESIS_BLOCK)[..4]);
+        message.extend(GENESIS_BLOCK);
+        message
+    }
+
+    #[test]
+    fn parse_message() {
+        let message = Message::parse(&VERACK[..]).unwrap();
+        assert_eq!(message, ParseResult::new_exact(Message { slice: &VERACK }));
+        assert_eq!(message.parsed().magic(), magic::MAINNET);
+        assert_eq!(message.parsed().command(), b"verack");
+        assert_eq!(message.parsed().payload(), &[]);
+
+        let bytes = block_message();
+        let message = Message::parse(&bytes[..]).unwrap().parsed_owned();
+        assert_eq!(message.command(), b"block");
+        let block = Block::parse(message.payload()).unwrap();
+        assert_eq!(block.remaining(), &[]);
+        assert_eq!(block.parsed().total_transactions(), 1);
+
+        for i in 0..bytes.len() {
+            assert_eq!(Message::parse(&bytes[..i]), Err(Error::MoreBytesNeeded));
+        }
+
+        let mut with_remaining = bytes.clone();
+        with_remaining.push(1);
+        let message = Message::parse(&with_remaining[..]).unwrap();
+        assert_eq!(message.remaining(), &[1]);
+        assert_eq!(message.consumed(), bytes.len());
+    }
+
+    #[test]
+    fn invalid_message() {
+        let mut bytes = VERACK;
+        bytes[12] = b'a'; // char after NUL
+        assert_eq!(Message::parse(&bytes[..]), Err(Error::InvalidCommand));
+
+        let mut bytes = VERACK;
+        bytes[4] = 0x07; // non printable
+        assert_eq!(Message::parse(&bytes[..]), Err(Error::InvalidCommand));
+
+        let mut bytes = VERACK;
+        bytes[19] = 0x02; // payload length 0x02000000 + 1
+        bytes[16] = 0x01;
+        assert_eq!(Message::parse(&bytes[..]), Err(Error::MessageTooLarge));
+
+        #[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+        {
+            let mut bytes = VERACK;
+            bytes[23] = 0;
+            assert_eq!(Message::parse(&bytes[..]), Err(Error::InvalidChecksum));
+        }
+    }
+
+    #[test]
+    fn message_magic() {
+        assert!(Message::parse_with_magic(&VERACK[..], magic::MAINNET).is_ok());
+        assert_eq!(
+            Message::parse_with_magic(&VERACK[..], magic::TESTNET3),
+            Err(Error::UnexpectedMagic)
+        );
+        assert_eq!(
+            Message::parse_with_magic(&VERACK[..4], magic::REGTEST),
+            Err(Error::UnexpectedMagic)
+        );
+        assert_eq!(
+            Message::parse_with_magic(&VERACK[..3], magic::REGTEST),
+            Err(Error::MoreBytesNeeded)
+        );
+    }
+
+    #[test]
+    fn visit_message() {
+        struct Commands(Vec<Vec<u8>>);
+        impl Visitor for Commands {
+            fn visit_message(&mut self, message: &Message) -> ControlFlow<()> {
+                self.0.push(message.command().to_vec());
+                ControlFlow::Continue(())
+            }
+        }
+        let mut bytes = VERACK.to_vec();
+        bytes.extend(block_message());
+        let mut commands = Commands(vec![]);
+        let first = Message::visit(&bytes[..], &mut commands).unwrap();
+        let second = Message::visit(first.remaining(), &mut commands).unwrap();
+        assert_eq!(second.remaining(), &[]);
+        assert_eq!(commands.0, vec![b"verack".to_vec(), b"block".to_vec()]);
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn size_of() {
+        assert_eq!(std::mem::size_of::<Message>(), 16);
+    }
+}
@@ -0,0 +1,39 @@
+//! Objects of the Bitcoin peer to peer network protocol.
+//!
+//! Every network message is wrapped in a [`Message`] envelope, the [`Message::payload()`] can then
+//! be parsed with the type specific to the [`Message::command()`], for example a `block` payload is
+//! a [`crate::bsl::Block`].
+
+mod address;
+mod address_v2;
+mod compact_block;
+mod headers;
+mod inv;
+mod message;
+mod version;
+
+pub use address::{Address, AddressIterator, Addrs, NetAddr};
+pub use address_v2::{network_id, AddressV2, AddressV2Iterator, AddrsV2};
+#[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+pub use compact_block::ShortIdHasher;
+pub use compact_block::{
+    BlockTxn, BlockTxnRequest, CompactBlock, IndexIterator, PrefilledIterator,
+};
+pub use headers::{HeaderIterator, Headers};
+pub use inv::{kind as inv_kind, Inv, InvIterator, Invs};
+pub use message::Message;
+pub use version::Version;
+
+/// The 4 bytes prefixing every network message, used also in block files, identifying the network.
+pub mod magic {
+    /// Bitcoin mainnet
+    pub const MAINNET: [u8; 4] = [0xF9, 0xBE, 0xB4, 0xD9];
+    /// Bitcoin testnet3
+    pub const TESTNET3: [u8; 4] = [0x0B, 0x11, 0x09, 0x07];
+    /// Bitcoin testnet4
+    pub const TESTNET4: [u8; 4] = [0x1C, 0x16, 0x3F, 0x28];
+    /// Bitcoin default signet
+    pub const SIGNET: [u8; 4] = [0x0A, 0x03, 0xCF, 0x40];
+    /// Bitcoin regtest
+    pub const REGTEST: [u8; 4] = [0xFA, 0xBF, 0xB5, 0xDA];
+}
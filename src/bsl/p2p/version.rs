@@ -0,0 +1,166 @@
+use crate::{
+    bsl::{p2p::NetAddr, Script},
+    number::{read_i32, read_i64, read_u64},
+    Error, Parse, ParseResult, SResult,
+};
+
+/// Length of the fields preceding the user agent: version, services, timestamp, receiver, sender
+/// and nonce.
+const FIXED_LEN: usize = 4 + 8 + 8 + 26 + 26 + 8;
+
+/// The payload of the `version` message, the first message sent by a peer when connecting.
+///
+/// The relay flag is optional (introduced with BIP37) and, like Bitcoin Core does, it's read
+/// whenever a byte follows the start height, so this is meant to be parsed from the exact
+/// [`super::Message::payload()`]. Any byte after the relay flag is left in
+/// [`ParseResult::remaining()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Version<'a> {
+    slice: &'a [u8],
+    user_agent: Script<'a>,
+}
+
+impl<'a> Parse<'a> for Version<'a> {
+    #[inline(always)]
+    fn parse(slice: &'a [u8]) -> SResult<Self> {
+        if slice.len() < FIXED_LEN {
+            return Err(Error::MoreBytesNeeded);
+        }
+        let user_agent = Script::parse(&slice[FIXED_LEN..])?;
+        let mut consumed = FIXED_LEN + user_agent.consumed();
+        read_i32(user_agent.remaining())?;
+        consumed += 4;
+        if slice.len() > consumed {
+            consumed += 1; // relay flag
+        }
+        Ok(ParseResult::new(
+            &slice[consumed..],
+            Version {
+                slice: &slice[..consumed],
+                user_agent: user_agent.parsed_owned(),
+            },
+        ))
+    }
+}
+
+impl<'a> Version<'a> {
+    /// Returns the protocol version of the peer
+    pub fn version(&self) -> i32 {
+        read_i32(self.slice).expect("slice length ensured by parsing")
+    }
+    /// Returns the service bits of the peer
+    pub fn services(&self) -> u64 {
+        read_u64(&self.slice[4..]).expect("slice length ensured by parsing")
+    }
+    /// Returns the UNIX timestamp of the peer
+    pub fn timestamp(&self) -> i64 {
+        read_i64(&self.slice[12..]).expect("slice length ensured by parsing")
+    }
+    /// Returns the network address of the node receiving this message
+    pub fn receiver(&self) -> NetAddr<'a> {
+        NetAddr::parse(&self.slice[20..])
+            .expect("slice length ensured by parsing")
+            .parsed_owned()
+    }
+    /// Returns the network address of the node sending this message
+    pub fn sender(&self) -> NetAddr<'a> {
+        NetAddr::parse(&self.slice[46..])
+            .expect("slice length ensured by parsing")
+            .parsed_owned()
+    }
+    /// Returns the random nonce used to detect connections to self
+    pub fn nonce(&self) -> u64 {
+        read_u64(&self.slice[72..]).expect("slice length ensured by parsing")
+    }
+    /// Returns the user agent (BIP14) bytes, usually an ASCII string like `/Satoshi:27.0.0/`
+    pub fn user_agent(&self) -> &[u8] {
+        self.user_agent.script()
+    }
+    /// Returns the height of the best block of the peer
+    pub fn start_height(&self) -> i32 {
+        let from = FIXED_LEN + self.user_agent.as_ref().len();
+        read_i32(&self.slice[from..]).expect("slice length ensured by parsing")
+    }
+    /// Returns whether the peer wants to receive transactions announcements, `true` if the flag
+    /// is missing
+    pub fn relay(&self) -> bool {
+        let from = FIXED_LEN + self.user_agent.as_ref().len() + 4;
+        self.slice.get(from).map(|b| *b != 0).unwrap_or(true)
+    }
+}
+
+impl<'a> AsRef<[u8]> for Version<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.slice
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{bsl::p2p::Version, Error, Parse};
+    use bitcoin::p2p::{address::Address, message_network::VersionMessage, ServiceFlags};
+
+    fn version_message(relay: bool) -> VersionMessage {
+        let mut version = VersionMessage::new(
+            ServiceFlags::NETWORK | ServiceFlags::WITNESS,
+            1_700_000_000,
+            Address::new(&"1.2.3.4:8333".parse().unwrap(), ServiceFlags::NONE),
+            Address::new(&"[::1]:18444".parse().unwrap(), ServiceFlags::WITNESS),
+            0x0102030405060708,
+            "/Satoshi:27.0.0/".to_string(),
+            850_000,
+        );
+        version.relay = relay;
+        version
+    }
+
+    #[test]
+    fn parse_version() {
+        for relay in [true, false] {
+            let message = version_message(relay);
+            let bytes = bitcoin::consensus::serialize(&message);
+            let version = Version::parse(&bytes[..]).unwrap();
+            assert_eq!(version.remaining(), &[]);
+            assert_eq!(version.consumed(), bytes.len());
+            let version = version.parsed_owned();
+
+            assert_eq!(version.version(), message.version as i32);
+            assert_eq!(version.services(), message.services.to_u64());
+            assert_eq!(version.timestamp(), 1_700_000_000);
+            assert_eq!(version.receiver().port(), 8333);
+            assert_eq!(version.receiver().services(), 0);
+            assert_eq!(
+                version.receiver().ip(),
+                &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 1, 2, 3, 4]
+            );
+            assert_eq!(version.sender().port(), 18444);
+            assert_eq!(version.sender().services(), ServiceFlags::WITNESS.to_u64());
+            assert_eq!(version.nonce(), 0x0102030405060708);
+            assert_eq!(version.user_agent(), b"/Satoshi:27.0.0/");
+            assert_eq!(version.start_height(), 850_000);
+            assert_eq!(version.relay(), relay);
+
+            // without the relay flag
+            let version = Version::parse(&bytes[..bytes.len() - 1]).unwrap();
+            assert_eq!(version.remaining(), &[]);
+            assert!(version.parsed().relay());
+            assert_eq!(version.parsed().start_height(), 850_000);
+
+            for i in 0..bytes.len() - 1 {
+                assert_eq!(Version::parse(&bytes[..i]), Err(Error::MoreBytesNeeded));
+            }
+        }
+    }
+
+    #[test]
+    fn trailing_bytes() {
+        let bytes = bitcoin::consensus::serialize(&version_message(false));
+        let mut trailing = bytes.clone();
+        trailing.extend([0u8, 1]);
+        let version = Version::parse(&trailing[..]).unwrap();
+        assert_eq!(version.consumed(), bytes.len());
+        assert_eq!(version.remaining(), &[0u8, 1][..]);
+        assert!(!version.parsed().relay());
+        assert_eq!(version.parsed().start_height(), 850_000);
+    }
+}
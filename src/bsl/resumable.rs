@@ -0,0 +1,272 @@
+use crate::{
+    bsl::{compact_size_len_of_prefix, scan_len, Block, BlockHeader, Transaction},
+    EmptyVisitor, Error, ParseResult, SResult, Visit, Visitor,
+};
+
+/// Parses a [`Block`] received in chunks, without parsing again the transactions already
+/// complete when more bytes are appended.
+///
+/// Every call to [`BlockParser::resume()`] must be given the block received so far, starting
+/// from its first byte. The visitor is called once for every object: a transaction is visited
+/// only when it's complete, which requires parsing it twice, the first time to find its end.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockParser {
+    /// The length of the header, of the number of transactions and of the complete transactions
+    consumed: usize,
+    total_txs: Option<usize>,
+    txs_done: usize,
+    needed: usize,
+}
+
+impl BlockParser {
+    /// Creates the parser for a block with no bytes received yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Continues parsing `slice` from the last complete transaction, calling the methods on
+    /// `visit` for the objects completed by the newly appended bytes.
+    ///
+    /// Returns [`Error::MoreBytesNeeded`] if the block is not complete, in this case the bytes
+    /// needed at minimum to make progress are returned by [`BlockParser::needed()`]. Any other error
+    /// is final.
+    pub fn resume<'a, V: Visitor>(
+        &mut self,
+        slice: &'a [u8],
+        visit: &mut V,
+    ) -> SResult<'a, Block<'a>> {
+        if slice.len() < self.needed {
+            return Err(Error::MoreBytesNeeded);
+        }
+        let total_txs = match self.total_txs {
+            Some(total_txs) => total_txs,
+            None => {
+                // check header and number of transactions are complete before visiting the header
+                let len_size = slice.get(80).map(compact_size_len_of_prefix).unwrap_or(1);
+                if slice.len() < 80 + len_size {
+                    self.needed = 80 + len_size;
+                    return Err(Error::MoreBytesNeeded);
+                }
+                let header = BlockHeader::visit(slice, visit)?;
+                let mut consumed = 80;
+                let total_txs = scan_len(header.remaining(), &mut consumed)? as usize;
+                visit.visit_block_begin(total_txs);
+                self.consumed = consumed;
+                self.total_txs = Some(total_txs);
+                total_txs
+            }
+        };
+
+        while self.txs_done < total_txs {
+            let len =
+                match Transaction::visit_detailed(&slice[self.consumed..], &mut EmptyVisitor {}) {
+                    Ok(tx) => tx.consumed(),
+                    Err(e) => {
+                        if e.error() == &Error::MoreBytesNeeded {
+                            self.needed = self.consumed + e.needed();
+                        }
+                        return Err(e.into());
+                    }
+                };
+            let end = self.consumed + len;
+            Transaction::visit(&slice[self.consumed..end], visit)?;
+            self.consumed = end;
+            self.txs_done += 1;
+            self.needed = end;
+        }
+
+        let (slice, remaining) = slice.split_at(self.consumed);
+        Ok(ParseResult::new(
+            remaining,
+            Block::from_parsed(slice, total_txs),
+        ))
+    }
+
+    /// The length of the slice needed at minimum to make progress
+    pub fn needed(&self) -> usize {
+        self.needed
+    }
+
+    /// The bytes already parsed: the header, the number of transactions and the complete
+    /// transactions
+    pub fn consumed(&self) -> usize {
+        self.consumed
+    }
+
+    /// The number of transactions in the block, once the header and the number are received
+    pub fn total_transactions(&self) -> Option<usize> {
+        self.total_txs
+    }
+
+    /// The number of complete transactions already visited
+    pub fn transactions_done(&self) -> usize {
+        self.txs_done
+    }
+}
+
+/// Parses a [`Transaction`] received in chunks, without parsing it again until the bytes needed at
+/// minimum are appended.
+///
+/// Every call to [`TransactionParser::resume()`] must be given the transaction received so far,
+/// starting from its first byte. The visitor is called only when the transaction is complete.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionParser {
+    needed: usize,
+}
+
+impl TransactionParser {
+    /// Creates the parser for a transaction with no bytes received yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses `slice` if it contains the bytes needed at minimum, calling the methods on `visit`
+    /// once the transaction is complete.
+    ///
+    /// Returns [`Error::MoreBytesNeeded`] if the transaction is not complete, in this case the
+    /// bytes needed at minimum are returned by [`TransactionParser::needed()`]. Any other error is
+    /// final.
+    pub fn resume<'a, V: Visitor>(
+        &mut self,
+        slice: &'a [u8],
+        visit: &mut V,
+    ) -> SResult<'a, Transaction<'a>> {
+        if slice.len() < self.needed {
+            return Err(Error::MoreBytesNeeded);
+        }
+        match Transaction::visit_detailed(slice, &mut EmptyVisitor {}) {
+            Ok(tx) => {
+                self.needed = tx.consumed();
+                Transaction::visit(slice, visit)
+            }
+            Err(e) => {
+                if e.error() == &Error::MoreBytesNeeded {
+                    self.needed = e.needed();
+                }
+                Err(e.into())
+            }
+        }
+    }
+
+    /// The length of the slice needed at minimum to make progress
+    pub fn needed(&self) -> usize {
+        self.needed
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::ops::ControlFlow;
+
+    use bitcoin_test_data::blocks::mainnet_702861;
+    use hex_lit::hex;
+
+    use super::*;
+    use crate::{test_common::GENESIS_BLOCK, Parse};
+
+    #[derive(Default)]
+    struct Count {
+        headers: usize,
+        txs: usize,
+        tx_ins: usize,
+    }
+    impl Visitor for Count {
+        fn visit_block_header(&mut self, _header: &BlockHeader) -> ControlFlow<()> {
+            self.headers += 1;
+            ControlFlow::Continue(())
+        }
+        fn visit_transaction(&mut self, _tx: &Transaction) -> ControlFlow<()> {
+            self.txs += 1;
+            ControlFlow::Continue(())
+        }
+        fn visit_tx_in(&mut self, _vin: usize, _tx_in: &crate::bsl::TxIn) -> ControlFlow<()> {
+            self.tx_ins += 1;
+            ControlFlow::Continue(())
+        }
+    }
+
+    #[test]
+    fn block_in_chunks() {
+        for chunk in [1, 1000, 65536] {
+            let block = mainnet_702861();
+            let mut parser = BlockParser::new();
+            let mut count = Count::default();
+            let mut end = 0;
+            let parsed = loop {
+                end = (end + chunk).min(block.len());
+                match parser.resume(&block[..end], &mut count) {
+                    Ok(parsed) => break parsed,
+                    Err(Error::MoreBytesNeeded) => assert!(parser.needed() > end),
+                    Err(e) => panic!("{:?}", e),
+                }
+            };
+            assert_eq!(parsed.parsed(), Block::parse(block).unwrap().parsed());
+            assert_eq!(parsed.remaining(), &[][..]);
+            let mut expected = Count::default();
+            Block::visit(block, &mut expected).unwrap();
+            assert_eq!(count.headers, 1);
+            assert_eq!(count.txs, 2500);
+            assert_eq!(count.tx_ins, expected.tx_ins);
+            assert_eq!(parser.consumed(), block.len());
+            assert_eq!(parser.transactions_done(), 2500);
+        }
+    }
+
+    #[test]
+    fn needed() {
+        let mut parser = BlockParser::new();
+        let mut count = Count::default();
+        assert!(parser.resume(&GENESIS_BLOCK[..10], &mut count).is_err());
+        assert_eq!(parser.needed(), 81);
+        assert_eq!(parser.total_transactions(), None);
+
+        // input script length is known
+        assert!(parser.resume(&GENESIS_BLOCK[..130], &mut count).is_err());
+        assert_eq!(parser.total_transactions(), Some(1));
+        assert_eq!(parser.needed(), 86 + 36 + 1 + 77 + 4);
+        assert_eq!(count.headers, 1);
+
+        assert!(parser.resume(&GENESIS_BLOCK[..200], &mut count).is_err());
+        assert_eq!(parser.consumed(), 81);
+        assert_eq!(count.tx_ins, 0);
+
+        let mut block = GENESIS_BLOCK.to_vec();
+        block.push(0);
+        let parsed = parser.resume(&block[..], &mut count).unwrap();
+        assert_eq!(parsed.remaining(), &[0u8][..]);
+        assert_eq!((count.headers, count.txs, count.tx_ins), (1, 1, 1));
+    }
+
+    #[test]
+    fn transaction_in_chunks() {
+        let tx = hex!("010000000001010000000000000000000000000000000000000000000000000000000000000000ffffffff3603da1b0e00045503bd5704c7dd8a0d0ced13bb5785010800000000000a636b706f6f6c122f4e696e6a61506f6f6c2f5345475749542fffffffff02b4e5a212000000001976a914876fbb82ec05caa6af7a3b5e5a983aae6c6cc6d688ac0000000000000000266a24aa21a9edf91c46b49eb8a29089980f02ee6b57e7d63d33b18b4fddac2bcd7db2a39837040120000000000000000000000000000000000000000000000000000000000000000000000000");
+        let mut parser = TransactionParser::new();
+        let mut count = Count::default();
+        let mut parsed_at = vec![];
+        for end in 0..tx.len() {
+            let needed = parser.needed();
+            assert_eq!(
+                parser.resume(&tx[..end], &mut count),
+                Err(Error::MoreBytesNeeded)
+            );
+            if parser.needed() != needed {
+                parsed_at.push(end);
+            }
+            assert!(parser.needed() > end);
+        }
+        assert_eq!(count.txs, 0);
+        // parsed again only when the needed bytes are available, not at every appended byte
+        assert!(parsed_at.len() < 30, "parsed {} times", parsed_at.len());
+
+        let parsed = parser.resume(&tx[..], &mut count).unwrap();
+        assert_eq!(parsed.parsed().as_ref(), &tx[..]);
+        assert_eq!((count.txs, count.tx_ins), (1, 1));
+
+        let mut tx = tx;
+        tx[5] = 2;
+        assert_eq!(
+            parser.resume(&tx[..], &mut count),
+            Err(Error::UnknownSegwitFlag(2))
+        );
+    }
+}
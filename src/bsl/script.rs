@@ -1,5 +1,5 @@
-use super::scan_len;
-use crate::{slice::split_at_checked, Parse, ParseResult, SResult};
+use super::{opcodes::*, scan_len};
+use crate::{slice::split_at_checked, Error, Parse, ParseResult, SResult};
 
 /// The Script, this type could be found in transaction outputs as `script_pubkey` or in transaction
 /// inputs as `script_sig`.
@@ -33,6 +33,11 @@ impl<'a> Script<'a> {
     pub fn script(&self) -> &[u8] {
         &self.slice[self.from..]
     }
+
+    /// Returns an iterator over the instructions of the script
+    pub fn instructions(&self) -> Instructions<'a> {
+        Instructions::new(&self.slice[self.from..])
+    }
 }
 
 impl<'a> AsRef<[u8]> for Script<'a> {
@@ -41,9 +46,132 @@ impl<'a> AsRef<[u8]> for Script<'a> {
     }
 }
 
+/// An instruction of a script: an opcode pushing data or any other opcode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction<'a> {
+    /// An opcode pushing `data` on the stack: `OP_0`, the direct pushes of 1 to 75 bytes and
+    /// `OP_PUSHDATA1/2/4`
+    Push {
+        /// The opcode of the push
+        opcode: u8,
+        /// The pushed bytes
+        data: &'a [u8],
+    },
+    /// Any opcode not followed by data, including `OP_1NEGATE` and `OP_1` to `OP_16`
+    Op(u8),
+}
+
+impl<'a> Instruction<'a> {
+    /// Returns the opcode of this instruction
+    pub fn opcode(&self) -> u8 {
+        match self {
+            Instruction::Push { opcode, .. } => *opcode,
+            Instruction::Op(opcode) => *opcode,
+        }
+    }
+
+    /// Returns the pushed bytes if this instruction is a push
+    pub fn push_bytes(&self) -> Option<&'a [u8]> {
+        match self {
+            Instruction::Push { data, .. } => Some(data),
+            Instruction::Op(_) => None,
+        }
+    }
+
+    /// Returns `true` if the data is pushed with the smallest possible opcode, as required by
+    /// the `MINIMALDATA` policy. Instructions not pushing data are always minimal.
+    pub fn is_minimal_push(&self) -> bool {
+        let (opcode, data) = match self {
+            Instruction::Push { opcode, data } => (*opcode, *data),
+            Instruction::Op(_) => return true,
+        };
+        match data.len() {
+            0 => opcode == OP_0,
+            1 if (1..=16).contains(&data[0]) || data[0] == 0x81 => false, // OP_1..OP_16, OP_1NEGATE
+            len @ 1..=75 => opcode as usize == len,
+            76..=255 => opcode == OP_PUSHDATA1,
+            256..=65535 => opcode == OP_PUSHDATA2,
+            _ => true,
+        }
+    }
+}
+
+/// Iterator over the [`Instruction`]s of a script, created with [`Instructions::new()`] on the
+/// bytes returned by [`crate::bsl::TxIn::script_sig()`], [`crate::bsl::TxOut::script_pubkey()`],
+/// a witness element, or with [`Script::instructions()`].
+///
+/// A push requiring more bytes than the ones left in the script returns
+/// [`Error::TruncatedPush`] with the offset of the push opcode, then the iteration ends.
+#[derive(Debug, Clone)]
+pub struct Instructions<'a> {
+    script: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Instructions<'a> {
+    /// Creates the iterator over the instructions of `script`
+    pub fn new(script: &'a [u8]) -> Self {
+        Instructions { script, offset: 0 }
+    }
+
+    /// Returns the offset in the script of the next instruction
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn next_instruction(&mut self) -> Result<Instruction<'a>, Error> {
+        let start = self.offset;
+        let remaining = &self.script[start..];
+        let opcode = remaining[0];
+        let (len_bytes, len) = match opcode {
+            OP_PUSHDATA1 => (1, remaining.get(1).map(|l| *l as usize)),
+            OP_PUSHDATA2 => (
+                2,
+                remaining
+                    .get(1..3)
+                    .map(|l| u16::from_le_bytes(l.try_into().expect("2 bytes")) as usize),
+            ),
+            OP_PUSHDATA4 => (
+                4,
+                remaining
+                    .get(1..5)
+                    .map(|l| u32::from_le_bytes(l.try_into().expect("4 bytes")) as usize),
+            ),
+            0..=75 => (0, Some(opcode as usize)),
+            _ => {
+                self.offset += 1;
+                return Ok(Instruction::Op(opcode));
+            }
+        };
+        let data_start: usize = 1 + len_bytes;
+        let data = len
+            .and_then(|len| remaining.get(data_start..data_start.checked_add(len)?))
+            .ok_or(Error::TruncatedPush(start as u32))?;
+        self.offset += data_start + data.len();
+        Ok(Instruction::Push { opcode, data })
+    }
+}
+
+impl<'a> Iterator for Instructions<'a> {
+    type Item = Result<Instruction<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.script.len() {
+            return None;
+        }
+        let result = self.next_instruction();
+        if result.is_err() {
+            self.offset = self.script.len();
+        }
+        Some(result)
+    }
+}
+
 #[cfg(test)]
 mod test {
+    use super::{Instruction, Instructions};
     use crate::{bsl::Script, Error, Parse};
+    use hex_lit::hex;
 
     fn check(slice: &[u8], script_slice: &[u8]) {
         let script = Script::parse(slice);
@@ -62,4 +190,105 @@ mod test {
         assert_eq!(Script::parse(&[1u8]), Err(Error::MoreBytesNeeded));
         assert_eq!(Script::parse(&[100u8]), Err(Error::MoreBytesNeeded));
     }
+
+    #[test]
+    fn instructions() {
+        // p2pkh
+        let script = hex!("76a914751e76e8199196d454941c45d1b3a323f1433bd688ac");
+        let instructions: Vec<_> = Instructions::new(&script).map(Result::unwrap).collect();
+        assert_eq!(instructions.len(), 5);
+        assert_eq!(instructions[0], Instruction::Op(0x76));
+        assert_eq!(instructions[2].opcode(), 20);
+        assert_eq!(instructions[2].push_bytes(), Some(&script[3..23]));
+        assert!(instructions.iter().all(Instruction::is_minimal_push));
+
+        let bytes = [&[script.len() as u8][..], &script[..]].concat();
+        let script = Script::parse(&bytes).unwrap().parsed_owned();
+        assert_eq!(script.instructions().count(), 5);
+
+        // OP_0, OP_PUSHDATA1 of 2 bytes, OP_PUSHDATA2 of 1 byte, OP_PUSHDATA4 of 0 bytes, OP_1
+        let script = hex!("004c0201024d0100034e0000000051");
+        let mut iter = Instructions::new(&script);
+        let expected = [
+            (0x00, &[][..], true),
+            (0x4c, &[1, 2], false),
+            (0x4d, &[3], false),
+        ];
+        for (opcode, data, minimal) in expected {
+            let instruction = iter.next().unwrap().unwrap();
+            assert_eq!(instruction, Instruction::Push { opcode, data });
+            assert_eq!(instruction.is_minimal_push(), minimal);
+        }
+        assert_eq!(iter.offset(), 9);
+        let instruction = iter.next().unwrap().unwrap();
+        assert_eq!(instruction.push_bytes(), Some(&[][..]));
+        assert!(!instruction.is_minimal_push());
+        assert_eq!(iter.next(), Some(Ok(Instruction::Op(0x51))));
+        assert_eq!(iter.next(), None);
+
+        // direct push of a small int should use OP_1..OP_16
+        let instruction = Instructions::new(&[1, 5]).next().unwrap().unwrap();
+        assert!(!instruction.is_minimal_push());
+        let instruction = Instructions::new(&[1, 17]).next().unwrap().unwrap();
+        assert!(instruction.is_minimal_push());
+    }
+
+    #[test]
+    fn truncated_push() {
+        for script in [
+            &hex!("51030102")[..],
+            &hex!("514c")[..],
+            &hex!("514d01")[..],
+            &hex!("514effffffff00")[..],
+        ] {
+            let mut iter = Instructions::new(script);
+            assert_eq!(iter.next(), Some(Ok(Instruction::Op(0x51))));
+            assert_eq!(iter.next(), Some(Err(Error::TruncatedPush(1))));
+            assert_eq!(iter.next(), None);
+        }
+    }
+
+    #[test]
+    fn instructions_as_rust_bitcoin() {
+        use crate::{bsl::Block, Visit, Visitor};
+        use bitcoin::script::Instruction as BitcoinInstruction;
+        use core::ops::ControlFlow;
+
+        fn check(script: &[u8]) {
+            let ours = Instructions::new(script);
+            let theirs = bitcoin::Script::from_bytes(script).instructions();
+            assert_eq!(ours.clone().count(), theirs.clone().count());
+            for (ours, theirs) in ours.zip(theirs) {
+                match (ours, theirs) {
+                    (Ok(Instruction::Push { data, .. }), Ok(BitcoinInstruction::PushBytes(p))) => {
+                        assert_eq!(data, p.as_bytes())
+                    }
+                    (Ok(Instruction::Op(op)), Ok(BitcoinInstruction::Op(o))) => {
+                        assert_eq!(op, o.to_u8())
+                    }
+                    (Err(_), Err(_)) => (),
+                    _ => panic!("different instructions"),
+                }
+            }
+        }
+        struct Check;
+        impl Visitor for Check {
+            fn visit_tx_in(&mut self, _vin: usize, tx_in: &crate::bsl::TxIn) -> ControlFlow<()> {
+                check(tx_in.script_sig());
+                ControlFlow::Continue(())
+            }
+            fn visit_tx_out(
+                &mut self,
+                _vout: usize,
+                tx_out: &crate::bsl::TxOut,
+            ) -> ControlFlow<()> {
+                check(tx_out.script_pubkey());
+                ControlFlow::Continue(())
+            }
+            fn visit_witness_element(&mut self, _witness_i: usize, witness_element: &[u8]) {
+                check(witness_element);
+            }
+        }
+        Block::visit(bitcoin_test_data::blocks::mainnet_702861(), &mut Check).unwrap();
+    }
 }
@@ -0,0 +1,292 @@
+use crate::bsl::{opcodes::*, Instruction, Instructions};
+
+/// The type of a script pubkey, with the data identifying the receiver borrowed from the script.
+///
+/// Classification follows the Bitcoin Core `Solver`, see [`ScriptType::classify()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptType<'a> {
+    /// Pay to public key, contains the 33 or 65 bytes public key
+    P2pk(&'a [u8]),
+    /// Pay to public key hash, contains the 20 bytes hash
+    P2pkh(&'a [u8]),
+    /// Pay to script hash, contains the 20 bytes hash
+    P2sh(&'a [u8]),
+    /// Pay to witness public key hash, contains the 20 bytes witness program
+    P2wpkh(&'a [u8]),
+    /// Pay to witness script hash, contains the 32 bytes witness program
+    P2wsh(&'a [u8]),
+    /// Pay to taproot, contains the 32 bytes output key
+    P2tr(&'a [u8]),
+    /// Pay to anchor, the anyone-can-spend `OP_1 <0x4e73>` output
+    P2a,
+    /// Bare multisig requiring `required` signatures of the public keys in `keys`
+    Multisig {
+        /// The number of signatures required
+        required: u8,
+        /// The public keys
+        keys: MultisigKeys<'a>,
+    },
+    /// `OP_RETURN` followed only by pushes, contains the script after the `OP_RETURN`
+    NullData(&'a [u8]),
+    /// A witness program of a version not yet defined, or of version 1 but not a taproot output
+    WitnessUnknown {
+        /// The witness version, from 1 to 16
+        version: u8,
+        /// The witness program, from 2 to 40 bytes
+        program: &'a [u8],
+    },
+    /// Any other script
+    NonStandard,
+}
+
+impl<'a> ScriptType<'a> {
+    /// Classify the given script pubkey
+    pub fn classify(script: &'a [u8]) -> Self {
+        match script {
+            [OP_HASH160, 20, hash @ .., OP_EQUAL] if hash.len() == 20 => ScriptType::P2sh(hash),
+            [version, len, program @ ..]
+                if (4..=42).contains(&script.len())
+                    && *len as usize == program.len()
+                    && (*version == OP_0 || small_int(*version).is_some()) =>
+            {
+                match (small_int(*version).unwrap_or(0), program.len()) {
+                    (0, 20) => ScriptType::P2wpkh(program),
+                    (0, 32) => ScriptType::P2wsh(program),
+                    (0, _) => ScriptType::NonStandard,
+                    (1, 32) => ScriptType::P2tr(program),
+                    (1, 2) if program == [0x4e, 0x73] => ScriptType::P2a,
+                    (version, _) => ScriptType::WitnessUnknown { version, program },
+                }
+            }
+            [OP_RETURN, rest @ ..] => {
+                // as `IsPushOnly` in Bitcoin Core, which accepts also OP_RESERVED
+                let push_only =
+                    Instructions::new(rest).all(|i| matches!(i, Ok(i) if i.opcode() <= OP_16));
+                if push_only {
+                    ScriptType::NullData(rest)
+                } else {
+                    ScriptType::NonStandard
+                }
+            }
+            [len, key @ .., OP_CHECKSIG] if *len as usize == key.len() && is_pubkey(key) => {
+                ScriptType::P2pk(key)
+            }
+            [OP_DUP, OP_HASH160, 20, hash @ .., OP_EQUALVERIFY, OP_CHECKSIG]
+                if hash.len() == 20 =>
+            {
+                ScriptType::P2pkh(hash)
+            }
+            _ => match_multisig(script).unwrap_or(ScriptType::NonStandard),
+        }
+    }
+}
+
+fn is_pubkey(key: &[u8]) -> bool {
+    match key.first() {
+        Some(2 | 3) => key.len() == 33,
+        Some(4 | 6 | 7) => key.len() == 65,
+        _ => false,
+    }
+}
+
+fn match_multisig(script: &[u8]) -> Option<ScriptType> {
+    let (last, rest) = script.split_last()?;
+    let (first, keys_script) = rest.split_first()?;
+    let (total, keys_script) = keys_script.split_last()?;
+    let required = small_int(*first)?;
+    let total = small_int(*total)?;
+    if *last != OP_CHECKMULTISIG || required > total {
+        return None;
+    }
+    let mut count = 0;
+    for instruction in Instructions::new(keys_script) {
+        match instruction.ok()? {
+            Instruction::Push { data, .. } if is_pubkey(data) => count += 1,
+            _ => return None,
+        }
+    }
+    if count != total as usize {
+        return None;
+    }
+    Some(ScriptType::Multisig {
+        required,
+        keys: MultisigKeys {
+            instructions: Instructions::new(keys_script),
+            remaining: count,
+        },
+    })
+}
+
+/// Iterator over the public keys of a bare multisig, see [`ScriptType::Multisig`]
+#[derive(Debug, Clone)]
+pub struct MultisigKeys<'a> {
+    instructions: Instructions<'a>,
+    remaining: usize,
+}
+
+impl<'a> PartialEq for MultisigKeys<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.clone().eq(other.clone())
+    }
+}
+
+impl<'a> Eq for MultisigKeys<'a> {}
+
+impl<'a> Iterator for MultisigKeys<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let key = self.instructions.next()?;
+        self.remaining -= 1;
+        Some(
+            key.ok()
+                .and_then(|i| i.push_bytes())
+                .expect("validated during classification"),
+        )
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for MultisigKeys<'a> {}
+
+#[cfg(test)]
+mod test {
+    use super::ScriptType;
+    use crate::{bsl::Block, Visit, Visitor};
+    use bitcoin::{hashes::Hash, ScriptBuf};
+    use core::ops::ControlFlow;
+    use hex_lit::hex;
+
+    #[test]
+    fn classify() {
+        let key = bitcoin::PublicKey::from_slice(&hex!(
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+        ))
+        .unwrap();
+        let hash20 = [1u8; 20];
+        let hash32 = [2u8; 32];
+
+        let p2pk = ScriptBuf::new_p2pk(&key);
+        assert_eq!(
+            ScriptType::classify(p2pk.as_bytes()),
+            ScriptType::P2pk(&key.to_bytes())
+        );
+        let p2pkh = ScriptBuf::new_p2pkh(&bitcoin::PubkeyHash::from_byte_array(hash20));
+        assert_eq!(
+            ScriptType::classify(p2pkh.as_bytes()),
+            ScriptType::P2pkh(&hash20)
+        );
+        let p2sh = ScriptBuf::new_p2sh(&bitcoin::ScriptHash::from_byte_array(hash20));
+        assert_eq!(
+            ScriptType::classify(p2sh.as_bytes()),
+            ScriptType::P2sh(&hash20)
+        );
+        let p2wpkh = ScriptBuf::new_p2wpkh(&bitcoin::WPubkeyHash::from_byte_array(hash20));
+        assert_eq!(
+            ScriptType::classify(p2wpkh.as_bytes()),
+            ScriptType::P2wpkh(&hash20)
+        );
+        let p2wsh = ScriptBuf::new_p2wsh(&bitcoin::WScriptHash::from_byte_array(hash32));
+        assert_eq!(
+            ScriptType::classify(p2wsh.as_bytes()),
+            ScriptType::P2wsh(&hash32)
+        );
+        let p2tr = hex!("51200202020202020202020202020202020202020202020202020202020202020202");
+        assert_eq!(ScriptType::classify(&p2tr), ScriptType::P2tr(&hash32));
+        assert_eq!(ScriptType::classify(&hex!("51024e73")), ScriptType::P2a);
+        assert_eq!(
+            ScriptType::classify(&hex!("52020102")),
+            ScriptType::WitnessUnknown {
+                version: 2,
+                program: &[1, 2]
+            }
+        );
+        assert_eq!(
+            ScriptType::classify(&hex!("0003010203")),
+            ScriptType::NonStandard
+        );
+
+        assert_eq!(
+            ScriptType::classify(&hex!("6a0401020304")),
+            ScriptType::NullData(&hex!("0401020304"))
+        );
+        assert_eq!(ScriptType::classify(&hex!("6a")), ScriptType::NullData(&[]));
+        assert_eq!(ScriptType::classify(&hex!("6a76")), ScriptType::NonStandard);
+        assert_eq!(
+            ScriptType::classify(&hex!("6a5001ff")),
+            ScriptType::NullData(&hex!("5001ff"))
+        );
+        assert_eq!(
+            ScriptType::classify(&hex!("6a04010203")),
+            ScriptType::NonStandard
+        );
+
+        let multisig = bitcoin::blockdata::script::Builder::new()
+            .push_int(1)
+            .push_key(&key)
+            .push_key(&key)
+            .push_int(2)
+            .push_opcode(bitcoin::opcodes::all::OP_CHECKMULTISIG)
+            .into_script();
+        match ScriptType::classify(multisig.as_bytes()) {
+            ScriptType::Multisig { required, keys } => {
+                assert_eq!(required, 1);
+                assert_eq!(keys.len(), 2);
+                assert!(keys.into_iter().all(|k| k == &key.to_bytes()[..]));
+            }
+            _ => panic!("not multisig"),
+        }
+        let mut wrong = multisig.to_bytes();
+        wrong[0] = 0x53; // required 3 of 2
+        assert_eq!(ScriptType::classify(&wrong), ScriptType::NonStandard);
+
+        assert_eq!(ScriptType::classify(&[]), ScriptType::NonStandard);
+    }
+
+    #[test]
+    fn classify_as_rust_bitcoin() {
+        struct Check(usize);
+        impl Visitor for Check {
+            fn visit_tx_out(
+                &mut self,
+                _vout: usize,
+                tx_out: &crate::bsl::TxOut,
+            ) -> ControlFlow<()> {
+                let script = bitcoin::Script::from_bytes(tx_out.script_pubkey());
+                let expected = match tx_out.script_type() {
+                    ScriptType::P2pk(_) => script.is_p2pk(),
+                    ScriptType::P2pkh(_) => script.is_p2pkh(),
+                    ScriptType::P2sh(_) => script.is_p2sh(),
+                    ScriptType::P2wpkh(_) => script.is_p2wpkh(),
+                    ScriptType::P2wsh(_) => script.is_p2wsh(),
+                    ScriptType::P2tr(_) => script.is_p2tr(),
+                    ScriptType::NullData(_) => script.is_op_return(),
+                    ScriptType::Multisig { .. } => script.is_multisig(),
+                    ScriptType::P2a | ScriptType::WitnessUnknown { .. } => {
+                        script.is_witness_program()
+                    }
+                    // op return with non push opcodes and witness version 0 programs of wrong
+                    // length are not standard but satisfy `is_op_return` and `is_witness_program`
+                    ScriptType::NonStandard => {
+                        !(script.is_p2pk()
+                            || script.is_p2pkh()
+                            || script.is_p2sh()
+                            || script.is_p2wpkh()
+                            || script.is_p2wsh()
+                            || script.is_p2tr()
+                            || script.is_multisig())
+                    }
+                };
+                assert!(expected);
+                self.0 += 1;
+                ControlFlow::Continue(())
+            }
+        }
+        let mut check = Check(0);
+        Block::visit(bitcoin_test_data::blocks::mainnet_702861(), &mut check).unwrap();
+        assert!(check.0 > 5000);
+    }
+}
@@ -0,0 +1,482 @@
+use core::ops::ControlFlow;
+
+use crate::{
+    bsl::{scan_len, Coin},
+    number::{read_u16, read_u64},
+    slice::split_at_checked,
+    Error, Parse, ParseResult, SResult, Visit, Visitor,
+};
+
+/// Magic bytes at the beginning of a UTXO snapshot
+pub const SNAPSHOT_MAGIC: [u8; 5] = *b"utxo\xff";
+
+/// Length of the [`SnapshotMetadata`]
+const METADATA_LEN: usize = 51;
+
+/// The metadata at the beginning of a UTXO snapshot file created with the Bitcoin Core
+/// `dumptxoutset` RPC and loaded with `loadtxoutset` (assumeutxo).
+///
+/// It is followed by the coins grouped by txid, see [`SnapshotTxCoins`] and [`UtxoSnapshot`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotMetadata<'a> {
+    slice: &'a [u8],
+}
+
+impl<'a> Parse<'a> for SnapshotMetadata<'a> {
+    #[inline(always)]
+    fn parse(slice: &'a [u8]) -> SResult<Self> {
+        let (slice, remaining) = split_at_checked(slice, METADATA_LEN)?;
+        if slice[..5] != SNAPSHOT_MAGIC {
+            return Err(Error::UnexpectedMagic);
+        }
+        if read_u16(&slice[5..])? != 2 {
+            return Err(Error::UnsupportedVersion);
+        }
+        Ok(ParseResult::new(remaining, SnapshotMetadata { slice }))
+    }
+}
+
+impl<'a> SnapshotMetadata<'a> {
+    /// Returns the version of the snapshot format, currently only version 2 is supported
+    pub fn version(&self) -> u16 {
+        read_u16(&self.slice[5..]).expect("slice length ensured by parsing")
+    }
+    /// Returns the magic of the network of the snapshot, see [`crate::bsl::p2p::magic`]
+    pub fn network_magic(&self) -> [u8; 4] {
+        self.slice[7..11]
+            .try_into()
+            .expect("slice length ensured by parsing")
+    }
+    /// Returns the hash of the block at which the snapshot was taken
+    pub fn base_block_hash(&self) -> &[u8] {
+        &self.slice[11..43]
+    }
+    /// Returns the number of coins in the snapshot
+    pub fn coins_count(&self) -> u64 {
+        read_u64(&self.slice[43..]).expect("slice length ensured by parsing")
+    }
+}
+
+impl<'a> AsRef<[u8]> for SnapshotMetadata<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.slice
+    }
+}
+
+/// A coin of a UTXO snapshot with its outpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotCoin<'a> {
+    txid: &'a [u8],
+    vout: u32,
+    coin: Coin<'a>,
+}
+
+impl<'a> SnapshotCoin<'a> {
+    /// Returns the txid of the transaction creating this output
+    pub fn txid(&self) -> &'a [u8] {
+        self.txid
+    }
+    /// Returns the index of this output in the transaction creating it
+    pub fn vout(&self) -> u32 {
+        self.vout
+    }
+    /// Returns the coin, containing the output and the height at which it was created
+    pub fn coin(&self) -> &Coin<'a> {
+        &self.coin
+    }
+}
+
+/// The coins of a UTXO snapshot created by the same transaction: the txid followed by the number
+/// of coins and, for each of them, the output index and the [`Coin`].
+///
+/// Snapshots are big, to parse them without having them entirely in memory read the data in a
+/// buffer and parse the groups one after the other, reading more data when
+/// [`Error::MoreBytesNeeded`] is returned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotTxCoins<'a> {
+    slice: &'a [u8],
+    n: usize,
+    from: usize,
+}
+
+impl<'a> Visit<'a> for SnapshotTxCoins<'a> {
+    #[inline(always)]
+    fn visit<'b, V: Visitor>(slice: &'a [u8], visit: &'b mut V) -> SResult<'a, Self> {
+        let (txid, _) = split_at_checked(slice, 32)?;
+        let mut consumed = 32;
+        let n = scan_len(&slice[consumed..], &mut consumed)? as usize;
+        let from = consumed;
+        for _ in 0..n {
+            let coin = scan_coin(&slice[consumed..], &mut consumed, txid)?;
+            if let ControlFlow::Break(_) = visit.visit_snapshot_coin(&coin) {
+                return Err(Error::VisitBreak);
+            }
+        }
+        let (slice, remaining) = slice.split_at(consumed);
+        Ok(ParseResult::new(
+            remaining,
+            SnapshotTxCoins { slice, n, from },
+        ))
+    }
+}
+
+fn scan_coin<'a>(
+    slice: &'a [u8],
+    consumed: &mut usize,
+    txid: &'a [u8],
+) -> Result<SnapshotCoin<'a>, Error> {
+    let mut vout_len = 0;
+    let vout = scan_len(slice, &mut vout_len)?;
+    let vout = u32::try_from(vout).map_err(|_| Error::VarIntOverflow)?;
+    let coin = Coin::parse(&slice[vout_len..])?;
+    *consumed += vout_len + coin.consumed();
+    Ok(SnapshotCoin {
+        txid,
+        vout,
+        coin: coin.parsed_owned(),
+    })
+}
+
+impl<'a> SnapshotTxCoins<'a> {
+    /// Returns the txid of the transaction creating the coins
+    pub fn txid(&self) -> &'a [u8] {
+        &self.slice[..32]
+    }
+    /// Returns the number of coins
+    pub fn n(&self) -> usize {
+        self.n
+    }
+    /// Returns an iterator over the coins
+    pub fn iter(&self) -> SnapshotCoinIterator<'a> {
+        SnapshotCoinIterator {
+            txid: &self.slice[..32],
+            slice: &self.slice[self.from..],
+            remaining: self.n,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &SnapshotTxCoins<'a> {
+    type Item = SnapshotCoin<'a>;
+    type IntoIter = SnapshotCoinIterator<'a>;
+
+    fn into_iter(self) -> SnapshotCoinIterator<'a> {
+        self.iter()
+    }
+}
+
+impl<'a> AsRef<[u8]> for SnapshotTxCoins<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.slice
+    }
+}
+
+/// Iterator over the [`SnapshotCoin`] contained in [`SnapshotTxCoins`], returned by
+/// [`SnapshotTxCoins::iter()`]
+pub struct SnapshotCoinIterator<'a> {
+    txid: &'a [u8],
+    slice: &'a [u8],
+    remaining: usize,
+}
+
+impl<'a> Iterator for SnapshotCoinIterator<'a> {
+    type Item = SnapshotCoin<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let mut consumed = 0;
+        let coin = scan_coin(self.slice, &mut consumed, self.txid)
+            .expect("slice validated during parsing");
+        self.slice = &self.slice[consumed..];
+        Some(coin)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for SnapshotCoinIterator<'a> {}
+
+/// Iterator over the [`SnapshotTxCoins`] of a UTXO snapshot entirely in memory, for example a
+/// memory-mapped file.
+///
+/// The iteration ends after the number of coins declared in the metadata, returning
+/// [`Error::CoinsCountMismatch`] if a group contains more coins than the ones left.
+/// After an error is returned the iteration ends.
+#[derive(Debug, Clone)]
+pub struct UtxoSnapshot<'a> {
+    metadata: SnapshotMetadata<'a>,
+    slice: &'a [u8],
+    coins_left: u64,
+}
+
+impl<'a> UtxoSnapshot<'a> {
+    /// Parse the metadata at the beginning of `slice` and creates the iterator over the coins
+    pub fn new(slice: &'a [u8]) -> Result<Self, Error> {
+        let metadata = SnapshotMetadata::parse(slice)?;
+        let coins_left = metadata.parsed().coins_count();
+        Ok(UtxoSnapshot {
+            slice: metadata.remaining(),
+            metadata: metadata.parsed_owned(),
+            coins_left,
+        })
+    }
+
+    /// Returns the metadata of the snapshot
+    pub fn metadata(&self) -> &SnapshotMetadata<'a> {
+        &self.metadata
+    }
+
+    /// Returns the data after the last coin, empty for a valid snapshot, available after the
+    /// iteration ends
+    pub fn remaining(&self) -> &'a [u8] {
+        self.slice
+    }
+
+    fn next_tx_coins(&mut self) -> Result<SnapshotTxCoins<'a>, Error> {
+        let tx_coins = SnapshotTxCoins::parse(self.slice)?;
+        let n = tx_coins.parsed().n() as u64;
+        if n > self.coins_left {
+            return Err(Error::CoinsCountMismatch);
+        }
+        self.coins_left -= n;
+        self.slice = tx_coins.remaining();
+        Ok(tx_coins.parsed_owned())
+    }
+}
+
+impl<'a> Iterator for UtxoSnapshot<'a> {
+    type Item = Result<SnapshotTxCoins<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.coins_left == 0 {
+            return None;
+        }
+        let result = self.next_tx_coins();
+        if result.is_err() {
+            self.coins_left = 0;
+        }
+        Some(result)
+    }
+}
+
+/// Computes the `hash_serialized_3` commitment of a UTXO set, the one returned by the Bitcoin Core
+/// `gettxoutsetinfo` RPC and hardcoded in the assumeutxo parameters.
+///
+/// Coins must be added in the order they appear in the snapshot. It can be used as a [`Visitor`]
+/// of [`SnapshotTxCoins`], in this case a coin with a script which cannot be decompressed
+/// (see [`crate::bsl::CompressedScript::script_pubkey()`]) makes the visit return
+/// [`Error::VisitBreak`].
+///
+/// The resulting hash is in byte order, reverse it to compare with the hex displayed by Bitcoin
+/// Core.
+#[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+pub struct HashSerialized(crate::hashes::Sha256Engine);
+
+#[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+impl HashSerialized {
+    /// Creates the hasher of an empty UTXO set
+    pub fn new() -> Self {
+        HashSerialized(crate::hashes::Sha256Engine::new())
+    }
+
+    /// Add the given coin to the commitment
+    pub fn add(&mut self, coin: &SnapshotCoin) -> Result<(), Error> {
+        let mut buffer = [0u8; crate::bsl::SPECIAL_SCRIPT_MAX_LEN];
+        let script_pubkey = coin.coin().script_pubkey(&mut buffer)?;
+        let code = (coin.coin().height() << 1) | coin.coin().is_coinbase() as u32;
+        let engine = &mut self.0;
+        engine.input(coin.txid());
+        engine.input(&coin.vout().to_le_bytes());
+        engine.input(&code.to_le_bytes());
+        engine.input(&coin.coin().value().to_le_bytes());
+        let len = script_pubkey.len();
+        if len < 0xFD {
+            engine.input(&[len as u8]);
+        } else {
+            // scripts are shorter than 10_001 bytes after decompression
+            engine.input(&[0xFD]);
+            engine.input(&(len as u16).to_le_bytes());
+        }
+        engine.input(script_pubkey);
+        Ok(())
+    }
+
+    /// Returns the `hash_serialized_3` of the added coins
+    pub fn finalize(self) -> [u8; 32] {
+        crate::hashes::sha256(&[&self.0.finalize()[..]])
+    }
+}
+
+#[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+impl Default for HashSerialized {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+impl Visitor for HashSerialized {
+    fn visit_snapshot_coin(&mut self, coin: &SnapshotCoin) -> ControlFlow<()> {
+        match self.add(coin) {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(_) => ControlFlow::Break(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SnapshotMetadata, SnapshotTxCoins, UtxoSnapshot};
+    use crate::{
+        bsl::{
+            compressed::test::{compress_amount, var_int},
+            p2p::magic,
+            SPECIAL_SCRIPT_MAX_LEN,
+        },
+        Error, Parse,
+    };
+    use hex_lit::hex;
+
+    const SCRIPT: [u8; 22] = hex!("0014751e76e8199196d454941c45d1b3a323f1433bd6");
+
+    fn coin(vout: u8, height: u32, amount: u64) -> Vec<u8> {
+        let mut bytes = vec![vout];
+        bytes.extend(var_int((height as u64) << 1));
+        bytes.extend(var_int(compress_amount(amount)));
+        bytes.extend(var_int(SCRIPT.len() as u64 + 6));
+        bytes.extend(SCRIPT);
+        bytes
+    }
+
+    fn snapshot() -> Vec<u8> {
+        let mut bytes = b"utxo\xff".to_vec();
+        bytes.extend(2u16.to_le_bytes());
+        bytes.extend(magic::REGTEST);
+        bytes.extend([0xBBu8; 32]);
+        bytes.extend(3u64.to_le_bytes());
+
+        bytes.extend([1u8; 32]);
+        bytes.push(2);
+        bytes.extend(coin(0, 100, 1_000));
+        bytes.extend(coin(3, 100, 2_000));
+
+        bytes.extend([2u8; 32]);
+        bytes.push(1);
+        bytes.extend(coin(1, 200, 3_000));
+        bytes
+    }
+
+    #[test]
+    fn parse_metadata() {
+        let bytes = snapshot();
+        let metadata = SnapshotMetadata::parse(&bytes).unwrap();
+        assert_eq!(metadata.consumed(), 51);
+        let metadata = metadata.parsed();
+        assert_eq!(metadata.version(), 2);
+        assert_eq!(metadata.network_magic(), magic::REGTEST);
+        assert_eq!(metadata.base_block_hash(), &[0xBBu8; 32]);
+        assert_eq!(metadata.coins_count(), 3);
+
+        assert_eq!(
+            SnapshotMetadata::parse(&bytes[..50]),
+            Err(Error::MoreBytesNeeded)
+        );
+        let mut wrong = bytes.clone();
+        wrong[4] = 0;
+        assert_eq!(SnapshotMetadata::parse(&wrong), Err(Error::UnexpectedMagic));
+        let mut wrong = bytes.clone();
+        wrong[5] = 1;
+        assert_eq!(
+            SnapshotMetadata::parse(&wrong),
+            Err(Error::UnsupportedVersion)
+        );
+    }
+
+    #[test]
+    fn parse_snapshot() {
+        let bytes = snapshot();
+        let tx_coins = SnapshotTxCoins::parse(&bytes[51..]).unwrap();
+        assert_eq!(tx_coins.parsed().txid(), &[1u8; 32]);
+        assert_eq!(tx_coins.parsed().n(), 2);
+        for i in 51..51 + tx_coins.consumed() {
+            assert_eq!(
+                SnapshotTxCoins::parse(&bytes[51..i]),
+                Err(Error::MoreBytesNeeded)
+            );
+        }
+
+        let mut snapshot = UtxoSnapshot::new(&bytes).unwrap();
+        assert_eq!(snapshot.metadata().coins_count(), 3);
+        let coins: Vec<_> = snapshot
+            .by_ref()
+            .flat_map(|tx_coins| tx_coins.unwrap().iter().collect::<Vec<_>>())
+            .collect();
+        assert_eq!(snapshot.remaining(), &[]);
+        assert_eq!(coins.len(), 3);
+        let mut buffer = [0u8; SPECIAL_SCRIPT_MAX_LEN];
+        assert_eq!(coins[1].txid(), &[1u8; 32]);
+        assert_eq!(coins[1].vout(), 3);
+        assert_eq!(coins[1].coin().value(), 2_000);
+        assert_eq!(
+            coins[1].coin().script_pubkey(&mut buffer).unwrap(),
+            &SCRIPT[..]
+        );
+        assert_eq!(coins[2].txid(), &[2u8; 32]);
+        assert_eq!(coins[2].coin().height(), 200);
+
+        let mut wrong = bytes.clone();
+        wrong[43] = 1; // coins count
+        let mut snapshot = UtxoSnapshot::new(&wrong).unwrap();
+        assert_eq!(snapshot.next(), Some(Err(Error::CoinsCountMismatch)));
+        assert!(snapshot.next().is_none());
+    }
+
+    #[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+    #[test]
+    fn hash_serialized() {
+        use super::HashSerialized;
+        use crate::Visit;
+        use bitcoin::{consensus::serialize, hashes::Hash};
+
+        let bytes = snapshot();
+        let mut expected = vec![];
+        for (txid, vout, height, amount) in [
+            (1u8, 0, 100u32, 1_000),
+            (1, 3, 100, 2_000),
+            (2, 1, 200, 3_000),
+        ] {
+            let out_point =
+                bitcoin::OutPoint::new(bitcoin::Txid::from_byte_array([txid; 32]), vout);
+            let tx_out = bitcoin::TxOut {
+                value: bitcoin::Amount::from_sat(amount),
+                script_pubkey: bitcoin::ScriptBuf::from_bytes(SCRIPT.to_vec()),
+            };
+            expected.extend(serialize(&out_point));
+            expected.extend(serialize(&(height << 1)));
+            expected.extend(serialize(&tx_out));
+        }
+        let expected = bitcoin::hashes::sha256d::Hash::hash(&expected).to_byte_array();
+
+        let mut hasher = HashSerialized::new();
+        for tx_coins in UtxoSnapshot::new(&bytes).unwrap() {
+            for coin in &tx_coins.unwrap() {
+                hasher.add(&coin).unwrap();
+            }
+        }
+        assert_eq!(hasher.finalize(), expected);
+
+        let mut hasher = HashSerialized::new();
+        let mut remaining = &bytes[51..];
+        while !remaining.is_empty() {
+            remaining = SnapshotTxCoins::visit(remaining, &mut hasher)
+                .unwrap()
+                .remaining();
+        }
+        assert_eq!(hasher.finalize(), expected);
+    }
+}
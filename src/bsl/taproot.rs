@@ -0,0 +1,215 @@
+use crate::Error;
+
+/// The first byte of the annex, the last witness element of a taproot spend if it starts with it
+/// and there are at least two elements
+pub const TAPROOT_ANNEX_TAG: u8 = 0x50;
+
+/// The leaf version of tapscript, as defined in BIP342
+pub const TAPROOT_LEAF_TAPSCRIPT: u8 = 0xc0;
+
+/// Size of the control block with an empty merkle path: leaf version and parity, internal key
+const CONTROL_BLOCK_BASE_SIZE: usize = 33;
+
+/// Size of a node of the merkle path of the control block
+const CONTROL_BLOCK_NODE_SIZE: usize = 32;
+
+/// Maximum depth of the merkle path of the control block
+const CONTROL_BLOCK_MAX_NODE_COUNT: usize = 128;
+
+/// How a taproot output is spent, returned by [`crate::bsl::Witness::taproot_spend()`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaprootSpend<'a> {
+    /// Spend with a signature for the tweaked output key
+    KeyPath {
+        /// The schnorr signature, 64 bytes or 65 with an explicit sighash type
+        signature: &'a [u8],
+    },
+    /// Spend revealing a script committed in the output key, executed with the preceding
+    /// witness elements as inputs
+    ScriptPath {
+        /// The executed script, a tapscript if the leaf version is [`TAPROOT_LEAF_TAPSCRIPT`]
+        script: &'a [u8],
+        /// The proof the script is committed in the output key
+        control_block: ControlBlock<'a>,
+    },
+}
+
+/// The control block of a taproot script path spend: the leaf version, the internal key and the
+/// merkle path proving the inclusion of the script in the taproot tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlBlock<'a> {
+    slice: &'a [u8],
+}
+
+impl<'a> ControlBlock<'a> {
+    /// Creates the control block from the last witness element of a script path spend.
+    ///
+    /// Returns [`Error::InvalidControlBlock`] if its size is not 33 bytes plus a merkle path of at
+    /// most 128 nodes of 32 bytes.
+    pub fn new(slice: &'a [u8]) -> Result<Self, Error> {
+        let path_len = slice
+            .len()
+            .checked_sub(CONTROL_BLOCK_BASE_SIZE)
+            .ok_or(Error::InvalidControlBlock)?;
+        if path_len % CONTROL_BLOCK_NODE_SIZE != 0
+            || path_len / CONTROL_BLOCK_NODE_SIZE > CONTROL_BLOCK_MAX_NODE_COUNT
+        {
+            return Err(Error::InvalidControlBlock);
+        }
+        Ok(ControlBlock { slice })
+    }
+
+    /// Returns the leaf version of the revealed script
+    pub fn leaf_version(&self) -> u8 {
+        self.slice[0] & 0xfe
+    }
+
+    /// Returns the parity of the y coordinate of the output key, 0 for even and 1 for odd
+    pub fn output_key_parity(&self) -> u8 {
+        self.slice[0] & 0x01
+    }
+
+    /// Returns the x-only internal key
+    pub fn internal_key(&self) -> &'a [u8] {
+        &self.slice[1..CONTROL_BLOCK_BASE_SIZE]
+    }
+
+    /// Returns the nodes of the merkle path from the leaf to the root of the taproot tree
+    pub fn merkle_path(&self) -> core::slice::ChunksExact<'a, u8> {
+        self.slice[CONTROL_BLOCK_BASE_SIZE..].chunks_exact(CONTROL_BLOCK_NODE_SIZE)
+    }
+
+    /// Returns the merkle root of the taproot tree containing `script`, combining its tapleaf hash
+    /// with the nodes of the merkle path.
+    ///
+    /// The script is committed in the spent output if the output key is the internal key tweaked
+    /// with [`ControlBlock::tap_tweak_hash()`] of this root.
+    #[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+    pub fn merkle_root(&self, script: &[u8]) -> [u8; 32] {
+        self.merkle_path()
+            .fold(tap_leaf_hash(self.leaf_version(), script), |hash, node| {
+                let mut engine = crate::hashes::Sha256Engine::tagged(b"TapBranch");
+                if hash[..] < *node {
+                    engine.input(&hash);
+                    engine.input(node);
+                } else {
+                    engine.input(node);
+                    engine.input(&hash);
+                }
+                engine.finalize()
+            })
+    }
+
+    /// Returns the hash tweaking the internal key into the output key, given the `merkle_root`
+    /// of the taproot tree
+    #[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+    pub fn tap_tweak_hash(&self, merkle_root: &[u8; 32]) -> [u8; 32] {
+        let mut engine = crate::hashes::Sha256Engine::tagged(b"TapTweak");
+        engine.input(self.internal_key());
+        engine.input(merkle_root);
+        engine.finalize()
+    }
+}
+
+impl<'a> AsRef<[u8]> for ControlBlock<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.slice
+    }
+}
+
+/// Returns the tapleaf hash of `script` with the given `leaf_version`, the leaf of the taproot tree
+/// committing to the script and the message of signatures in script path spends
+#[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+pub fn tap_leaf_hash(leaf_version: u8, script: &[u8]) -> [u8; 32] {
+    let mut engine = crate::hashes::Sha256Engine::tagged(b"TapLeaf");
+    engine.input(&[leaf_version]);
+    engine.input_len(script.len());
+    engine.input(script);
+    engine.finalize()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn control_block() {
+        assert_eq!(
+            ControlBlock::new(&[0xc0; 32]),
+            Err(Error::InvalidControlBlock)
+        );
+        assert_eq!(
+            ControlBlock::new(&[0xc0; 34]),
+            Err(Error::InvalidControlBlock)
+        );
+        assert_eq!(
+            ControlBlock::new(&[0xc0; 33 + 32 * 129]),
+            Err(Error::InvalidControlBlock)
+        );
+        assert!(ControlBlock::new(&[0xc0; 33 + 32 * 128]).is_ok());
+
+        let mut slice = [2u8; 33 + 64];
+        slice[0] = 0xc1;
+        slice[65..].copy_from_slice(&[3u8; 32]);
+        let control_block = ControlBlock::new(&slice).unwrap();
+        assert_eq!(control_block.leaf_version(), TAPROOT_LEAF_TAPSCRIPT);
+        assert_eq!(control_block.output_key_parity(), 1);
+        assert_eq!(control_block.internal_key(), &[2u8; 32]);
+        let path: Vec<_> = control_block.merkle_path().collect();
+        assert_eq!(path, vec![&[2u8; 32][..], &[3u8; 32][..]]);
+    }
+
+    #[cfg(feature = "bitcoin")]
+    #[test]
+    fn script_commitment() {
+        use bitcoin::{
+            hashes::Hash,
+            key::{Keypair, Secp256k1},
+            taproot::{LeafVersion, TapLeafHash, TapTweakHash, TaprootBuilder},
+            ScriptBuf,
+        };
+
+        let secp = Secp256k1::new();
+        let keypair = Keypair::from_seckey_slice(&secp, &[1u8; 32]).unwrap();
+        let (internal_key, _) = keypair.x_only_public_key();
+        let scripts: Vec<_> = (0..5u8)
+            .map(|i| ScriptBuf::from_bytes(vec![0x51 + i]))
+            .collect();
+        let spend_info = TaprootBuilder::new()
+            .add_leaf(2, scripts[0].clone())
+            .unwrap()
+            .add_leaf(2, scripts[1].clone())
+            .unwrap()
+            .add_leaf(3, scripts[2].clone())
+            .unwrap()
+            .add_leaf(3, scripts[3].clone())
+            .unwrap()
+            .add_leaf(2, scripts[4].clone())
+            .unwrap()
+            .finalize(&secp, internal_key)
+            .unwrap();
+        let merkle_root = spend_info.merkle_root().unwrap().to_byte_array();
+        for script in scripts {
+            let expected = spend_info
+                .control_block(&(script.clone(), LeafVersion::TapScript))
+                .unwrap();
+            let serialized = expected.serialize();
+            let control_block = ControlBlock::new(&serialized).unwrap();
+            assert_eq!(
+                control_block.output_key_parity(),
+                expected.output_key_parity.to_u8()
+            );
+            assert_eq!(control_block.internal_key(), &internal_key.serialize()[..]);
+            assert_eq!(
+                tap_leaf_hash(TAPROOT_LEAF_TAPSCRIPT, script.as_bytes()),
+                TapLeafHash::from_script(&script, LeafVersion::TapScript).to_byte_array()
+            );
+            assert_eq!(control_block.merkle_root(script.as_bytes()), merkle_root);
+            let tweak = TapTweakHash::from_key_and_tweak(internal_key, spend_info.merkle_root());
+            assert_eq!(
+                control_block.tap_tweak_hash(&merkle_root),
+                tweak.to_byte_array()
+            );
+        }
+    }
+}
@@ -2,6 +2,7 @@ use core::{num::NonZeroU32, ops::ControlFlow};
 
 use crate::{
     bsl::{TxIns, TxOuts, Witnesses},
+    detailed::{self, DetailedError},
     number::{read_i32, read_u32, read_u8},
     Error, ParseResult, SResult, Visit, Visitor,
 };
@@ -14,6 +15,9 @@ pub struct Transaction<'a> {
     /// The length of the slice inlcuding all inputs and outputs of the transaction.
     /// If some the tx is segwit
     inputs_outputs_len: Option<NonZeroU32>,
+
+    /// The offset in the slice where the outputs start
+    outputs_offset: u32,
 }
 
 impl<'a> Visit<'a> for Transaction<'a> {
@@ -40,6 +44,7 @@ impl<'a> Visit<'a> for Transaction<'a> {
                 let tx = Transaction {
                     slice: &slice[..consumed],
                     inputs_outputs_len: NonZeroU32::new(inputs_outputs_len as u32), // inputs_outputs_len is at least 2 bytes if both empty, they contain the compact int len
+                    outputs_offset: 6 + inputs.consumed() as u32,
                 };
                 match visit.visit_transaction(&tx) {
                     ControlFlow::Continue(_) => Ok(ParseResult::new(&slice[consumed..], tx)),
@@ -56,6 +61,7 @@ impl<'a> Visit<'a> for Transaction<'a> {
             let tx = Transaction {
                 slice: &slice[..consumed],
                 inputs_outputs_len: None,
+                outputs_offset: 4 + inputs.consumed() as u32,
             };
             match visit.visit_transaction(&tx) {
                 ControlFlow::Continue(_) => Ok(ParseResult::new(&slice[consumed..], tx)),
@@ -65,6 +71,15 @@ impl<'a> Visit<'a> for Transaction<'a> {
     }
 }
 impl<'a> Transaction<'a> {
+    /// Visit the transaction like [`Visit::visit()`], in case of error returns also the position
+    /// and the context where it happened, see [`crate::bsl::Block::visit_detailed()`].
+    pub fn visit_detailed<V: Visitor>(
+        slice: &'a [u8],
+        visit: &mut V,
+    ) -> Result<ParseResult<'a, Self>, DetailedError> {
+        detailed::visit_detailed(slice, visit)
+    }
+
     /// Returns the transaction version.
     pub fn version(&self) -> i32 {
         read_i32(&self.slice[..4]).expect("slice length granted during parsing")
@@ -76,6 +91,39 @@ impl<'a> Transaction<'a> {
         read_u32(&self.slice[from..]).expect("slice length granted during parsing")
     }
 
+    /// Returns if the transaction is serialized with the segwit marker and flag, followed by the
+    /// witnesses after the outputs.
+    pub fn is_segwit(&self) -> bool {
+        self.inputs_outputs_len.is_some()
+    }
+
+    /// Returns the transaction inputs.
+    pub fn inputs(&self) -> TxIns<'a> {
+        let start = if self.is_segwit() { 6 } else { 4 };
+        TxIns::from_parsed(&self.slice[start..self.outputs_offset as usize])
+    }
+
+    /// Returns the transaction outputs.
+    pub fn outputs(&self) -> TxOuts<'a> {
+        TxOuts::from_parsed(&self.slice[self.outputs_offset as usize..self.outputs_end()])
+    }
+
+    /// Returns the witnesses of the inputs, `None` if the transaction is not segwit.
+    pub fn witnesses(&self) -> Option<Witnesses<'a>> {
+        self.inputs_outputs_len?;
+        let n = self.inputs().n();
+        let slice = &self.slice[self.outputs_end()..self.slice.len() - 4];
+        // a segwit transaction with inputs must have at least a non-empty witness
+        Some(Witnesses::from_parsed(slice, n, n == 0))
+    }
+
+    fn outputs_end(&self) -> usize {
+        match self.inputs_outputs_len {
+            Some(len) => len.get() as usize + 6,
+            None => self.slice.len() - 4,
+        }
+    }
+
     /// Return the txid preimage, or the data that must be fed to the hashing function (double sha256)
     /// to obtain the txid.
     /// It is a tuple of 3 because for segwit transactions they are 3 non-contiguos bytes slices and
@@ -123,6 +171,35 @@ impl<'a> Transaction<'a> {
         Sha256::digest(&hash[..])
     }
 
+    /// Return the wtxid preimage, or the data that must be fed to the hashing function (double
+    /// sha256) to obtain the witness transaction identifier.
+    /// Differently from [`Transaction::txid_preimage()`] it's always a single slice: the whole
+    /// transaction, which for legacy transactions is also the txid preimage.
+    pub fn wtxid_preimage(&self) -> &'a [u8] {
+        self.slice
+    }
+
+    /// Return the witness transaction identifier as defined in BIP 141, equal to the txid for
+    /// legacy transactions.
+    /// Note the witness commitment of a block uses 0 in place of the coinbase wtxid.
+    #[cfg(feature = "bitcoin_hashes")]
+    pub fn wtxid(&self) -> crate::bitcoin_hashes::sha256d::Hash {
+        use crate::bitcoin_hashes::{sha256d, Hash};
+        sha256d::Hash::hash(self.wtxid_preimage())
+    }
+
+    /// Calculate the wtxid using the sha2 crate.
+    /// NOTE: the result type is not displayed backwards when converted to string.
+    #[cfg(feature = "sha2")]
+    pub fn wtxid_sha2(
+        &self,
+    ) -> crate::sha2::digest::generic_array::GenericArray<u8, crate::sha2::digest::typenum::U32>
+    {
+        use crate::sha2::{Digest, Sha256};
+        let hash = Sha256::digest(self.wtxid_preimage());
+        Sha256::digest(&hash[..])
+    }
+
     /// Transaction weight as defined by BIP 141
     pub fn weight(&self) -> u64 {
         let total_size = self.as_ref().len() as u64;
@@ -187,7 +264,7 @@ impl<'o> redb::RedbValue for Transaction<'o> {
 #[cfg(test)]
 mod test {
     use crate::{bsl::Transaction, test_common::GENESIS_TX, Parse};
-    use bitcoin::consensus::deserialize;
+    use bitcoin::{consensus::deserialize, hashes::Hash};
     use hex_lit::hex;
 
     #[test]
@@ -198,11 +275,23 @@ mod test {
         assert_eq!(tx.consumed(), 204);
         assert_eq!(tx.parsed().version(), 1);
         assert_eq!(tx.parsed().locktime(), 0);
+        assert!(!tx.parsed().is_segwit());
+        assert_eq!(tx.parsed().inputs().n(), 1);
+        assert_eq!(tx.parsed().outputs().n(), 1);
+        assert_eq!(
+            tx.parsed().outputs().iter().next().unwrap().value(),
+            50 * 100_000_000
+        );
+        assert!(tx.parsed().witnesses().is_none());
 
         check_hash(
             tx.parsed(),
             hex!("4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b"),
         );
+        check_wtxid(
+            tx.parsed(),
+            hex!("4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b"),
+        );
     }
 
     #[test]
@@ -214,11 +303,28 @@ mod test {
         assert_eq!(tx.consumed(), 222);
         assert_eq!(tx.parsed().version(), 1);
         assert_eq!(tx.parsed().locktime(), 0);
+        assert!(tx.parsed().is_segwit());
+        assert_eq!(tx.parsed().inputs().n(), 1);
+        assert_eq!(tx.parsed().outputs().n(), 2);
+        let witnesses = tx.parsed().witnesses().unwrap();
+        assert!(!witnesses.all_empty());
+        assert_eq!(
+            witnesses.iter().next().unwrap().get(0),
+            Some(&[0u8; 32][..])
+        );
 
         check_hash(
             tx.parsed(),
             hex!("4be105f158ea44aec57bf12c5817d073a712ab131df6f37786872cfc70734188"), // testnet tx
         );
+        let wtxid = bitcoin::consensus::deserialize::<bitcoin::Transaction>(&segwit_tx[..])
+            .unwrap()
+            .compute_wtxid();
+        assert_eq!(tx.parsed().wtxid_preimage(), &segwit_tx[..]);
+        check_wtxid(
+            tx.parsed(),
+            crate::test_common::reverse(wtxid.to_byte_array()),
+        );
     }
 
     #[test]
@@ -247,6 +353,49 @@ mod test {
         assert!(Transaction::parse(&tx_nonminimal[..]).is_err());
     }
 
+    #[cfg(feature = "bitcoin")]
+    #[test]
+    fn inputs_outputs_witnesses() {
+        use crate::{bsl::Block, Visit, Visitor};
+        use bitcoin::consensus::serialize;
+        use core::ops::ControlFlow;
+
+        struct Check(usize);
+        impl Visitor for Check {
+            fn visit_transaction(&mut self, tx: &Transaction) -> ControlFlow<()> {
+                let expected: bitcoin::Transaction = deserialize(tx.as_ref()).unwrap();
+                let segwit = expected.input.iter().any(|i| !i.witness.is_empty());
+                assert_eq!(tx.is_segwit(), segwit);
+
+                let inputs = tx.inputs();
+                assert_eq!(inputs.as_ref(), &serialize(&expected.input)[..]);
+                for (tx_in, expected) in inputs.iter().zip(expected.input.iter()) {
+                    assert_eq!(tx_in.sequence(), expected.sequence.0);
+                }
+                let outputs = tx.outputs();
+                assert_eq!(outputs.as_ref(), &serialize(&expected.output)[..]);
+                assert_eq!(outputs.n(), expected.output.len());
+
+                match tx.witnesses() {
+                    Some(witnesses) => {
+                        assert_eq!(witnesses.n(), expected.input.len());
+                        for (witness, expected) in witnesses.iter().zip(expected.input.iter()) {
+                            assert_eq!(witness.as_ref(), &serialize(&expected.witness)[..]);
+                        }
+                    }
+                    None => assert!(!segwit),
+                }
+                self.0 += 1;
+                ControlFlow::Continue(())
+            }
+        }
+
+        let block = bitcoin_test_data::blocks::mainnet_702861();
+        let mut check = Check(0);
+        Block::visit(block, &mut check).unwrap();
+        assert_eq!(check.0, 2500);
+    }
+
     #[cfg(target_pointer_width = "64")]
     #[test]
     fn size_of() {
@@ -273,6 +422,18 @@ mod test {
         assert_eq!(&tx.txid_sha2()[..], &reverse(expected)[..]);
     }
 
+    #[cfg(all(not(feature = "sha2"), not(feature = "bitcoin_hashes")))]
+    fn check_wtxid(_tx: &Transaction, _expected: [u8; 32]) {}
+
+    #[cfg(any(feature = "sha2", feature = "bitcoin_hashes"))]
+    fn check_wtxid(tx: &Transaction, expected: [u8; 32]) {
+        use crate::test_common::reverse;
+        #[cfg(feature = "bitcoin_hashes")]
+        assert_eq!(&tx.wtxid()[..], &reverse(expected)[..]);
+        #[cfg(feature = "sha2")]
+        assert_eq!(&tx.wtxid_sha2()[..], &reverse(expected)[..]);
+    }
+
     #[cfg(feature = "bitcoin")]
     #[test]
     fn test_weight() {
@@ -35,6 +35,13 @@ impl<'a> Visit<'a> for TxIns<'a> {
     }
 }
 impl<'a> TxIns<'a> {
+    /// Creates the transaction inputs from an already validated slice, like one part of a parsed
+    /// [`crate::bsl::Transaction`]
+    pub(crate) fn from_parsed(slice: &'a [u8]) -> Self {
+        let mut consumed = 0;
+        let n = scan_len(slice, &mut consumed).expect("granted by parsing") as usize;
+        TxIns { slice, n }
+    }
     /// Returns if there are no transaction inputs
     #[inline(always)]
     pub fn is_empty(&self) -> bool {
@@ -52,7 +59,58 @@ impl<'a> AsRef<[u8]> for TxIns<'a> {
     }
 }
 
-impl<'a> TxIns<'a> {}
+impl<'a> TxIns<'a> {
+    /// Returns an iterator over [`TxIn`]
+    ///
+    /// If possible is better to use [`Visitor::visit_tx_in`] to avoid double pass, however, it may
+    /// be convenient to iterate in case you already have validated the slice, for example some
+    /// data in a db.
+    pub fn iter(&self) -> TxInIterator<'a> {
+        let mut consumed = 0;
+        scan_len(self.slice, &mut consumed).expect("len granted by parsing");
+        TxInIterator {
+            remaining: self.n,
+            slice: &self.slice[consumed..],
+        }
+    }
+}
+
+impl<'a> IntoIterator for &TxIns<'a> {
+    type Item = TxIn<'a>;
+    type IntoIter = TxInIterator<'a>;
+
+    /// Returns an iterator over [`TxIn`], see [`TxIns::iter()`]
+    fn into_iter(self) -> TxInIterator<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over the [`TxIn`] of [`TxIns`], returned by [`TxIns::iter()`]
+#[derive(Debug, Clone)]
+pub struct TxInIterator<'a> {
+    remaining: usize,
+    slice: &'a [u8],
+}
+
+impl<'a> Iterator for TxInIterator<'a> {
+    type Item = TxIn<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let tx_in = TxIn::parse(self.slice).expect("granted from parsing");
+        self.remaining -= 1;
+        self.slice = tx_in.remaining();
+        Some(tx_in.parsed_owned())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for TxInIterator<'a> {}
 
 #[cfg(test)]
 mod test {
@@ -138,6 +196,33 @@ mod test {
         assert!(!is_mine.1);
     }
 
+    #[test]
+    fn iter_tx_ins() {
+        let tx_in_bytes = hex!("a15d57094aa7a21a28cb20b59aab8fc7d1149a3bdbcddba9c622e4f5f6a99ece010000006c493046022100f93bb0e7d8db7bd46e40132d1f8242026e045f03a0efe71bbb8e3f475e970d790221009337cd7f1f929f00cc6ff01f03729b069a7c21b59b1736ddfee5db5946c5da8c0121033b9b137ee87d5a812d6f506efdd37f0affa7ffc310711c06c7f3e097c9447c52ffffffff");
+        let mut tx_ins_bytes = vec![2u8];
+        tx_ins_bytes.extend(&tx_in_bytes);
+        tx_ins_bytes.extend(&tx_in_bytes);
+        *tx_ins_bytes.last_mut().unwrap() = 0;
+        let tx_ins = TxIns::parse(&tx_ins_bytes[..]).unwrap().parsed_owned();
+
+        let mut iter = tx_ins.iter();
+        assert_eq!(iter.len(), 2);
+        assert_eq!(iter.next().unwrap().sequence(), 0xffffffff);
+        assert_eq!(iter.len(), 1);
+        let tx_in = iter.next().unwrap();
+        assert_eq!(tx_in.sequence(), 0x00ffffff);
+        assert_eq!(tx_in.prevout().vout(), 1);
+        assert!(iter.next().is_none());
+        assert_eq!(iter.len(), 0);
+
+        for tx_in in &tx_ins {
+            assert_eq!(tx_in.as_ref().len(), tx_in_bytes.len());
+        }
+
+        let empty = TxIns::parse(&[0u8][..]).unwrap().parsed_owned();
+        assert!(empty.iter().next().is_none());
+    }
+
     #[cfg(target_pointer_width = "64")]
     #[test]
     fn size_of() {
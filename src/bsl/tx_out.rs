@@ -1,4 +1,4 @@
-use crate::bsl::Script;
+use crate::bsl::{Script, ScriptType};
 use crate::number::read_u64;
 use crate::{Parse, ParseResult, SResult};
 
@@ -33,6 +33,20 @@ impl<'a> TxOut<'a> {
     pub fn script_pubkey(&self) -> &[u8] {
         self.script_pubkey.script()
     }
+    /// Return the type of the script pubkey of this output, see [`ScriptType::classify()`]
+    pub fn script_type(&self) -> ScriptType {
+        ScriptType::classify(self.script_pubkey())
+    }
+
+    /// Return the address of this output, or `None` if the script pubkey has no address
+    /// representation, see [`crate::address::Address::from_script()`]
+    #[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+    pub fn address(
+        &self,
+        params: &crate::address::AddressParams,
+    ) -> Option<crate::address::Address> {
+        crate::address::Address::from_script(self.script_pubkey(), params)
+    }
 
     #[cfg(feature = "bitcoin")]
     /// Return the script pubkey of this output as a bitcoin::Script
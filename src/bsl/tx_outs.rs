@@ -35,6 +35,13 @@ impl<'a> Visit<'a> for TxOuts<'a> {
     }
 }
 impl<'a> TxOuts<'a> {
+    /// Creates the transaction outputs from an already validated slice, like one part of a parsed
+    /// [`crate::bsl::Transaction`]
+    pub(crate) fn from_parsed(slice: &'a [u8]) -> Self {
+        let mut consumed = 0;
+        let n = scan_len(slice, &mut consumed).expect("granted by parsing") as usize;
+        TxOuts { slice, n }
+    }
     /// If there are no outputs.
     #[inline(always)]
     pub fn is_empty(&self) -> bool {
@@ -0,0 +1,615 @@
+use core::ops::ControlFlow;
+
+use crate::{
+    bsl::{
+        block_file::next_record, scan_len, Block, CompressedScript, CompressedTxOut,
+        SPECIAL_SCRIPT_MAX_LEN,
+    },
+    number::scan_var_int,
+    Error, Parse, ParseResult, SResult, Visit, Visitor,
+};
+
+/// The undo data of a spent output, as stored by Bitcoin Core in its `rev*.dat` files (`Coin`
+/// serialized as in `TxInUndoFormatter`).
+///
+/// It contains the height of the block creating the output, whether it was created by a coinbase
+/// and the output itself, compressed as a [`CompressedTxOut`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxInUndo<'a> {
+    slice: &'a [u8],
+    code: u32,
+    tx_out: CompressedTxOut<'a>,
+}
+
+impl<'a> Parse<'a> for TxInUndo<'a> {
+    #[inline(always)]
+    fn parse(slice: &'a [u8]) -> SResult<Self> {
+        let mut consumed = 0;
+        let code = scan_var_int(slice, &mut consumed)?;
+        let code = u32::try_from(code).map_err(|_| Error::VarIntOverflow)?;
+        if code >> 1 > 0 {
+            // old versions stored the transaction version, now it's a dummy value
+            let _ = scan_var_int(&slice[consumed..], &mut consumed)?;
+        }
+        let tx_out = CompressedTxOut::parse(&slice[consumed..])?;
+        consumed += tx_out.consumed();
+        Ok(ParseResult::new(
+            tx_out.remaining(),
+            TxInUndo {
+                slice: &slice[..consumed],
+                code,
+                tx_out: tx_out.parsed_owned(),
+            },
+        ))
+    }
+}
+
+impl<'a> TxInUndo<'a> {
+    /// Returns the height of the block containing the transaction creating the spent output
+    pub fn height(&self) -> u32 {
+        self.code >> 1
+    }
+    /// Returns `true` if the spent output was created by a coinbase transaction
+    pub fn is_coinbase(&self) -> bool {
+        self.code & 1 == 1
+    }
+    /// Returns the amount of the spent output (satoshi)
+    pub fn value(&self) -> u64 {
+        self.tx_out.value()
+    }
+    /// Returns the script pubkey of the spent output, see [`CompressedScript::script_pubkey()`]
+    pub fn script_pubkey<'b>(
+        &'b self,
+        buffer: &'b mut [u8; SPECIAL_SCRIPT_MAX_LEN],
+    ) -> Result<&'b [u8], Error> {
+        self.tx_out.script_pubkey(buffer)
+    }
+    /// Returns the compressed script pubkey of the spent output
+    pub fn compressed_script(&self) -> &CompressedScript<'a> {
+        self.tx_out.compressed_script()
+    }
+}
+
+impl<'a> AsRef<[u8]> for TxInUndo<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.slice
+    }
+}
+
+/// The undo data of a transaction (`CTxUndo`): one [`TxInUndo`] for every input of the
+/// transaction, in the same order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxUndo<'a> {
+    slice: &'a [u8],
+    n: usize,
+    from: usize,
+}
+
+impl<'a> Visit<'a> for TxUndo<'a> {
+    #[inline(always)]
+    fn visit<'b, V: Visitor>(slice: &'a [u8], visit: &'b mut V) -> SResult<'a, Self> {
+        let mut consumed = 0;
+        let n = scan_len(slice, &mut consumed)? as usize;
+        let from = consumed;
+        visit.visit_tx_undo(n);
+        for vin in 0..n {
+            let tx_in_undo = TxInUndo::parse(&slice[consumed..])?;
+            consumed += tx_in_undo.consumed();
+            if let ControlFlow::Break(_) = visit.visit_tx_in_undo(vin, tx_in_undo.parsed()) {
+                return Err(Error::VisitBreak);
+            }
+        }
+        let (slice, remaining) = slice.split_at(consumed);
+        Ok(ParseResult::new(remaining, TxUndo { slice, n, from }))
+    }
+}
+
+impl<'a> TxUndo<'a> {
+    /// Returns the number of spent outputs, equal to the number of inputs of the transaction
+    pub fn n(&self) -> usize {
+        self.n
+    }
+    /// Returns an iterator over the undo data of the spent outputs
+    pub fn iter(&self) -> TxInUndoIterator<'a> {
+        TxInUndoIterator {
+            slice: &self.slice[self.from..],
+            remaining: self.n,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &TxUndo<'a> {
+    type Item = TxInUndo<'a>;
+    type IntoIter = TxInUndoIterator<'a>;
+
+    fn into_iter(self) -> TxInUndoIterator<'a> {
+        self.iter()
+    }
+}
+
+impl<'a> AsRef<[u8]> for TxUndo<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.slice
+    }
+}
+
+/// Iterator over the [`TxInUndo`] contained in [`TxUndo`], returned by [`TxUndo::iter()`]
+pub struct TxInUndoIterator<'a> {
+    slice: &'a [u8],
+    remaining: usize,
+}
+
+impl<'a> Iterator for TxInUndoIterator<'a> {
+    type Item = TxInUndo<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let tx_in_undo = TxInUndo::parse(self.slice).expect("slice validated during parsing");
+        self.slice = tx_in_undo.remaining();
+        Some(tx_in_undo.parsed_owned())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for TxInUndoIterator<'a> {}
+
+/// The undo data of a block (`CBlockUndo`), as stored by Bitcoin Core in its `rev*.dat` files.
+///
+/// It contains one [`TxUndo`] for every transaction of the block except the coinbase, in the same
+/// order. Use [`BlockUndo::visit_block()`] to visit a block together with the outputs spent by its
+/// inputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockUndo<'a> {
+    slice: &'a [u8],
+    n: usize,
+    from: usize,
+}
+
+impl<'a> Visit<'a> for BlockUndo<'a> {
+    #[inline(always)]
+    fn visit<'b, V: Visitor>(slice: &'a [u8], visit: &'b mut V) -> SResult<'a, Self> {
+        let mut consumed = 0;
+        let n = scan_len(slice, &mut consumed)? as usize;
+        let from = consumed;
+        visit.visit_block_undo(n);
+        for _ in 0..n {
+            let tx_undo = TxUndo::visit(&slice[consumed..], visit)?;
+            consumed += tx_undo.consumed();
+        }
+        let (slice, remaining) = slice.split_at(consumed);
+        Ok(ParseResult::new(remaining, BlockUndo { slice, n, from }))
+    }
+}
+
+impl<'a> BlockUndo<'a> {
+    /// Returns the number of [`TxUndo`], equal to the number of transactions in the block minus
+    /// the coinbase
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Returns an iterator over the undo data of the transactions
+    pub fn iter(&self) -> TxUndoIterator<'a> {
+        TxUndoIterator {
+            slice: &self.slice[self.from..],
+            remaining: self.n,
+        }
+    }
+
+    /// Visit the block in `block` calling [`Visitor::visit_tx_in_undo()`] with the spent output
+    /// right after [`Visitor::visit_tx_in()`] of every input spending it.
+    ///
+    /// Inputs of the coinbase don't spend outputs, thus no undo data is visited for them.
+    /// Returns [`Error::UndoMismatch`] if the transactions and the inputs of the block don't
+    /// match this undo data, which is the case when the undo data belongs to another block.
+    pub fn visit_block<'b, V: Visitor>(
+        &self,
+        block: &'a [u8],
+        visit: &'b mut V,
+    ) -> SResult<'a, Block<'a>> {
+        let mut with_undo = WithUndo {
+            visit,
+            txs: self.iter(),
+            tx_ins: None,
+            tx_index: 0,
+            mismatch: false,
+        };
+        let result = Block::visit(block, &mut with_undo);
+        if with_undo.mismatch || (result.is_ok() && with_undo.txs.len() > 0) {
+            return Err(Error::UndoMismatch);
+        }
+        result
+    }
+
+    /// Verify the checksum stored after the undo data in the `rev*.dat` files, which commits also
+    /// to the hash of the previous block `prev_blockhash`.
+    #[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+    pub fn verify_checksum(&self, prev_blockhash: &[u8; 32], checksum: &[u8; 32]) -> bool {
+        &crate::hashes::sha256d(&[prev_blockhash, self.slice]) == checksum
+    }
+}
+
+impl<'a> IntoIterator for &BlockUndo<'a> {
+    type Item = TxUndo<'a>;
+    type IntoIter = TxUndoIterator<'a>;
+
+    fn into_iter(self) -> TxUndoIterator<'a> {
+        self.iter()
+    }
+}
+
+impl<'a> AsRef<[u8]> for BlockUndo<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.slice
+    }
+}
+
+/// Iterator over the [`TxUndo`] contained in [`BlockUndo`], returned by [`BlockUndo::iter()`]
+pub struct TxUndoIterator<'a> {
+    slice: &'a [u8],
+    remaining: usize,
+}
+
+impl<'a> Iterator for TxUndoIterator<'a> {
+    type Item = TxUndo<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let tx_undo = TxUndo::parse(self.slice).expect("slice validated during parsing");
+        self.slice = tx_undo.remaining();
+        Some(tx_undo.parsed_owned())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for TxUndoIterator<'a> {}
+
+/// Forwards every call to the wrapped visitor, adding the undo data after every input.
+struct WithUndo<'a, 'b, V: Visitor> {
+    visit: &'b mut V,
+    txs: TxUndoIterator<'a>,
+    tx_ins: Option<TxInUndoIterator<'a>>,
+    /// Number of transactions completely visited
+    tx_index: usize,
+    mismatch: bool,
+}
+
+impl<'a, 'b, V: Visitor> Visitor for WithUndo<'a, 'b, V> {
+    fn visit_block_header(&mut self, header: &crate::bsl::BlockHeader) -> ControlFlow<()> {
+        self.visit.visit_block_header(header)
+    }
+    fn visit_block_begin(&mut self, total_transactions: usize) {
+        if total_transactions != self.txs.len() + 1 {
+            self.mismatch = true;
+        }
+        self.visit.visit_block_begin(total_transactions)
+    }
+    fn visit_transaction(&mut self, tx: &crate::bsl::Transaction) -> ControlFlow<()> {
+        if self.tx_index > 0 && self.tx_ins.take().map_or(true, |i| i.len() > 0) {
+            self.mismatch = true;
+            return ControlFlow::Break(());
+        }
+        self.tx_index += 1;
+        self.visit.visit_transaction(tx)
+    }
+    fn visit_tx_ins(&mut self, total_inputs: usize) {
+        self.visit.visit_tx_ins(total_inputs)
+    }
+    fn visit_tx_in(&mut self, vin: usize, tx_in: &crate::bsl::TxIn) -> ControlFlow<()> {
+        if self.mismatch {
+            return ControlFlow::Break(());
+        }
+        if let ControlFlow::Break(_) = self.visit.visit_tx_in(vin, tx_in) {
+            return ControlFlow::Break(());
+        }
+        if self.tx_index == 0 {
+            return ControlFlow::Continue(());
+        }
+        if self.tx_ins.is_none() {
+            match self.txs.next() {
+                Some(tx_undo) => {
+                    self.visit.visit_tx_undo(tx_undo.n());
+                    self.tx_ins = Some(tx_undo.iter());
+                }
+                None => {
+                    self.mismatch = true;
+                    return ControlFlow::Break(());
+                }
+            }
+        }
+        match self.tx_ins.as_mut().and_then(|i| i.next()) {
+            Some(tx_in_undo) => self.visit.visit_tx_in_undo(vin, &tx_in_undo),
+            None => {
+                self.mismatch = true;
+                ControlFlow::Break(())
+            }
+        }
+    }
+    fn visit_tx_outs(&mut self, total_outputs: usize) {
+        self.visit.visit_tx_outs(total_outputs)
+    }
+    fn visit_tx_out(&mut self, vout: usize, tx_out: &crate::bsl::TxOut) -> ControlFlow<()> {
+        self.visit.visit_tx_out(vout, tx_out)
+    }
+    fn visit_witness(&mut self, vin: usize) -> ControlFlow<()> {
+        self.visit.visit_witness(vin)
+    }
+    fn visit_witness_total_element(&mut self, witness_total: usize) {
+        self.visit.visit_witness_total_element(witness_total)
+    }
+    fn visit_witness_element(&mut self, witness_i: usize, witness_element: &[u8]) {
+        self.visit.visit_witness_element(witness_i, witness_element)
+    }
+    fn visit_witness_end(&mut self) {
+        self.visit.visit_witness_end()
+    }
+}
+
+/// Iterator over the block undo data contained in a Bitcoin Core undo file (`rev*.dat`), for
+/// example a memory-mapped file.
+///
+/// Undo files are a sequence of `magic | u32 size | block undo | checksum` records, where the
+/// checksum can be verified with [`BlockUndo::verify_checksum()`]. Like
+/// [`crate::bsl::BlockFile`], zero bytes terminate the iteration and obfuscated files must be
+/// deobfuscated with [`crate::bsl::deobfuscate`] first.
+///
+/// Every item is the [`BlockUndo`] with its offset in the file and the checksum following it.
+///
+/// After an error is returned the iteration ends.
+#[derive(Debug, Clone)]
+pub struct UndoFile<'a> {
+    slice: &'a [u8],
+    magic: [u8; 4],
+    offset: usize,
+}
+
+impl<'a> UndoFile<'a> {
+    /// Creates the iterator over the undo file data `slice` where every record is expected to be
+    /// prefixed by `magic`, see [`crate::bsl::p2p::magic`] for known values.
+    pub fn new(slice: &'a [u8], magic: [u8; 4]) -> Self {
+        UndoFile {
+            slice,
+            magic,
+            offset: 0,
+        }
+    }
+
+    fn next_undo(&mut self) -> Result<Option<(usize, BlockUndo<'a>, [u8; 32])>, Error> {
+        let (undo_offset, undo_slice) = match next_record(self.slice, self.offset, self.magic)? {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+        let end = undo_offset + undo_slice.len();
+        let checksum = self
+            .slice
+            .get(end..end + 32)
+            .ok_or(Error::MoreBytesNeeded)?;
+        let undo = BlockUndo::parse(undo_slice)?;
+        if !undo.remaining().is_empty() {
+            return Err(Error::BlockSizeMismatch);
+        }
+        self.offset = end + 32;
+        let checksum = checksum.try_into().expect("32 bytes");
+        Ok(Some((undo_offset, undo.parsed_owned(), checksum)))
+    }
+}
+
+impl<'a> Iterator for UndoFile<'a> {
+    type Item = Result<(usize, BlockUndo<'a>, [u8; 32]), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = self.next_undo().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.offset = self.slice.len();
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::ops::ControlFlow;
+
+    use super::{BlockUndo, TxInUndo, TxUndo, UndoFile};
+    use crate::{
+        bsl::{compressed::test::var_int, p2p::magic, TxIn, SPECIAL_SCRIPT_MAX_LEN},
+        test_common::GENESIS_BLOCK,
+        Error, Parse, Visitor,
+    };
+    use bitcoin::{consensus::serialize, hashes::Hash};
+
+    fn tx_in_undo(height: u32, coinbase: bool, compressed_amount: u64, hash: u8) -> Vec<u8> {
+        let mut bytes = var_int(((height as u64) << 1) | coinbase as u64);
+        if height > 0 {
+            bytes.push(0);
+        }
+        bytes.extend(var_int(compressed_amount));
+        bytes.push(0); // P2PKH
+        bytes.extend([hash; 20]);
+        bytes
+    }
+
+    fn tx(inputs: u8) -> bitcoin::Transaction {
+        bitcoin::Transaction {
+            version: bitcoin::transaction::Version::TWO,
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: (0..inputs)
+                .map(|i| bitcoin::TxIn {
+                    previous_output: bitcoin::OutPoint::new(
+                        bitcoin::Txid::from_byte_array([i; 32]),
+                        i as u32,
+                    ),
+                    witness: bitcoin::Witness::from_slice(&[[i]]),
+                    ..Default::default()
+                })
+                .collect(),
+            output: vec![],
+        }
+    }
+
+    /// A block with the genesis coinbase and two transactions spending 1 and 2 outputs and its
+    /// undo data
+    fn block_and_undo() -> (Vec<u8>, Vec<u8>) {
+        let genesis: bitcoin::Block = bitcoin::consensus::deserialize(&GENESIS_BLOCK).unwrap();
+        let block = bitcoin::Block {
+            header: genesis.header,
+            txdata: vec![genesis.txdata[0].clone(), tx(1), tx(2)],
+        };
+        let mut undo = vec![2u8, 1];
+        undo.extend(tx_in_undo(0, false, 9, 1));
+        undo.push(2);
+        undo.extend(tx_in_undo(100, true, 0x32, 2));
+        undo.extend(tx_in_undo(700_000, false, 7, 3));
+        (serialize(&block), undo)
+    }
+
+    #[test]
+    fn parse_tx_in_undo() {
+        let bytes = tx_in_undo(700_000, true, 0x32, 3);
+        let tx_in_undo = TxInUndo::parse(&bytes).unwrap();
+        assert_eq!(tx_in_undo.consumed(), bytes.len());
+        let tx_in_undo = tx_in_undo.parsed();
+        assert_eq!(tx_in_undo.height(), 700_000);
+        assert!(tx_in_undo.is_coinbase());
+        assert_eq!(tx_in_undo.value(), 50_0000_0000);
+        let mut buffer = [0u8; SPECIAL_SCRIPT_MAX_LEN];
+        let script = tx_in_undo.script_pubkey(&mut buffer).unwrap();
+        assert_eq!(&script[3..23], &[3u8; 20]);
+        assert!(tx_in_undo.compressed_script().is_special());
+
+        assert_eq!(
+            TxInUndo::parse(&bytes[..bytes.len() - 1]),
+            Err(Error::MoreBytesNeeded)
+        );
+        let overflow = var_int(1 << 32);
+        assert_eq!(TxInUndo::parse(&overflow), Err(Error::VarIntOverflow));
+    }
+
+    #[test]
+    fn parse_block_undo() {
+        let (_, undo) = block_and_undo();
+        let block_undo = BlockUndo::parse(&undo).unwrap();
+        assert_eq!(block_undo.consumed(), undo.len());
+        let block_undo = block_undo.parsed_owned();
+        assert_eq!(block_undo.n(), 2);
+        let txs: Vec<TxUndo> = block_undo.iter().collect();
+        assert_eq!(txs[0].n(), 1);
+        assert_eq!(txs[1].n(), 2);
+        let values: Vec<_> = txs.iter().flatten().map(|t| t.value()).collect();
+        assert_eq!(values, vec![1_0000_0000, 50_0000_0000, 1_000_000]);
+        let heights: Vec<_> = txs.iter().flatten().map(|t| t.height()).collect();
+        assert_eq!(heights, vec![0, 100, 700_000]);
+
+        for i in 0..undo.len() {
+            assert_eq!(BlockUndo::parse(&undo[..i]), Err(Error::MoreBytesNeeded));
+        }
+    }
+
+    #[derive(Default)]
+    struct Spent(Vec<(u8, usize, u64)>, usize);
+    impl Visitor for Spent {
+        fn visit_tx_in(&mut self, _vin: usize, tx_in: &TxIn) -> ControlFlow<()> {
+            self.1 = tx_in.prevout().txid()[0] as usize;
+            ControlFlow::Continue(())
+        }
+        fn visit_tx_in_undo(&mut self, vin: usize, tx_in_undo: &TxInUndo) -> ControlFlow<()> {
+            let mut buffer = [0u8; SPECIAL_SCRIPT_MAX_LEN];
+            let script = tx_in_undo.script_pubkey(&mut buffer).unwrap();
+            // the spent script and the prevout of the input just visited
+            self.0.push((script[3], self.1, tx_in_undo.value()));
+            assert_eq!(vin, self.1);
+            ControlFlow::Continue(())
+        }
+    }
+
+    #[test]
+    fn visit_block_with_undo() {
+        let (block, undo) = block_and_undo();
+        let block_undo = BlockUndo::parse(&undo).unwrap().parsed_owned();
+        let mut visitor = Spent::default();
+        let parsed = block_undo.visit_block(&block, &mut visitor).unwrap();
+        assert_eq!(parsed.parsed().total_transactions(), 3);
+        assert_eq!(
+            visitor.0,
+            vec![(1, 0, 1_0000_0000), (2, 0, 50_0000_0000), (3, 1, 1_000_000)]
+        );
+
+        let mut wrong_undo = undo.clone();
+        wrong_undo[0] = 1;
+        let wrong_undo = BlockUndo::parse(&wrong_undo).unwrap().parsed_owned();
+        let result = wrong_undo.visit_block(&block, &mut Spent::default());
+        assert_eq!(result, Err(Error::UndoMismatch));
+
+        let mut wrong_undo = vec![2u8, 2];
+        wrong_undo.extend(tx_in_undo(0, false, 9, 1));
+        wrong_undo.extend(tx_in_undo(0, false, 9, 1));
+        wrong_undo.push(1);
+        wrong_undo.extend(tx_in_undo(0, false, 9, 1));
+        let wrong_undo = BlockUndo::parse(&wrong_undo).unwrap().parsed_owned();
+        let result = wrong_undo.visit_block(&block, &mut Spent::default());
+        assert_eq!(result, Err(Error::UndoMismatch));
+
+        let result = block_undo.visit_block(&GENESIS_BLOCK, &mut Spent::default());
+        assert_eq!(result, Err(Error::UndoMismatch));
+    }
+
+    #[test]
+    fn undo_file() {
+        let (_, undo) = block_and_undo();
+        let checksum = [7u8; 32];
+        let mut data = vec![];
+        for _ in 0..2 {
+            data.extend(magic::MAINNET);
+            data.extend((undo.len() as u32).to_le_bytes());
+            data.extend(&undo);
+            data.extend(checksum);
+        }
+        data.extend([0u8; 10]);
+
+        let records: Vec<_> = UndoFile::new(&data, magic::MAINNET)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].0, 8);
+        assert_eq!(records[1].0, 8 + undo.len() + 32 + 8);
+        assert_eq!(records[1].1.as_ref(), &undo[..]);
+        assert_eq!(records[1].2, checksum);
+
+        let mut iter = UndoFile::new(&data[..data.len() - 11], magic::MAINNET);
+        assert!(iter.next().unwrap().is_ok());
+        assert_eq!(iter.next(), Some(Err(Error::MoreBytesNeeded)));
+        assert!(iter.next().is_none());
+    }
+
+    #[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+    #[test]
+    fn verify_checksum() {
+        let (_, undo) = block_and_undo();
+        let block_undo = BlockUndo::parse(&undo).unwrap().parsed_owned();
+        let prev_blockhash = [5u8; 32];
+        let mut preimage = prev_blockhash.to_vec();
+        preimage.extend(&undo);
+        let checksum = bitcoin::hashes::sha256d::Hash::hash(&preimage).to_byte_array();
+        assert!(block_undo.verify_checksum(&prev_blockhash, &checksum));
+        assert!(!block_undo.verify_checksum(&[6u8; 32], &checksum));
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn size_of() {
+        assert_eq!(std::mem::size_of::<TxInUndo>(), 80);
+        assert_eq!(std::mem::size_of::<BlockUndo>(), 32);
+    }
+}
@@ -1,4 +1,5 @@
 use super::scan_len;
+use crate::bsl::{ControlBlock, TaprootSpend, TAPROOT_ANNEX_TAG};
 use crate::{Error, Visit};
 use crate::{ParseResult, SResult, Visitor};
 
@@ -48,11 +49,107 @@ impl<'a> Witness<'a> {
     pub fn is_empty(&self) -> bool {
         self.n == 0
     }
+
+    /// Returns the annex of a taproot spend: the last element if it starts with
+    /// [`TAPROOT_ANNEX_TAG`] and there are at least two elements.
+    ///
+    /// The witness is assumed to spend a taproot output.
+    pub fn annex(&self) -> Option<&'a [u8]> {
+        if self.n < 2 {
+            return None;
+        }
+        self.iter()
+            .last()
+            .filter(|last| last.first() == Some(&TAPROOT_ANNEX_TAG))
+    }
+
+    /// Returns whether this witness spends a taproot output through the key path or the script
+    /// path, ignoring the annex.
+    ///
+    /// Returns [`Error::EmptyWitness`] if there are no elements other than the annex and
+    /// [`Error::InvalidControlBlock`] for a script path spend with a malformed control block.
+    /// The witness is assumed to spend a taproot output.
+    pub fn taproot_spend(&self) -> Result<TaprootSpend<'a>, Error> {
+        let n = self.n - self.annex().is_some() as usize;
+        let mut elements = self.iter().skip(n.saturating_sub(2));
+        match n {
+            0 => Err(Error::EmptyWitness),
+            1 => Ok(TaprootSpend::KeyPath {
+                signature: elements.next().expect("n elements"),
+            }),
+            _ => Ok(TaprootSpend::ScriptPath {
+                script: elements.next().expect("n elements"),
+                control_block: ControlBlock::new(elements.next().expect("n elements"))?,
+            }),
+        }
+    }
+
+    /// Returns the number of elements of this witness
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Returns an iterator over the elements of this witness
+    pub fn iter(&self) -> WitnessElementIterator<'a> {
+        let mut consumed = 0;
+        scan_len(self.slice, &mut consumed).expect("len granted by parsing");
+        WitnessElementIterator {
+            remaining: self.n,
+            slice: &self.slice[consumed..],
+        }
+    }
+
+    /// Returns the element at index `i`, if any
+    pub fn get(&self, i: usize) -> Option<&'a [u8]> {
+        self.iter().nth(i)
+    }
 }
 
+impl<'a> IntoIterator for &Witness<'a> {
+    type Item = &'a [u8];
+    type IntoIter = WitnessElementIterator<'a>;
+
+    /// Returns an iterator over the elements of the witness, see [`Witness::iter()`]
+    fn into_iter(self) -> WitnessElementIterator<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over the elements of a [`Witness`], returned by [`Witness::iter()`]
+#[derive(Debug, Clone)]
+pub struct WitnessElementIterator<'a> {
+    remaining: usize,
+    slice: &'a [u8],
+}
+
+impl<'a> Iterator for WitnessElementIterator<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let mut consumed = 0;
+        let len = scan_len(self.slice, &mut consumed).expect("len granted by parsing") as usize;
+        let (element, remaining) = self.slice[consumed..].split_at(len);
+        self.remaining -= 1;
+        self.slice = remaining;
+        Some(element)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for WitnessElementIterator<'a> {}
+
 #[cfg(test)]
 mod test {
-    use crate::{bsl::Witness, Parse, ParseResult, Visit, Visitor};
+    use crate::{
+        bsl::{ControlBlock, TaprootSpend, Witness},
+        Error, Parse, ParseResult, Visit, Visitor,
+    };
     use hex_lit::hex;
 
     const FUZZ_DATA: [u8; 14] = [
@@ -85,6 +182,90 @@ mod test {
         );
     }
 
+    fn serialize(elements: &[&[u8]]) -> Vec<u8> {
+        let mut witness = vec![elements.len() as u8];
+        for element in elements {
+            witness.push(element.len() as u8);
+            witness.extend_from_slice(element);
+        }
+        witness
+    }
+
+    #[test]
+    fn taproot() {
+        let signature = [1u8; 64];
+        let annex = [0x50, 0x01];
+        let script = [0x51];
+        let mut control_block = [2u8; 65];
+        control_block[0] = 0xc0;
+
+        let witness = serialize(&[&signature]);
+        let witness = Witness::parse(&witness[..]).unwrap().parsed_owned();
+        assert_eq!(witness.annex(), None);
+        let key_path = TaprootSpend::KeyPath {
+            signature: &signature[..],
+        };
+        assert_eq!(witness.taproot_spend(), Ok(key_path.clone()));
+
+        let witness = serialize(&[&signature, &annex]);
+        let witness = Witness::parse(&witness[..]).unwrap().parsed_owned();
+        assert_eq!(witness.annex(), Some(&annex[..]));
+        assert_eq!(witness.taproot_spend(), Ok(key_path));
+
+        // a single element is never an annex
+        let witness = serialize(&[&annex]);
+        let witness = Witness::parse(&witness[..]).unwrap().parsed_owned();
+        assert_eq!(witness.annex(), None);
+        let key_path = TaprootSpend::KeyPath {
+            signature: &annex[..],
+        };
+        assert_eq!(witness.taproot_spend(), Ok(key_path));
+
+        let script_path = TaprootSpend::ScriptPath {
+            script: &script[..],
+            control_block: ControlBlock::new(&control_block[..]).unwrap(),
+        };
+        for elements in [
+            &[&script[..], &control_block[..]][..],
+            &[&signature[..], &script[..], &control_block[..]][..],
+            &[&signature[..], &script[..], &control_block[..], &annex[..]][..],
+        ] {
+            let witness = serialize(elements);
+            let witness = Witness::parse(&witness[..]).unwrap().parsed_owned();
+            assert_eq!(witness.taproot_spend(), Ok(script_path.clone()));
+        }
+
+        let witness = serialize(&[&script, &control_block[..64]]);
+        let witness = Witness::parse(&witness[..]).unwrap().parsed_owned();
+        assert_eq!(witness.taproot_spend(), Err(Error::InvalidControlBlock));
+
+        let witness = Witness::parse(&[0u8][..]).unwrap().parsed_owned();
+        assert_eq!(witness.annex(), None);
+        assert_eq!(witness.taproot_spend(), Err(Error::EmptyWitness));
+    }
+
+    #[test]
+    fn iter_witness() {
+        let witness = hex!("030100020001020304");
+        let witness = Witness::parse(&witness[..]).unwrap().parsed_owned();
+        assert_eq!(witness.n(), 3);
+        let mut iter = witness.iter();
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.next(), Some(&[0u8][..]));
+        assert_eq!(iter.next(), Some(&[0u8, 1][..]));
+        assert_eq!(iter.len(), 1);
+        assert_eq!(iter.next(), Some(&[3u8, 4][..]));
+        assert_eq!(iter.next(), None);
+
+        assert_eq!(witness.get(1), Some(&[0u8, 1][..]));
+        assert_eq!(witness.get(3), None);
+        let elements: Vec<_> = (&witness).into_iter().collect();
+        assert_eq!(elements, vec![&[0u8][..], &[0, 1], &[3, 4]]);
+
+        let empty = Witness::parse(&[0u8][..]).unwrap().parsed_owned();
+        assert_eq!(empty.get(0), None);
+    }
+
     #[test]
     fn visit_witness() {
         let witness = hex!("0201000101");
@@ -1,12 +1,13 @@
 use core::ops::ControlFlow;
 
 use crate::bsl::Witness;
-use crate::{ParseResult, SResult, Visit};
+use crate::{Parse, ParseResult, SResult, Visit};
 
 /// Struct containining all the Witness in the tx (which is the same number as the inputs)
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct Witnesses<'a> {
     slice: &'a [u8],
+    n: usize,
     all_empty: bool,
 }
 impl<'a> AsRef<[u8]> for Witnesses<'a> {
@@ -16,6 +17,15 @@ impl<'a> AsRef<[u8]> for Witnesses<'a> {
 }
 
 impl<'a> Witnesses<'a> {
+    /// Creates the witnesses of `n` inputs from an already validated slice, like one part of a
+    /// parsed [`crate::bsl::Transaction`]
+    pub(crate) fn from_parsed(slice: &'a [u8], n: usize, all_empty: bool) -> Self {
+        Witnesses {
+            slice,
+            n,
+            all_empty,
+        }
+    }
     /// Parse the witnesses in the slice
     pub fn parse(slice: &'a [u8], total_inputs: usize) -> SResult<Self> {
         Self::visit(slice, total_inputs, &mut crate::visit::EmptyVisitor {})
@@ -46,6 +56,7 @@ impl<'a> Witnesses<'a> {
         }
         let witnesses = Witnesses {
             slice: &slice[..consumed],
+            n: total_inputs,
             all_empty,
         };
         Ok(ParseResult::new(&slice[consumed..], witnesses))
@@ -55,8 +66,62 @@ impl<'a> Witnesses<'a> {
     pub fn all_empty(&self) -> bool {
         self.all_empty
     }
+
+    /// Returns the number of witnesses, equal to the number of inputs of the transaction
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Returns an iterator over the [`Witness`] of every input
+    ///
+    /// If possible is better to use [`crate::Visitor::visit_witness`] to avoid double pass,
+    /// however, it may be convenient to iterate in case you already have validated the slice, for
+    /// example some data in a db.
+    pub fn iter(&self) -> WitnessIterator<'a> {
+        WitnessIterator {
+            remaining: self.n,
+            slice: self.slice,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &Witnesses<'a> {
+    type Item = Witness<'a>;
+    type IntoIter = WitnessIterator<'a>;
+
+    /// Returns an iterator over the [`Witness`] of every input, see [`Witnesses::iter()`]
+    fn into_iter(self) -> WitnessIterator<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over the [`Witness`] of [`Witnesses`], returned by [`Witnesses::iter()`]
+#[derive(Debug, Clone)]
+pub struct WitnessIterator<'a> {
+    remaining: usize,
+    slice: &'a [u8],
+}
+
+impl<'a> Iterator for WitnessIterator<'a> {
+    type Item = Witness<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let witness = Witness::parse(self.slice).expect("granted from parsing");
+        self.remaining -= 1;
+        self.slice = witness.remaining();
+        Some(witness.parsed_owned())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
 }
 
+impl<'a> ExactSizeIterator for WitnessIterator<'a> {}
+
 #[cfg(test)]
 mod test {
     use core::ops::ControlFlow;
@@ -73,6 +138,24 @@ mod test {
         assert_eq!(witnesses.consumed(), 8);
     }
 
+    #[test]
+    fn iter_witnesses() {
+        let witnesses_bytes = hex!("01010002010101020003"); // [[0]], [[1][2]], [] and 03 remaining
+        let witnesses = Witnesses::parse(&witnesses_bytes[..], 3).unwrap();
+        assert_eq!(witnesses.remaining(), &[3u8][..]);
+        let witnesses = witnesses.parsed_owned();
+        assert_eq!(witnesses.n(), 3);
+        let mut iter = witnesses.iter();
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.next().unwrap().get(0), Some(&[0u8][..]));
+        let witness = iter.next().unwrap();
+        assert_eq!(witness.n(), 2);
+        assert_eq!(witness.get(1), Some(&[2u8][..]));
+        assert!(iter.next().unwrap().is_empty());
+        assert!(iter.next().is_none());
+        assert_eq!((&witnesses).into_iter().count(), 3);
+    }
+
     #[test]
     fn visit_witnesses() {
         let witnesses_bytes = hex!("0101000201010102"); // first witness is [[0]], second witness is [[1][2]]
@@ -0,0 +1,509 @@
+//! Error details returned by `visit_detailed` on [`crate::bsl::Block`],
+//! [`crate::bsl::Transaction`] and [`crate::bsl::p2p::CompactBlock`]: where the error happened and
+//! in which object.
+//!
+//! [`Error`] is kept small because it's returned in the hot path of every parsing, details are
+//! instead collected by a visitor wrapping the user one and keeping track of the position of the
+//! last successfully visited object, so that normal parsing doesn't pay for them.
+
+use core::ops::ControlFlow;
+
+use crate::{
+    bsl::{self, compact_size_len, compact_size_len_of_prefix, scan_len},
+    Error, ParseResult, Visit, Visitor,
+};
+
+/// The object being parsed when an error happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorContext {
+    /// The visited object outside of the transactions, like the header of a block or the version
+    /// of a transaction before any input is visited
+    Root,
+
+    /// The transaction at index `tx` outside of its inputs, outputs and witnesses, like the segwit
+    /// flag, the number of outputs or the locktime
+    Transaction {
+        /// Index of the transaction in the block, 0 when visiting a transaction
+        tx: usize,
+    },
+
+    /// An input of a transaction
+    TxIn {
+        /// Index of the transaction in the block, 0 when visiting a transaction
+        tx: usize,
+        /// Index of the input in the transaction
+        vin: usize,
+    },
+
+    /// An output of a transaction
+    TxOut {
+        /// Index of the transaction in the block, 0 when visiting a transaction
+        tx: usize,
+        /// Index of the output in the transaction
+        vout: usize,
+    },
+
+    /// A witness of a transaction
+    Witness {
+        /// Index of the transaction in the block, 0 when visiting a transaction
+        tx: usize,
+        /// Index of the input of this witness
+        vin: usize,
+        /// Index of the element in the witness, `None` if the error is in the number of elements
+        element: Option<usize>,
+    },
+}
+
+/// An [`Error`] with the position and the context where it happened, returned by
+/// [`crate::bsl::Block::visit_detailed()`] and the analogous methods.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetailedError {
+    error: Error,
+    offset: usize,
+    context: ErrorContext,
+    expected: Option<usize>,
+    available: usize,
+}
+
+impl DetailedError {
+    /// The error returned by the parsing
+    pub fn error(&self) -> &Error {
+        &self.error
+    }
+
+    /// The offset in the visited slice where the object failing to parse starts.
+    ///
+    /// It's exact for inputs, outputs, witnesses and for objects following them, otherwise it's
+    /// the end of the last successfully visited object.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The object being parsed when the error happened
+    pub fn context(&self) -> ErrorContext {
+        self.context
+    }
+
+    /// The number of bytes needed by the object failing to parse starting from
+    /// [`DetailedError::offset()`], known only for [`Error::MoreBytesNeeded`] in inputs, outputs
+    /// and witnesses.
+    pub fn expected(&self) -> Option<usize> {
+        self.expected
+    }
+
+    /// The number of bytes available in the visited slice starting from
+    /// [`DetailedError::offset()`]
+    pub fn available(&self) -> usize {
+        self.available
+    }
+}
+
+impl DetailedError {
+    /// Returns the length of the visited slice needed at minimum to go past the object failed
+    /// with [`Error::MoreBytesNeeded`]
+    pub(crate) fn needed(&self) -> usize {
+        let expected = self.expected.unwrap_or(0).max(self.available + 1);
+        self.offset.saturating_add(expected)
+    }
+}
+
+impl From<DetailedError> for Error {
+    fn from(value: DetailedError) -> Self {
+        value.error
+    }
+}
+
+/// Visits `T` in `slice` through a [`Tracker`] wrapping `visit`.
+///
+/// The tracker relies on the callbacks of the visited type to know where the transactions start,
+/// so `T` must be a block, a transaction or a compact block.
+pub(crate) fn visit_detailed<'a, T: Visit<'a>, V: Visitor>(
+    slice: &'a [u8],
+    visit: &mut V,
+) -> Result<ParseResult<'a, T>, DetailedError> {
+    let mut tracker = Tracker::new(slice, visit);
+    T::visit(slice, &mut tracker).map_err(|e| tracker.detail(e))
+}
+
+/// Wraps the user visitor to track the position and the context of the next object to parse.
+struct Tracker<'s, 'v, V> {
+    inner: &'v mut V,
+    slice: &'s [u8],
+
+    /// Position of the next byte to parse
+    pos: usize,
+    context: ErrorContext,
+
+    /// Index of the current transaction, and the position where it starts
+    tx: usize,
+    tx_start: usize,
+    inputs_seen: bool,
+    prefilled_seen: bool,
+    total: usize,
+}
+
+impl<'s, 'v, V: Visitor> Tracker<'s, 'v, V> {
+    fn new(slice: &'s [u8], inner: &'v mut V) -> Self {
+        Tracker {
+            inner,
+            slice,
+            pos: 0,
+            context: ErrorContext::Root,
+            tx: 0,
+            tx_start: 0,
+            inputs_seen: false,
+            prefilled_seen: false,
+            total: 0,
+        }
+    }
+
+    /// Builds the detailed error for `error` happened while visiting the slice
+    fn detail(&self, error: Error) -> DetailedError {
+        let offset = self.pos.min(self.slice.len());
+        let remaining = &self.slice[offset..];
+        let expected = match (&error, self.context) {
+            (Error::MoreBytesNeeded, ErrorContext::TxIn { .. }) => {
+                Some(prefixed_len(remaining, 36, 4))
+            }
+            (Error::MoreBytesNeeded, ErrorContext::TxOut { .. }) => {
+                Some(prefixed_len(remaining, 8, 0))
+            }
+            (Error::MoreBytesNeeded, ErrorContext::Witness { element, .. }) => {
+                Some(match element {
+                    Some(_) => prefixed_len(remaining, 0, 0),
+                    None => remaining
+                        .first()
+                        .map(compact_size_len_of_prefix)
+                        .unwrap_or(1),
+                })
+            }
+            _ => None,
+        };
+        DetailedError {
+            error,
+            offset,
+            context: self.context,
+            expected,
+            available: remaining.len(),
+        }
+    }
+
+    /// Returns the position after the end of `slice`, which must be a part of the visited slice
+    fn end_of(&self, slice: &[u8]) -> usize {
+        (slice.as_ptr() as usize - self.slice.as_ptr() as usize) + slice.len()
+    }
+
+    fn end_transaction(&mut self) {
+        self.context = ErrorContext::Transaction { tx: self.tx };
+    }
+}
+
+impl<'s, 'v, V: Visitor> Visitor for Tracker<'s, 'v, V> {
+    fn visit_block_header(&mut self, header: &bsl::BlockHeader) -> ControlFlow<()> {
+        if let ControlFlow::Break(_) = self.inner.visit_block_header(header) {
+            return ControlFlow::Break(());
+        }
+        self.pos = self.end_of(header.as_ref());
+        ControlFlow::Continue(())
+    }
+    fn visit_block_begin(&mut self, total_transactions: usize) {
+        self.inner.visit_block_begin(total_transactions);
+        self.pos += compact_size_len(total_transactions);
+        self.tx_start = self.pos;
+        self.end_transaction();
+    }
+    fn visit_transaction(&mut self, tx: &bsl::Transaction) -> ControlFlow<()> {
+        if let ControlFlow::Break(_) = self.inner.visit_transaction(tx) {
+            return ControlFlow::Break(());
+        }
+        self.pos = self.end_of(tx.as_ref());
+        self.tx += 1;
+        self.tx_start = self.pos;
+        self.inputs_seen = false;
+        self.end_transaction();
+        ControlFlow::Continue(())
+    }
+    fn visit_tx_ins(&mut self, total_inputs: usize) {
+        self.inner.visit_tx_ins(total_inputs);
+        // called a second time after the segwit marker and flag
+        let skip = if self.inputs_seen { 6 } else { 4 };
+        self.inputs_seen = true;
+        self.pos = self.tx_start + skip + compact_size_len(total_inputs);
+        self.total = total_inputs;
+        self.context = match total_inputs {
+            0 => ErrorContext::Transaction { tx: self.tx },
+            _ => ErrorContext::TxIn {
+                tx: self.tx,
+                vin: 0,
+            },
+        };
+    }
+    fn visit_tx_in(&mut self, vin: usize, tx_in: &bsl::TxIn) -> ControlFlow<()> {
+        if let ControlFlow::Break(_) = self.inner.visit_tx_in(vin, tx_in) {
+            return ControlFlow::Break(());
+        }
+        self.pos = self.end_of(tx_in.as_ref());
+        if vin + 1 < self.total {
+            self.context = ErrorContext::TxIn {
+                tx: self.tx,
+                vin: vin + 1,
+            };
+        } else {
+            self.end_transaction();
+        }
+        ControlFlow::Continue(())
+    }
+    fn visit_tx_outs(&mut self, total_outputs: usize) {
+        self.inner.visit_tx_outs(total_outputs);
+        self.pos += compact_size_len(total_outputs);
+        self.total = total_outputs;
+        self.context = match total_outputs {
+            0 => ErrorContext::Transaction { tx: self.tx },
+            _ => ErrorContext::TxOut {
+                tx: self.tx,
+                vout: 0,
+            },
+        };
+    }
+    fn visit_tx_out(&mut self, vout: usize, tx_out: &bsl::TxOut) -> ControlFlow<()> {
+        if let ControlFlow::Break(_) = self.inner.visit_tx_out(vout, tx_out) {
+            return ControlFlow::Break(());
+        }
+        self.pos = self.end_of(tx_out.as_ref());
+        if vout + 1 < self.total {
+            self.context = ErrorContext::TxOut {
+                tx: self.tx,
+                vout: vout + 1,
+            };
+        } else {
+            self.end_transaction();
+        }
+        ControlFlow::Continue(())
+    }
+    fn visit_witness(&mut self, vin: usize) -> ControlFlow<()> {
+        self.context = ErrorContext::Witness {
+            tx: self.tx,
+            vin,
+            element: None,
+        };
+        self.inner.visit_witness(vin)
+    }
+    fn visit_witness_total_element(&mut self, witness_total: usize) {
+        self.inner.visit_witness_total_element(witness_total);
+        self.pos += compact_size_len(witness_total);
+        if let ErrorContext::Witness { element, .. } = &mut self.context {
+            *element = Some(0);
+        }
+    }
+    fn visit_witness_element(&mut self, witness_i: usize, witness_element: &[u8]) {
+        self.inner.visit_witness_element(witness_i, witness_element);
+        self.pos = self.end_of(witness_element);
+        if let ErrorContext::Witness { element, .. } = &mut self.context {
+            *element = Some(witness_i + 1);
+        }
+    }
+    fn visit_witness_end(&mut self) {
+        self.inner.visit_witness_end();
+        self.end_transaction();
+    }
+    fn visit_message(&mut self, message: &bsl::p2p::Message) -> ControlFlow<()> {
+        self.inner.visit_message(message)
+    }
+    fn visit_invs(&mut self, total_invs: usize) {
+        self.inner.visit_invs(total_invs)
+    }
+    fn visit_inv(&mut self, i: usize, kind: u32, hash: &[u8]) -> ControlFlow<()> {
+        self.inner.visit_inv(i, kind, hash)
+    }
+    fn visit_headers(&mut self, total_headers: usize) {
+        self.inner.visit_headers(total_headers)
+    }
+    fn visit_addrs(&mut self, total_addrs: usize) {
+        self.inner.visit_addrs(total_addrs)
+    }
+    fn visit_addr(&mut self, i: usize, address: &bsl::p2p::Address) -> ControlFlow<()> {
+        self.inner.visit_addr(i, address)
+    }
+    fn visit_addr_v2(&mut self, i: usize, address: &bsl::p2p::AddressV2) -> ControlFlow<()> {
+        self.inner.visit_addr_v2(i, address)
+    }
+    fn visit_prefilled_transaction(&mut self, index: usize) -> ControlFlow<()> {
+        if let ControlFlow::Break(_) = self.inner.visit_prefilled_transaction(index) {
+            return ControlFlow::Break(());
+        }
+        // the compact block has been parsed up to the differential index of this transaction,
+        // which is preceded by the previous transaction or, for the first one, by the header, the
+        // nonce, the short ids and the number of prefilled transactions
+        let mut pos = self.pos;
+        if !self.prefilled_seen {
+            self.prefilled_seen = true;
+            pos += 8;
+            let short_ids = scan_len(&self.slice[pos..], &mut pos).expect("granted by parsing");
+            pos += short_ids as usize * 6;
+            scan_len(&self.slice[pos..], &mut pos).expect("granted by parsing");
+        }
+        scan_len(&self.slice[pos..], &mut pos).expect("granted by parsing");
+        self.pos = pos;
+        self.tx = index;
+        self.tx_start = pos;
+        self.inputs_seen = false;
+        self.end_transaction();
+        ControlFlow::Continue(())
+    }
+    fn visit_block_undo(&mut self, total_txs: usize) {
+        self.inner.visit_block_undo(total_txs)
+    }
+    fn visit_tx_undo(&mut self, total_spent: usize) {
+        self.inner.visit_tx_undo(total_spent)
+    }
+    fn visit_tx_in_undo(&mut self, vin: usize, tx_in_undo: &bsl::TxInUndo) -> ControlFlow<()> {
+        self.inner.visit_tx_in_undo(vin, tx_in_undo)
+    }
+    fn visit_snapshot_coin(&mut self, coin: &bsl::SnapshotCoin) -> ControlFlow<()> {
+        self.inner.visit_snapshot_coin(coin)
+    }
+    fn visit_merkle_match(&mut self, position: usize, txid: &[u8]) -> ControlFlow<()> {
+        self.inner.visit_merkle_match(position, txid)
+    }
+}
+
+/// Returns the length of an object made of `prefix` bytes, a length prefixed field and `suffix`
+/// bytes, as much as it's known from the available `slice`.
+fn prefixed_len(slice: &[u8], prefix: usize, suffix: usize) -> usize {
+    let Some(first) = slice.get(prefix) else {
+        return prefix + 1;
+    };
+    let mut consumed = 0;
+    match scan_len(&slice[prefix..], &mut consumed) {
+        Ok(len) => usize::try_from(len)
+            .unwrap_or(usize::MAX)
+            .saturating_add(prefix + consumed + suffix),
+        Err(_) => prefix + compact_size_len_of_prefix(first),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hex_lit::hex;
+
+    use crate::{
+        bsl::{Block, Transaction},
+        test_common::{GENESIS_BLOCK, GENESIS_TX},
+        EmptyVisitor, Error,
+    };
+
+    use super::ErrorContext;
+
+    const SEGWIT_TX: [u8; 222] = hex!("010000000001010000000000000000000000000000000000000000000000000000000000000000ffffffff3603da1b0e00045503bd5704c7dd8a0d0ced13bb5785010800000000000a636b706f6f6c122f4e696e6a61506f6f6c2f5345475749542fffffffff02b4e5a212000000001976a914876fbb82ec05caa6af7a3b5e5a983aae6c6cc6d688ac0000000000000000266a24aa21a9edf91c46b49eb8a29089980f02ee6b57e7d63d33b18b4fddac2bcd7db2a39837040120000000000000000000000000000000000000000000000000000000000000000000000000");
+
+    #[test]
+    fn truncated_genesis_block() {
+        let ok = Block::visit_detailed(&GENESIS_BLOCK, &mut EmptyVisitor {}).unwrap();
+        assert_eq!(ok.consumed(), GENESIS_BLOCK.len());
+
+        let err = Block::visit_detailed(&GENESIS_BLOCK[..50], &mut EmptyVisitor {}).unwrap_err();
+        assert_eq!(err.error(), &Error::MoreBytesNeeded);
+        assert_eq!(err.context(), ErrorContext::Root);
+        assert_eq!(
+            (err.offset(), err.available(), err.expected()),
+            (0, 50, None)
+        );
+
+        // genesis tx input starts at 81 (header and tx count) + 5 (version and input count)
+        let err = Block::visit_detailed(&GENESIS_BLOCK[..100], &mut EmptyVisitor {}).unwrap_err();
+        assert_eq!(err.context(), ErrorContext::TxIn { tx: 0, vin: 0 });
+        assert_eq!(err.offset(), 86);
+        assert_eq!(err.available(), 14);
+        assert_eq!(err.expected(), Some(36 + 1), "script length not available");
+        let err = Block::visit_detailed(&GENESIS_BLOCK[..126], &mut EmptyVisitor {}).unwrap_err();
+        assert_eq!(err.offset(), 86);
+        assert_eq!(err.available(), 40);
+        assert_eq!(err.expected(), Some(36 + 1 + 77 + 4));
+
+        // the output starts after the input and the output count
+        let vout_offset = 86 + 118 + 1;
+        let err = Block::visit_detailed(&GENESIS_BLOCK[..vout_offset + 9], &mut EmptyVisitor {})
+            .unwrap_err();
+        assert_eq!(err.context(), ErrorContext::TxOut { tx: 0, vout: 0 });
+        assert_eq!(err.offset(), vout_offset);
+        assert_eq!(err.expected(), Some(8 + 1 + 67));
+
+        let err = Block::visit_detailed(&GENESIS_BLOCK[..283], &mut EmptyVisitor {}).unwrap_err();
+        assert_eq!(err.context(), ErrorContext::Transaction { tx: 0 });
+        assert_eq!(err.offset(), 281);
+        assert_eq!(err.available(), 2);
+
+        let mut two_txs = GENESIS_BLOCK.to_vec();
+        two_txs[80] = 2;
+        two_txs.extend(&GENESIS_TX[..10]);
+        let err = Block::visit_detailed(&two_txs[..], &mut EmptyVisitor {}).unwrap_err();
+        assert_eq!(err.context(), ErrorContext::TxIn { tx: 1, vin: 0 });
+        assert_eq!(err.offset(), GENESIS_BLOCK.len() + 5);
+    }
+
+    #[test]
+    fn segwit_transaction() {
+        let tx = Transaction::visit_detailed(&SEGWIT_TX, &mut EmptyVisitor {}).unwrap();
+        assert_eq!(tx.consumed(), SEGWIT_TX.len());
+
+        // witness starts after version, marker, flag, input and outputs
+        let witness_offset = SEGWIT_TX.len() - 4 - 34;
+        let err =
+            Transaction::visit_detailed(&SEGWIT_TX[..witness_offset + 10], &mut EmptyVisitor {})
+                .unwrap_err();
+        let context = ErrorContext::Witness {
+            tx: 0,
+            vin: 0,
+            element: Some(0),
+        };
+        assert_eq!(err.context(), context);
+        assert_eq!(err.offset(), witness_offset + 1);
+        assert_eq!(err.available(), 9);
+        assert_eq!(err.expected(), Some(33));
+
+        let err = Transaction::visit_detailed(&SEGWIT_TX[..witness_offset], &mut EmptyVisitor {})
+            .unwrap_err();
+        let context = ErrorContext::Witness {
+            tx: 0,
+            vin: 0,
+            element: None,
+        };
+        assert_eq!(err.context(), context);
+        assert_eq!((err.offset(), err.expected()), (witness_offset, Some(1)));
+
+        let mut flag = SEGWIT_TX;
+        flag[5] = 2;
+        let err = Transaction::visit_detailed(&flag, &mut EmptyVisitor {}).unwrap_err();
+        assert_eq!(err.error(), &Error::UnknownSegwitFlag(2));
+        assert_eq!(err.context(), ErrorContext::Transaction { tx: 0 });
+        assert_eq!(err.expected(), None);
+    }
+
+    #[test]
+    fn every_truncation() {
+        for i in 0..SEGWIT_TX.len() {
+            let err =
+                Transaction::visit_detailed(&SEGWIT_TX[..i], &mut EmptyVisitor {}).unwrap_err();
+            assert_eq!(err.error(), &Error::MoreBytesNeeded);
+            assert_eq!(err.offset() + err.available(), i);
+            assert!(err.expected().map(|e| e > err.available()).unwrap_or(true));
+        }
+        for i in 0..GENESIS_BLOCK.len() {
+            let err = Block::visit_detailed(&GENESIS_BLOCK[..i], &mut EmptyVisitor {}).unwrap_err();
+            assert_eq!(err.offset() + err.available(), i);
+            assert!(err.expected().map(|e| e > err.available()).unwrap_or(true));
+        }
+    }
+
+    #[test]
+    fn non_minimal() {
+        let mut tx = GENESIS_TX.to_vec();
+        tx[4] = 0xfd;
+        tx.insert(5, 0);
+        tx.insert(5, 1);
+        let err = Transaction::visit_detailed(&tx[..], &mut EmptyVisitor {}).unwrap_err();
+        assert_eq!(err.error(), &Error::NonMinimalVarInt);
+        assert_eq!(err.context(), ErrorContext::Root);
+        assert_eq!(Error::from(err), Error::NonMinimalVarInt);
+    }
+}
@@ -18,6 +18,108 @@ pub enum Error {
     /// for example because it found what it was searching for
     VisitBreak,
 
+    /// The network magic doesn't match the expected one
+    UnexpectedMagic,
+
+    /// The command of a network message contains non printable chars or it is not NUL padded
+    InvalidCommand,
+
+    /// The payload of a network message is bigger than the maximum allowed
+    MessageTooLarge,
+
+    /// The checksum of a network message doesn't match its payload
+    InvalidChecksum,
+
+    /// A BIP155 network address is longer than 512 bytes or its length doesn't match the one
+    /// required by its network id
+    InvalidNetworkAddress,
+
+    /// A differentially encoded transaction index of a compact block message doesn't fit in 16
+    /// bits
+    DifferentialIndexOverflow,
+
+    /// The size of a block in a block file doesn't match the size of the parsed block
+    BlockSizeMismatch,
+
+    /// The decoded Bitcoin Core VARINT, or compact size in the Bitcoin Core on-disk formats, doesn't
+    /// fit in the expected integer type
+    VarIntOverflow,
+
+    /// A public key stored compressed cannot be decompressed because it's not a valid point on the
+    /// curve
+    InvalidPublicKey,
+
+    /// The undo data doesn't match the transactions or the inputs of the visited block
+    UndoMismatch,
+
+    /// The key of a database entry doesn't start with the expected prefix
+    UnexpectedPrefix,
+
+    /// The version of a file format is not supported
+    UnsupportedVersion,
+
+    /// The number of coins in a UTXO snapshot doesn't match the one declared in its metadata
+    CoinsCountMismatch,
+
+    /// A push of a script requires more bytes than the ones left, contains the offset of the push
+    /// opcode in the script
+    TruncatedPush(u32),
+
+    /// The merkle root of the transactions doesn't match the one in the block header
+    MerkleRootMismatch,
+
+    /// The merkle tree of the transactions is mutated by duplicating some of them (CVE-2012-2459)
+    MutatedMerkleTree,
+
+    /// The witness commitment in the coinbase doesn't match the witnesses of the transactions, or
+    /// transactions have witnesses but the coinbase doesn't commit to them
+    WitnessCommitmentMismatch,
+
+    /// The partial merkle tree of a merkle block is malformed
+    InvalidMerkleProof,
+
+    /// The compact encoding of a proof of work target is negative, overflowing or zero
+    InvalidTarget,
+
+    /// The block hash is greater than the target
+    InsufficientProofOfWork,
+
+    /// The previous block hash of a header doesn't match the tip of the chain
+    PrevBlockhashMismatch,
+
+    /// The target of a block header is not the one required by the difficulty adjustment rules
+    UnexpectedBits,
+
+    /// The timestamp of a block header is not greater than the median time past of the chain
+    TimeTooOld,
+
+    /// The first block header of a difficulty period precedes the previous one by more than
+    /// allowed by BIP94
+    TimewarpAttack,
+
+    /// The input index is not lower than the number of inputs of the transaction
+    InputIndexOutOfRange,
+
+    /// The output spent by an input is needed but it has not been provided
+    MissingSpentOutput,
+
+    /// The signature hash type is not valid for the signature version
+    InvalidSighashType,
+
+    /// A taproot signature hash with `SIGHASH_SINGLE` has no output with the same index of the
+    /// input
+    SighashSingleWithoutOutput,
+
+    /// A witness has no elements where at least one is required
+    EmptyWitness,
+
+    /// The control block of a taproot script path spend is not 33 bytes plus up to 128 nodes of
+    /// 32 bytes
+    InvalidControlBlock,
+
+    /// The buffer provided by the caller is smaller than needed
+    BufferTooSmall,
+
     /// Other error with an error code to be usable by downstream crates
     Other(u32),
 }
@@ -0,0 +1,156 @@
+//! Hashing primitives used internally, backed by `bitcoin_hashes` if enabled or by `sha2`
+//! otherwise.
+
+/// Incremental sha256
+#[cfg(feature = "bitcoin_hashes")]
+pub(crate) struct Sha256Engine(crate::bitcoin_hashes::sha256::HashEngine);
+
+#[cfg(feature = "bitcoin_hashes")]
+impl Sha256Engine {
+    pub(crate) fn new() -> Self {
+        use crate::bitcoin_hashes::{sha256, Hash};
+        Self(sha256::Hash::engine())
+    }
+    pub(crate) fn input(&mut self, data: &[u8]) {
+        crate::bitcoin_hashes::HashEngine::input(&mut self.0, data)
+    }
+    pub(crate) fn finalize(self) -> [u8; 32] {
+        use crate::bitcoin_hashes::{sha256, Hash};
+        sha256::Hash::from_engine(self.0).to_byte_array()
+    }
+}
+
+/// Incremental sha256
+#[cfg(all(feature = "sha2", not(feature = "bitcoin_hashes")))]
+pub(crate) struct Sha256Engine(crate::sha2::Sha256);
+
+#[cfg(all(feature = "sha2", not(feature = "bitcoin_hashes")))]
+impl Sha256Engine {
+    pub(crate) fn new() -> Self {
+        Self(crate::sha2::Digest::new())
+    }
+    pub(crate) fn input(&mut self, data: &[u8]) {
+        crate::sha2::Digest::update(&mut self.0, data)
+    }
+    pub(crate) fn finalize(self) -> [u8; 32] {
+        crate::sha2::Digest::finalize(self.0).into()
+    }
+}
+
+impl Sha256Engine {
+    /// Creates an engine for the BIP340 tagged hash with the given `tag`
+    pub(crate) fn tagged(tag: &[u8]) -> Self {
+        let tag_hash = sha256(&[tag]);
+        let mut engine = Sha256Engine::new();
+        engine.input(&tag_hash);
+        engine.input(&tag_hash);
+        engine
+    }
+
+    /// Input `len` encoded as a compact int
+    pub(crate) fn input_len(&mut self, len: usize) {
+        match len {
+            0..=0xFC => self.input(&[len as u8]),
+            0xFD..=0xFFFF => {
+                self.input(&[0xFD]);
+                self.input(&(len as u16).to_le_bytes());
+            }
+            0x10000..=0xFFFF_FFFF => {
+                self.input(&[0xFE]);
+                self.input(&(len as u32).to_le_bytes());
+            }
+            _ => {
+                self.input(&[0xFF]);
+                self.input(&(len as u64).to_le_bytes());
+            }
+        }
+    }
+}
+
+/// Single sha256 of the concatenation of `parts`
+pub(crate) fn sha256(parts: &[&[u8]]) -> [u8; 32] {
+    let mut engine = Sha256Engine::new();
+    for part in parts {
+        engine.input(part);
+    }
+    engine.finalize()
+}
+
+/// Double sha256 of the concatenation of `parts`
+pub(crate) fn sha256d(parts: &[&[u8]]) -> [u8; 32] {
+    sha256(&[&sha256(parts)[..]])
+}
+
+/// SipHash-2-4 of `data` with the given keys
+pub(crate) fn siphash24(k0: u64, k1: u64, data: &[u8]) -> u64 {
+    let mut v = [
+        0x736f6d6570736575 ^ k0,
+        0x646f72616e646f6d ^ k1,
+        0x6c7967656e657261 ^ k0,
+        0x7465646279746573 ^ k1,
+    ];
+    let chunks = data.chunks_exact(8);
+    let mut last = (data.len() as u64 & 0xff) << 56;
+    for (i, b) in chunks.remainder().iter().enumerate() {
+        last |= (*b as u64) << (8 * i);
+    }
+    for chunk in chunks {
+        let m = u64::from_le_bytes(chunk.try_into().expect("chunks of 8"));
+        v[3] ^= m;
+        sip_round(&mut v);
+        sip_round(&mut v);
+        v[0] ^= m;
+    }
+    v[3] ^= last;
+    sip_round(&mut v);
+    sip_round(&mut v);
+    v[0] ^= last;
+    v[2] ^= 0xff;
+    for _ in 0..4 {
+        sip_round(&mut v);
+    }
+    v[0] ^ v[1] ^ v[2] ^ v[3]
+}
+
+#[inline(always)]
+fn sip_round(v: &mut [u64; 4]) {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13) ^ v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16) ^ v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21) ^ v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17) ^ v[2];
+    v[2] = v[2].rotate_left(32);
+}
+
+#[cfg(test)]
+mod test {
+    use hex_lit::hex;
+
+    #[test]
+    fn sha256d() {
+        assert_eq!(
+            super::sha256d(&[]),
+            hex!("5df6e0e2761359d30a8275058e299fcc0381534545f55cf43e41983f5d4c9456")
+        );
+        assert_eq!(super::sha256d(&[b"ab", b"c"]), super::sha256d(&[b"abc"]));
+    }
+
+    #[test]
+    fn siphash24() {
+        use bitcoin::hashes::siphash24;
+        let data: Vec<u8> = (0..64).collect();
+        let (k0, k1) = (0x0706050403020100, 0x0f0e0d0c0b0a0908);
+        for i in 0..data.len() {
+            assert_eq!(
+                super::siphash24(k0, k1, &data[..i]),
+                siphash24::Hash::hash_to_u64_with_keys(k0, k1, &data[..i]),
+            );
+        }
+        // test vector from the SipHash paper
+        assert_eq!(super::siphash24(k0, k1, &data[..15]), 0xa129ca6149be45e5);
+    }
+}
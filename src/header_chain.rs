@@ -0,0 +1,532 @@
+//! Contextual validation of a chain of block headers, as done by Bitcoin Core when accepting
+//! headers: linkage to the previous header, difficulty retargeting, median time past and
+//! accumulated chain work.
+//!
+//! The validator keeps a fixed size state and doesn't allocate, so it only supports extending the
+//! chain from its tip: handling reorgs is left to the caller, for example by keeping clones of the
+//! [`HeaderChain`] at interesting heights.
+//!
+//! ```
+//! # use bitcoin_slices::{bsl::BlockHeader, header_chain::{HeaderChain, MAINNET}, Parse};
+//! let mut chain = HeaderChain::new(MAINNET);
+//! let block_1 = hex_lit::hex!("010000006fe28c0ab6f1b372c1a6a246ae63f74f931e8365e15a089c68d6190000000000982051fd1e4ba744bbbe680e1fee14677ba1a3c3540bf7b1cdb606e857233e0e61bc6649ffff001d01e36299");
+//! let header = BlockHeader::parse(&block_1[..]).unwrap().parsed_owned();
+//! chain.push(&header).unwrap();
+//! assert_eq!(chain.height(), 1);
+//! assert_eq!(chain.chain_work().to_u128(), Some(0x200020002));
+//! ```
+
+use crate::{bsl::BlockHeader, Error, Parse, U256};
+
+/// The number of previous block timestamps used to compute the median time past
+const MEDIAN_TIME_SPAN: usize = 11;
+
+/// The maximum time the first block of a difficulty period may precede the last block of the
+/// previous period, when [`ChainParams::enforce_bip94`] is set
+const MAX_TIMEWARP: i64 = 600;
+
+/// Merkle root of the genesis block of every network but testnet4, in byte order
+const BITCOIN_GENESIS_MERKLE_ROOT: [u8; 32] = [
+    0x3b, 0xa3, 0xed, 0xfd, 0x7a, 0x7b, 0x12, 0xb2, 0x7a, 0xc7, 0x2c, 0x3e, 0x67, 0x76, 0x8f, 0x61,
+    0x7f, 0xc8, 0x1b, 0xc3, 0x88, 0x8a, 0x51, 0x32, 0x3a, 0x9f, 0xb8, 0xaa, 0x4b, 0x1e, 0x5e, 0x4a,
+];
+
+/// Merkle root of the genesis block of testnet4, in byte order
+const TESTNET4_GENESIS_MERKLE_ROOT: [u8; 32] = [
+    0x4e, 0x7b, 0x2b, 0x91, 0x28, 0xfe, 0x02, 0x91, 0xdb, 0x06, 0x93, 0xaf, 0x2a, 0xe4, 0x18, 0xb7,
+    0x67, 0xe6, 0x57, 0xcd, 0x40, 0x7e, 0x80, 0xcb, 0x14, 0x34, 0x22, 0x1e, 0xae, 0xa7, 0xa0, 0x7a,
+];
+
+/// Serialize a genesis block header, which has version 1 and no previous block
+const fn genesis_header(merkle_root: [u8; 32], time: u32, bits: u32, nonce: u32) -> [u8; 80] {
+    let mut header = [0u8; 80];
+    header[0] = 1;
+    let mut i = 0;
+    while i < 32 {
+        header[36 + i] = merkle_root[i];
+        i += 1;
+    }
+    let fields = [time.to_le_bytes(), bits.to_le_bytes(), nonce.to_le_bytes()];
+    let mut i = 0;
+    while i < 12 {
+        header[68 + i] = fields[i / 4][i % 4];
+        i += 1;
+    }
+    header
+}
+
+/// The consensus parameters of a network needed to validate block headers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainParams {
+    /// The serialized header of the genesis block
+    pub genesis_header: [u8; 80],
+    /// The easiest target allowed, in compact form
+    pub pow_limit: u32,
+    /// The expected time between blocks, in seconds
+    pub pow_target_spacing: u32,
+    /// The expected duration of a difficulty period, in seconds
+    pub pow_target_timespan: u32,
+    /// Blocks may use the `pow_limit` target if they come more than twice the target spacing
+    /// after the previous one (testnet 20 minutes rule)
+    pub allow_min_difficulty_blocks: bool,
+    /// The target never changes
+    pub no_retargeting: bool,
+    /// Enforce BIP94: retarget from the first block of the period and prevent the timewarp attack
+    pub enforce_bip94: bool,
+}
+
+/// Chain parameters of mainnet
+pub const MAINNET: ChainParams = ChainParams {
+    genesis_header: genesis_header(
+        BITCOIN_GENESIS_MERKLE_ROOT,
+        1231006505,
+        0x1d00ffff,
+        2083236893,
+    ),
+    pow_limit: 0x1d00ffff,
+    pow_target_spacing: 600,
+    pow_target_timespan: 14 * 24 * 60 * 60,
+    allow_min_difficulty_blocks: false,
+    no_retargeting: false,
+    enforce_bip94: false,
+};
+
+/// Chain parameters of testnet version 3
+pub const TESTNET3: ChainParams = ChainParams {
+    genesis_header: genesis_header(
+        BITCOIN_GENESIS_MERKLE_ROOT,
+        1296688602,
+        0x1d00ffff,
+        414098458,
+    ),
+    allow_min_difficulty_blocks: true,
+    ..MAINNET
+};
+
+/// Chain parameters of testnet version 4
+pub const TESTNET4: ChainParams = ChainParams {
+    genesis_header: genesis_header(
+        TESTNET4_GENESIS_MERKLE_ROOT,
+        1714777860,
+        0x1d00ffff,
+        393743547,
+    ),
+    allow_min_difficulty_blocks: true,
+    enforce_bip94: true,
+    ..MAINNET
+};
+
+/// Chain parameters of the default signet
+pub const SIGNET: ChainParams = ChainParams {
+    genesis_header: genesis_header(
+        BITCOIN_GENESIS_MERKLE_ROOT,
+        1598918400,
+        0x1e0377ae,
+        52613770,
+    ),
+    pow_limit: 0x1e0377ae,
+    ..MAINNET
+};
+
+/// Chain parameters of regtest
+pub const REGTEST: ChainParams = ChainParams {
+    genesis_header: genesis_header(BITCOIN_GENESIS_MERKLE_ROOT, 1296688602, 0x207fffff, 2),
+    pow_limit: 0x207fffff,
+    allow_min_difficulty_blocks: true,
+    no_retargeting: true,
+    ..MAINNET
+};
+
+impl ChainParams {
+    /// Returns the number of blocks in a difficulty period, 2016 for every predefined network
+    pub fn difficulty_adjustment_interval(&self) -> u32 {
+        self.pow_target_timespan / self.pow_target_spacing
+    }
+
+    /// Returns the target, in compact form, of the first block of a difficulty period given the
+    /// target `bits` it is computed from and the timestamps of the first and the last block of the
+    /// previous period.
+    ///
+    /// The elapsed time is clamped to a factor of 4 of the target timespan and the result to
+    /// the `pow_limit`, like `CalculateNextWorkRequired` in Bitcoin Core.
+    pub fn retarget(&self, bits: u32, first_time: u32, last_time: u32) -> u32 {
+        if self.no_retargeting {
+            return bits;
+        }
+        let timespan = self.pow_target_timespan as i64;
+        let actual = (last_time as i64 - first_time as i64).clamp(timespan / 4, timespan * 4);
+        let pow_limit = U256::from_compact(self.pow_limit).unwrap_or(U256::MAX);
+        let target = U256::from_compact(bits).unwrap_or(pow_limit);
+        let target = target.mul_u64(actual as u64) / U256::from(timespan as u64);
+        target.min(pow_limit).to_compact()
+    }
+}
+
+/// Validates block headers extending a chain, starting from the genesis block.
+///
+/// Only rules depending on the previous headers are checked, plus the proof of work of the
+/// header. The check of the timestamp against the current time is left to the caller.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderChain {
+    params: ChainParams,
+    tip_hash: [u8; 32],
+    height: u32,
+    bits: u32,
+    /// Timestamps of the last blocks, the one at height `h` is at index `h % MEDIAN_TIME_SPAN`
+    times: [u32; MEDIAN_TIME_SPAN],
+    period_start_time: u32,
+    period_start_bits: u32,
+    /// Bits of the last block not using the min difficulty exception
+    last_regular_bits: u32,
+    chain_work: U256,
+}
+
+impl HeaderChain {
+    /// Creates a chain containing only the genesis block of the given network
+    pub fn new(params: ChainParams) -> Self {
+        let genesis = BlockHeader::parse(&params.genesis_header[..])
+            .expect("genesis header is 80 bytes")
+            .parsed_owned();
+        HeaderChain {
+            params,
+            tip_hash: crate::hashes::sha256d(&[genesis.as_ref()]),
+            height: 0,
+            bits: genesis.bits(),
+            times: [genesis.time(); MEDIAN_TIME_SPAN],
+            period_start_time: genesis.time(),
+            period_start_bits: genesis.bits(),
+            last_regular_bits: genesis.bits(),
+            chain_work: genesis.work(),
+        }
+    }
+
+    /// Returns the parameters of the network of this chain
+    pub fn params(&self) -> &ChainParams {
+        &self.params
+    }
+
+    /// Returns the hash of the last header of the chain
+    pub fn tip_hash(&self) -> [u8; 32] {
+        self.tip_hash
+    }
+
+    /// Returns the height of the last header of the chain
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the sum of the work of every header of the chain, genesis included
+    pub fn chain_work(&self) -> U256 {
+        self.chain_work
+    }
+
+    /// Returns the median of the timestamps of the last 11 headers of the chain, or of all of them
+    /// if the chain is shorter. A new header must have a timestamp greater than this.
+    pub fn median_time_past(&self) -> u32 {
+        let count = (self.height as usize + 1).min(MEDIAN_TIME_SPAN);
+        let mut times = [0u32; MEDIAN_TIME_SPAN];
+        times[..count].copy_from_slice(&self.times[..count]);
+        times[..count].sort_unstable();
+        times[count / 2]
+    }
+
+    /// Returns the target, in compact form, required for the next header if it has timestamp
+    /// `time`, the timestamp matters only for networks allowing min difficulty blocks.
+    pub fn next_bits(&self, time: u32) -> u32 {
+        let params = &self.params;
+        let interval = params.difficulty_adjustment_interval();
+        if (self.height + 1) % interval != 0 {
+            if params.allow_min_difficulty_blocks {
+                let spacing = params.pow_target_spacing as i64;
+                if time as i64 > self.tip_time() as i64 + spacing * 2 {
+                    return params.pow_limit;
+                }
+                return self.last_regular_bits;
+            }
+            return self.bits;
+        }
+        let bits = if params.enforce_bip94 {
+            self.period_start_bits
+        } else {
+            self.bits
+        };
+        params.retarget(bits, self.period_start_time, self.tip_time())
+    }
+
+    /// Validates `header` as the next one of the chain and makes it the new tip.
+    ///
+    /// Returns [`Error::PrevBlockhashMismatch`] if it doesn't build on the tip,
+    /// [`Error::UnexpectedBits`] if its target is not the required one,
+    /// [`Error::InsufficientProofOfWork`] if its hash doesn't meet the target,
+    /// [`Error::TimeTooOld`] if its timestamp is not after the median time past and
+    /// [`Error::TimewarpAttack`] if BIP94 is enforced and the first block of a difficulty period
+    /// precedes the previous block by more than 10 minutes. The chain is left untouched on error.
+    pub fn push(&mut self, header: &BlockHeader) -> Result<(), Error> {
+        if header.prev_blockhash() != self.tip_hash {
+            return Err(Error::PrevBlockhashMismatch);
+        }
+        if header.bits() != self.next_bits(header.time()) {
+            return Err(Error::UnexpectedBits);
+        }
+        header.validate_pow()?;
+        if header.time() <= self.median_time_past() {
+            return Err(Error::TimeTooOld);
+        }
+        let height = self.height + 1;
+        let interval = self.params.difficulty_adjustment_interval();
+        if self.params.enforce_bip94
+            && height % interval == 0
+            && (header.time() as i64) < self.tip_time() as i64 - MAX_TIMEWARP
+        {
+            return Err(Error::TimewarpAttack);
+        }
+
+        self.tip_hash = crate::hashes::sha256d(&[header.as_ref()]);
+        self.height = height;
+        self.bits = header.bits();
+        self.times[height as usize % MEDIAN_TIME_SPAN] = header.time();
+        if height % interval == 0 {
+            self.period_start_time = header.time();
+            self.period_start_bits = header.bits();
+        }
+        if height % interval == 0 || header.bits() != self.params.pow_limit {
+            self.last_regular_bits = header.bits();
+        }
+        self.chain_work = self.chain_work + header.work();
+        Ok(())
+    }
+
+    fn tip_time(&self) -> u32 {
+        self.times[self.height as usize % MEDIAN_TIME_SPAN]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hex_lit::hex;
+
+    use super::*;
+    use crate::test_common::reverse;
+
+    #[test]
+    fn retarget() {
+        // vectors from Bitcoin Core pow_tests.cpp
+        assert_eq!(
+            MAINNET.retarget(0x1d00ffff, 1261130161, 1262152739),
+            0x1d00d86a
+        );
+        assert_eq!(
+            MAINNET.retarget(0x1d00ffff, 1231006505, 1233061996),
+            0x1d00ffff
+        );
+        assert_eq!(
+            MAINNET.retarget(0x1c05a3f4, 1279008237, 1279297671),
+            0x1c0168fd
+        );
+        assert_eq!(
+            MAINNET.retarget(0x1c387f6f, 1263163443, 1269211443),
+            0x1d00e1fd
+        );
+        assert_eq!(REGTEST.retarget(0x207fffff, 0, 1), 0x207fffff);
+        assert_eq!(MAINNET.difficulty_adjustment_interval(), 2016);
+    }
+
+    #[test]
+    fn genesis() {
+        let expected = [
+            (
+                MAINNET,
+                hex!("000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"),
+            ),
+            (
+                TESTNET3,
+                hex!("000000000933ea01ad0ee984209779baaec3ced90fa3f408719526f8d77f4943"),
+            ),
+            (
+                TESTNET4,
+                hex!("00000000da84f2bafbbc53dee25a72ae507ff4914b867c565be350b0da8bf043"),
+            ),
+            (
+                SIGNET,
+                hex!("00000008819873e925422c1ff0f99f7cc9bbb232af63a077a480a3633bee1ef6"),
+            ),
+            (
+                REGTEST,
+                hex!("0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206"),
+            ),
+        ];
+        for (params, hash) in expected {
+            let chain = HeaderChain::new(params);
+            assert_eq!(chain.tip_hash(), reverse(hash));
+            assert_eq!(chain.height(), 0);
+        }
+        assert_eq!(
+            HeaderChain::new(MAINNET).chain_work().to_u128(),
+            Some(0x100010001)
+        );
+    }
+
+    #[test]
+    fn mainnet_linkage() {
+        let mut chain = HeaderChain::new(MAINNET);
+        let block_2 = hex!("010000004860eb18bf1b1620e37e9490fc8a427514416fd75159ab86688e9a8300000000d5fdcc541e25de1c7a5addedf24858b8bb665c9f36ef744ee42c316022c90f9bb0bc6649ffff001d08d2bd61");
+        let header = BlockHeader::parse(&block_2[..]).unwrap().parsed_owned();
+        assert_eq!(chain.push(&header), Err(Error::PrevBlockhashMismatch));
+        assert_eq!(chain.height(), 0);
+
+        let block_1 = hex!("010000006fe28c0ab6f1b372c1a6a246ae63f74f931e8365e15a089c68d6190000000000982051fd1e4ba744bbbe680e1fee14677ba1a3c3540bf7b1cdb606e857233e0e61bc6649ffff001d01e36299");
+        let header = BlockHeader::parse(&block_1[..]).unwrap().parsed_owned();
+        chain.push(&header).unwrap();
+        let header = BlockHeader::parse(&block_2[..]).unwrap().parsed_owned();
+        chain.push(&header).unwrap();
+        assert_eq!(chain.height(), 2);
+        assert_eq!(
+            chain.tip_hash(),
+            reverse(hex!(
+                "000000006a625f06636b8bb6ac7b960a8d03705d1ace08b1a19da3fdcc99ddbd"
+            ))
+        );
+        assert_eq!(chain.median_time_past(), 1231469665);
+
+        let mut tampered = block_1;
+        tampered[76] ^= 1;
+        let chain = HeaderChain::new(MAINNET);
+        let header = BlockHeader::parse(&tampered[..]).unwrap().parsed_owned();
+        assert_eq!(
+            chain.clone().push(&header),
+            Err(Error::InsufficientProofOfWork)
+        );
+    }
+
+    /// Mine a header on top of `prev_blockhash` with the given `time` and `bits`
+    fn mine(prev_blockhash: [u8; 32], time: u32, bits: u32) -> [u8; 80] {
+        let mut header = [0u8; 80];
+        header[0] = 4;
+        header[4..36].copy_from_slice(&prev_blockhash);
+        header[68..72].copy_from_slice(&time.to_le_bytes());
+        header[72..76].copy_from_slice(&bits.to_le_bytes());
+        for nonce in 0u32.. {
+            header[76..80].copy_from_slice(&nonce.to_le_bytes());
+            let parsed = BlockHeader::parse(&header[..]).unwrap().parsed_owned();
+            if parsed.validate_pow().is_ok() {
+                break;
+            }
+        }
+        header
+    }
+
+    fn push(chain: &mut HeaderChain, time: u32, bits: u32) -> Result<(), Error> {
+        let header = mine(chain.tip_hash(), time, bits);
+        let header = BlockHeader::parse(&header[..]).unwrap().parsed_owned();
+        chain.push(&header)
+    }
+
+    const START: u32 = 1_000_000;
+
+    /// A network with an easy target, retargeting every 4 blocks spaced 1 second
+    fn fast_params(allow_min_difficulty_blocks: bool, enforce_bip94: bool) -> ChainParams {
+        ChainParams {
+            genesis_header: mine([0u8; 32], START, 0x2000ffff),
+            pow_limit: 0x2000ffff,
+            pow_target_spacing: 1,
+            pow_target_timespan: 4,
+            allow_min_difficulty_blocks,
+            no_retargeting: false,
+            enforce_bip94,
+        }
+    }
+
+    #[test]
+    fn retarget_and_time() {
+        let mut chain = HeaderChain::new(fast_params(false, false));
+        let genesis_work = chain.chain_work();
+
+        // the period lasts 3 seconds instead of 4: the target goes down by 3/4 at height 4
+        for i in 1..4 {
+            assert_eq!(chain.next_bits(START + i), 0x2000ffff);
+            push(&mut chain, START + i, 0x2000ffff).unwrap();
+        }
+        assert_eq!(chain.next_bits(START + 4), 0x2000bfff);
+        assert_eq!(
+            push(&mut chain, START + 4, 0x2000ffff),
+            Err(Error::UnexpectedBits)
+        );
+        push(&mut chain, START + 4, 0x2000bfff).unwrap();
+        assert_eq!(chain.height(), 4);
+        let work = U256::from_compact(0x2000bfff).unwrap().target_to_work();
+        assert_eq!(chain.chain_work(), genesis_work.mul_u64(4) + work);
+
+        // median of the 5 timestamps is the one of height 2
+        assert_eq!(chain.median_time_past(), START + 2);
+        assert_eq!(
+            push(&mut chain, START + 2, 0x2000bfff),
+            Err(Error::TimeTooOld)
+        );
+        push(&mut chain, START + 3, 0x2000bfff).unwrap();
+        assert_eq!(chain.height(), 5);
+    }
+
+    #[test]
+    fn min_difficulty() {
+        let mut chain = HeaderChain::new(fast_params(true, false));
+        for i in 1..4 {
+            push(&mut chain, START + i, 0x2000ffff).unwrap();
+        }
+        push(&mut chain, START + 4, 0x2000bfff).unwrap();
+
+        // a block more than twice the target spacing after the previous one can use the pow limit
+        assert_eq!(chain.next_bits(START + 6), 0x2000bfff);
+        assert_eq!(chain.next_bits(START + 7), 0x2000ffff);
+        push(&mut chain, START + 7, 0x2000ffff).unwrap();
+
+        // the following one must go back to the last regular target
+        assert_eq!(chain.next_bits(START + 8), 0x2000bfff);
+        assert_eq!(
+            push(&mut chain, START + 8, 0x2000ffff),
+            Err(Error::UnexpectedBits)
+        );
+        push(&mut chain, START + 8, 0x2000bfff).unwrap();
+    }
+
+    #[test]
+    fn bip94() {
+        let mut chain = HeaderChain::new(fast_params(true, true));
+        let mut chain_no_bip94 = HeaderChain::new(fast_params(true, false));
+        let blocks = [
+            (1, 0x2000ffff),
+            (2, 0x2000ffff),
+            (3, 0x2000ffff),
+            (4, 0x2000bfff),
+        ];
+        let blocks = blocks
+            .iter()
+            .chain(&[(5, 0x2000bfff), (6, 0x2000bfff), (9, 0x2000ffff)]);
+        for (time, bits) in blocks {
+            let header = mine(chain.tip_hash(), START + time, *bits);
+            let header = BlockHeader::parse(&header[..]).unwrap().parsed_owned();
+            chain.push(&header).unwrap();
+            chain_no_bip94.push(&header).unwrap();
+        }
+
+        // retarget from the first block of the period, not from the last min difficulty one
+        assert_eq!(chain.next_bits(START + 10), 0x2000effe);
+        assert_eq!(chain_no_bip94.next_bits(START + 10), 0x2000ffff);
+
+        let mut chain = HeaderChain::new(fast_params(true, true));
+        push(&mut chain, START + 1, 0x2000ffff).unwrap();
+        push(&mut chain, START + 2, 0x2000ffff).unwrap();
+        push(&mut chain, START + 10_000, 0x2000ffff).unwrap();
+        assert_eq!(
+            push(&mut chain, START + 10_000 - 601, 0x2000ffff),
+            Err(Error::TimewarpAttack)
+        );
+        push(&mut chain, START + 10_000 - 600, 0x2000ffff).unwrap();
+        assert_eq!(chain.height(), 4);
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn size_of() {
+        assert_eq!(std::mem::size_of::<HeaderChain>(), 224);
+    }
+}
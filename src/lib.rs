@@ -1,28 +1,43 @@
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 #![deny(missing_docs)]
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 #![warn(missing_docs)]
 #![doc = include_str!("../README.md")]
 
+#[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+pub mod address;
 pub mod bsl;
+mod detailed;
 mod error;
+#[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+mod hashes;
+#[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+pub mod header_chain;
 pub mod number;
 mod parse_result;
+mod secp256k1;
+#[cfg(any(feature = "bitcoin_hashes", feature = "sha2"))]
+pub mod sighash;
 mod slice;
+#[cfg(feature = "std")]
+pub mod stream;
+mod u256;
 mod visit;
 
 #[cfg(feature = "slice_cache")]
 mod slice_cache;
 
-#[cfg(feature = "slice_cache")]
-#[macro_use]
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "slice_cache", macro_use)]
 extern crate alloc;
 
 #[cfg(feature = "slice_cache")]
 pub use slice_cache::SliceCache;
 
+pub use detailed::{DetailedError, ErrorContext};
 pub use error::Error;
 pub use parse_result::ParseResult;
+pub use u256::U256;
 
 #[allow(deprecated)]
 pub use slice::read_slice;
@@ -1,4 +1,4 @@
-//! Contains methods to parse numbers (u8,u16,u32,u64,i32) from slices
+//! Contains methods to parse numbers (u8,u16,u32,u64,i32,i64) from slices
 
 use core::convert::TryInto;
 
@@ -175,6 +175,39 @@ pub fn read_u64(slice: &[u8]) -> Result<u64, Error> {
     ]))
 }
 
+#[inline(always)]
+/// Read a i64 from a slice
+pub fn read_i64(slice: &[u8]) -> Result<i64, Error> {
+    read_u64(slice).map(|n| n as i64)
+}
+
+/// Same as [`crate::bsl::scan_len`] but for the VARINT encoding used by Bitcoin Core in its
+/// on-disk formats (undo data, chainstate), which is different from the compact int used in
+/// network serialization.
+///
+/// Every byte carries 7 bits of the value with the MSB set if more bytes follow, moreover every
+/// continuation adds one to the value so that every number has a unique encoding.
+#[inline(always)]
+pub fn scan_var_int(slice: &[u8], consumed: &mut usize) -> Result<u64, Error> {
+    let mut n = 0u64;
+    for (i, byte) in slice.iter().enumerate() {
+        if n > (u64::MAX >> 7) {
+            return Err(Error::VarIntOverflow);
+        }
+        n = (n << 7) | (byte & 0x7F) as u64;
+        if byte & 0x80 != 0 {
+            if n == u64::MAX {
+                return Err(Error::VarIntOverflow);
+            }
+            n += 1;
+        } else {
+            *consumed += i + 1;
+            return Ok(n);
+        }
+    }
+    Err(Error::MoreBytesNeeded)
+}
+
 #[cfg(test)]
 mod test {
     use crate::ParseResult;
@@ -215,6 +248,54 @@ mod test {
             read_u64(&[1u8, 2, 3, 4, 5, 6, 7]),
             Err(Error::MoreBytesNeeded)
         );
+
+        assert_eq!(
+            read_i64(&[255u8, 255, 255, 255, 255, 255, 255, 255]),
+            Ok(-1i64)
+        );
+        assert_eq!(read_i64(&[1u8, 0, 0, 0, 0, 0, 0, 0]), Ok(1i64));
+        assert_eq!(read_i64(&[1u8, 2, 3]), Err(Error::MoreBytesNeeded));
+    }
+
+    #[test]
+    fn var_int() {
+        use hex_lit::hex;
+        // from bitcoin core varint_bitpatterns test
+        let vectors: [(&[u8], u64); 10] = [
+            (&hex!("00"), 0),
+            (&hex!("7f"), 0x7f),
+            (&hex!("8000"), 0x80),
+            (&hex!("a334"), 0x1234),
+            (&hex!("82fe7f"), 0xffff),
+            (&hex!("c7e756"), 0x123456),
+            (&hex!("86ffc7e756"), 0x80123456),
+            (&hex!("8efefefe7f"), 0xffffffff),
+            (&hex!("fefefefefefefefe7f"), 0x7fffffffffffffff),
+            (&hex!("80fefefefefefefefe7f"), 0xffffffffffffffff),
+        ];
+        for (bytes, expected) in &vectors {
+            let mut consumed = 0;
+            assert_eq!(scan_var_int(bytes, &mut consumed), Ok(*expected));
+            assert_eq!(consumed, bytes.len());
+
+            let mut consumed = 0;
+            let bytes_more = [bytes, &[0xFFu8][..]].concat();
+            assert_eq!(scan_var_int(&bytes_more, &mut consumed), Ok(*expected));
+            assert_eq!(consumed, bytes.len());
+
+            assert_eq!(
+                scan_var_int(&bytes[..bytes.len() - 1], &mut 0),
+                Err(Error::MoreBytesNeeded)
+            );
+        }
+        assert_eq!(
+            scan_var_int(&hex!("80fefefefefefefeff00"), &mut 0),
+            Err(Error::VarIntOverflow)
+        );
+        assert_eq!(
+            scan_var_int(&hex!("80fefefefefefefefe8000"), &mut 0),
+            Err(Error::VarIntOverflow)
+        );
     }
 
     #[test]
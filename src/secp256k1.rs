@@ -0,0 +1,192 @@
+//! Minimal secp256k1 field arithmetic, needed only to decompress public keys.
+
+/// Field element as little-endian 64 bits limbs.
+type Fe = [u64; 4];
+
+/// The field prime `2^256 - 2^32 - 977`
+const P: Fe = [
+    0xFFFFFFFEFFFFFC2F,
+    0xFFFFFFFFFFFFFFFF,
+    0xFFFFFFFFFFFFFFFF,
+    0xFFFFFFFFFFFFFFFF,
+];
+
+/// `(P + 1) / 4`, since `P = 3 mod 4` the square root of `a` is `a^((P + 1) / 4)`
+const SQRT_EXP: Fe = [
+    0xFFFFFFFFBFFFFF0C,
+    0xFFFFFFFFFFFFFFFF,
+    0xFFFFFFFFFFFFFFFF,
+    0x3FFFFFFFFFFFFFFF,
+];
+
+/// `2^256 mod P`
+const R: u128 = 0x1000003D1;
+
+fn from_be_bytes(bytes: &[u8]) -> Fe {
+    let mut fe = [0u64; 4];
+    for (i, limb) in fe.iter_mut().enumerate() {
+        let begin = 24 - i * 8;
+        *limb = u64::from_be_bytes(bytes[begin..begin + 8].try_into().expect("8 bytes"));
+    }
+    fe
+}
+
+fn to_be_bytes(fe: &Fe, out: &mut [u8]) {
+    for (i, limb) in fe.iter().enumerate() {
+        let begin = 24 - i * 8;
+        out[begin..begin + 8].copy_from_slice(&limb.to_be_bytes());
+    }
+}
+
+fn gte_p(a: &Fe) -> bool {
+    for i in (0..4).rev() {
+        if a[i] != P[i] {
+            return a[i] > P[i];
+        }
+    }
+    true
+}
+
+fn sub_p(a: &mut Fe) {
+    let mut borrow = 0u64;
+    for i in 0..4 {
+        let (d, b1) = a[i].overflowing_sub(P[i]);
+        let (d, b2) = d.overflowing_sub(borrow);
+        a[i] = d;
+        borrow = (b1 | b2) as u64;
+    }
+}
+
+/// Reduce a 512 bits number modulo `P`
+fn reduce(t: [u64; 8]) -> Fe {
+    let mut r = [0u64; 4];
+    let mut carry = 0u128;
+    for i in 0..4 {
+        let v = t[i] as u128 + t[i + 4] as u128 * R + carry;
+        r[i] = v as u64;
+        carry = v >> 64;
+    }
+    // fold the remaining carry, at most two times
+    while carry > 0 {
+        let mut v = carry * R;
+        for limb in r.iter_mut() {
+            v += *limb as u128;
+            *limb = v as u64;
+            v >>= 64;
+        }
+        carry = v;
+    }
+    if gte_p(&r) {
+        sub_p(&mut r);
+    }
+    r
+}
+
+fn mul(a: &Fe, b: &Fe) -> Fe {
+    let mut t = [0u64; 8];
+    for i in 0..4 {
+        let mut carry = 0u128;
+        for j in 0..4 {
+            let v = t[i + j] as u128 + a[i] as u128 * b[j] as u128 + carry;
+            t[i + j] = v as u64;
+            carry = v >> 64;
+        }
+        t[i + 4] = carry as u64;
+    }
+    reduce(t)
+}
+
+fn add_small(a: &Fe, b: u64) -> Fe {
+    let mut t = [a[0], a[1], a[2], a[3], 0, 0, 0, 0];
+    let mut v = b as u128;
+    for limb in t.iter_mut() {
+        v += *limb as u128;
+        *limb = v as u64;
+        v >>= 64;
+    }
+    reduce(t)
+}
+
+fn pow(a: &Fe, exp: &Fe) -> Fe {
+    let mut result = [1u64, 0, 0, 0];
+    for limb in exp.iter().rev() {
+        for bit in (0..64).rev() {
+            result = mul(&result, &result);
+            if (limb >> bit) & 1 == 1 {
+                result = mul(&result, a);
+            }
+        }
+    }
+    result
+}
+
+/// Decompress a 33 bytes public key (`0x02` or `0x03` prefix and x coordinate) into its 65 bytes
+/// uncompressed form (`0x04` prefix, x and y coordinates).
+///
+/// Returns `None` if the prefix is invalid or x is not the coordinate of a point on the curve.
+pub(crate) fn decompress_pubkey(compressed: &[u8; 33]) -> Option<[u8; 65]> {
+    let odd = match compressed[0] {
+        0x02 => false,
+        0x03 => true,
+        _ => return None,
+    };
+    let x = from_be_bytes(&compressed[1..]);
+    if gte_p(&x) {
+        return None;
+    }
+    let y_square = add_small(&mul(&mul(&x, &x), &x), 7);
+    let mut y = pow(&y_square, &SQRT_EXP);
+    if mul(&y, &y) != y_square {
+        return None;
+    }
+    if (y[0] & 1 == 1) != odd {
+        // y = P - y, y is not zero since 7 is not a cube
+        let mut neg = P;
+        let mut borrow = 0u64;
+        for i in 0..4 {
+            let (d, b1) = neg[i].overflowing_sub(y[i]);
+            let (d, b2) = d.overflowing_sub(borrow);
+            neg[i] = d;
+            borrow = (b1 | b2) as u64;
+        }
+        y = neg;
+    }
+    let mut uncompressed = [0u8; 65];
+    uncompressed[0] = 0x04;
+    uncompressed[1..33].copy_from_slice(&compressed[1..]);
+    to_be_bytes(&y, &mut uncompressed[33..]);
+    Some(uncompressed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::decompress_pubkey;
+    use bitcoin::secp256k1::{PublicKey, Secp256k1, SecretKey};
+
+    #[test]
+    fn decompress() {
+        let secp = Secp256k1::new();
+        for i in 1u8..=50 {
+            let secret = SecretKey::from_slice(&[i; 32]).unwrap();
+            let public = PublicKey::from_secret_key(&secp, &secret);
+            assert_eq!(
+                decompress_pubkey(&public.serialize()),
+                Some(public.serialize_uncompressed())
+            );
+        }
+
+        let mut invalid_prefix = [0u8; 33];
+        invalid_prefix[0] = 0x04;
+        assert_eq!(decompress_pubkey(&invalid_prefix), None);
+
+        let mut not_on_curve = [0u8; 33];
+        not_on_curve[0] = 0x02;
+        not_on_curve[32] = 5; // x^3 + 7 = 132 which is not a square mod p
+        assert!(PublicKey::from_slice(&not_on_curve).is_err());
+        assert_eq!(decompress_pubkey(&not_on_curve), None);
+
+        let mut x_too_big = [0xFFu8; 33];
+        x_too_big[0] = 0x02;
+        assert_eq!(decompress_pubkey(&x_too_big), None);
+    }
+}
@@ -0,0 +1,697 @@
+//! Compute the signature hashes of the inputs of a [`Transaction`], the messages signed by its
+//! signatures, directly from the parsed slices.
+//!
+//! Legacy, segwit version 0 (BIP143) and taproot (BIP341) signature hashes are supported through
+//! [`SighashCache`], which computes the hashes of the prevouts, sequences and outputs shared by
+//! every input only once. The outputs spent by the transaction, needed by segwit signature
+//! hashes, are provided by the caller through the [`SpentOutputs`] trait.
+//!
+//! ```
+//! # use bitcoin_slices::{bsl::Transaction, sighash::{SighashCache, SIGHASH_ALL}, Parse};
+//! let tx = hex_lit::hex!("010000000100000000000000000000000000000000000000000000000000000000000000000000000000ffffffff0100000000000000000000000000");
+//! let tx = Transaction::parse(&tx[..]).unwrap().parsed_owned();
+//! let spent_outputs: &[(u64, &[u8])] = &[(50_000, &[0x51])];
+//! let cache = SighashCache::new(&tx, spent_outputs);
+//! let sighash = cache.segwit_v0_signature_hash(0, &[0x51], SIGHASH_ALL).unwrap();
+//! # assert_ne!(sighash, [0u8; 32]);
+//! ```
+
+use crate::{
+    bsl::{
+        opcodes::{
+            OP_CHECKSIG, OP_CODESEPARATOR, OP_DUP, OP_EQUALVERIFY, OP_HASH160, OP_PUSHDATA1,
+            OP_PUSHDATA2, OP_PUSHDATA4,
+        },
+        scan_len, Instructions, Transaction, TxIn, TxIns, TxOut, TxOuts,
+    },
+    hashes::{sha256, Sha256Engine},
+    Error,
+};
+
+/// Sign all the inputs and the outputs, only valid for taproot and equivalent to [`SIGHASH_ALL`]
+pub const SIGHASH_DEFAULT: u8 = 0x00;
+
+/// Sign all the inputs and the outputs
+pub const SIGHASH_ALL: u8 = 0x01;
+
+/// Sign all the inputs and none of the outputs
+pub const SIGHASH_NONE: u8 = 0x02;
+
+/// Sign all the inputs and the output with the same index of the signed input
+pub const SIGHASH_SINGLE: u8 = 0x03;
+
+/// Flag to sign only the signed input instead of all of them
+pub const SIGHASH_ANYONECANPAY: u8 = 0x80;
+
+/// Provides the outputs spent by the inputs of a transaction, by input index.
+pub trait SpentOutputs {
+    /// Returns the value and the script pubkey of the output spent by the input `vin`, `None` if
+    /// it's not known.
+    fn spent_output(&self, vin: usize) -> Option<(u64, &[u8])>;
+}
+
+impl<T: SpentOutputs + ?Sized> SpentOutputs for &T {
+    fn spent_output(&self, vin: usize) -> Option<(u64, &[u8])> {
+        (**self).spent_output(vin)
+    }
+}
+
+impl<'a> SpentOutputs for [TxOut<'a>] {
+    fn spent_output(&self, vin: usize) -> Option<(u64, &[u8])> {
+        self.get(vin)
+            .map(|tx_out| (tx_out.value(), tx_out.script_pubkey()))
+    }
+}
+
+impl<'a> SpentOutputs for [(u64, &'a [u8])] {
+    fn spent_output(&self, vin: usize) -> Option<(u64, &[u8])> {
+        self.get(vin).copied()
+    }
+}
+
+/// Returns the script code used in the segwit version 0 signature hash of a pay to witness public
+/// key hash input: `OP_DUP OP_HASH160 <pubkey_hash> OP_EQUALVERIFY OP_CHECKSIG`
+pub fn p2wpkh_script_code(pubkey_hash: &[u8; 20]) -> [u8; 25] {
+    let mut script_code = [0u8; 25];
+    script_code[..3].copy_from_slice(&[OP_DUP, OP_HASH160, 20]);
+    script_code[3..23].copy_from_slice(pubkey_hash);
+    script_code[23..].copy_from_slice(&[OP_EQUALVERIFY, OP_CHECKSIG]);
+    script_code
+}
+
+/// Computes the signature hashes of the inputs of a transaction, caching the intermediate hashes
+/// shared by all the inputs.
+#[derive(Debug, Clone)]
+pub struct SighashCache<'a, S> {
+    tx: Transaction<'a>,
+    inputs: TxIns<'a>,
+    outputs: TxOuts<'a>,
+    spent_outputs: S,
+
+    /// Single sha256 of the prevouts, the sequences and the outputs of the transaction.
+    /// Segwit version 0 uses the double sha256 of the same data.
+    sha_prevouts: [u8; 32],
+    sha_sequences: [u8; 32],
+    sha_outputs: [u8; 32],
+
+    /// Single sha256 of the spent amounts and of the spent script pubkeys, only needed by taproot
+    /// and computed on first use.
+    sha_spent: Option<([u8; 32], [u8; 32])>,
+}
+
+impl<'a, S: SpentOutputs> SighashCache<'a, S> {
+    /// Creates the cache for the transaction `tx` spending `spent_outputs`
+    pub fn new(tx: &Transaction<'a>, spent_outputs: S) -> Self {
+        let inputs = tx.inputs();
+        let outputs = tx.outputs();
+
+        let mut prevouts = Sha256Engine::new();
+        let mut sequences = Sha256Engine::new();
+        for tx_in in inputs.iter() {
+            prevouts.input(tx_in.prevout().as_ref());
+            sequences.input(&tx_in.sequence().to_le_bytes());
+        }
+        let mut len = 0;
+        scan_len(outputs.as_ref(), &mut len).expect("granted by parsing");
+
+        SighashCache {
+            tx: tx.clone(),
+            sha_prevouts: prevouts.finalize(),
+            sha_sequences: sequences.finalize(),
+            sha_outputs: sha256(&[&outputs.as_ref()[len..]]),
+            inputs,
+            outputs,
+            spent_outputs,
+            sha_spent: None,
+        }
+    }
+
+    /// Computes the signature hash of the input `vin` of a legacy, non-segwit, transaction.
+    ///
+    /// `script_code` is the script being executed from the last executed `OP_CODESEPARATOR`, whose
+    /// remaining `OP_CODESEPARATOR`s are removed. If the `signature` being checked is given, its
+    /// pushes are removed from the script code as by `FindAndDelete` in Bitcoin Core.
+    ///
+    /// As in Bitcoin Core a [`SIGHASH_SINGLE`] without an output with the same index of the
+    /// input returns the number one.
+    pub fn legacy_signature_hash(
+        &self,
+        vin: usize,
+        script_code: &[u8],
+        signature: Option<&[u8]>,
+        sighash_type: u8,
+    ) -> Result<[u8; 32], Error> {
+        if vin >= self.inputs.n() {
+            return Err(Error::InputIndexOutOfRange);
+        }
+        let base_type = sighash_type & 0x1f;
+        let anyone_can_pay = sighash_type & SIGHASH_ANYONECANPAY != 0;
+        if base_type == SIGHASH_SINGLE && vin >= self.outputs.n() {
+            let mut one = [0u8; 32];
+            one[0] = 1;
+            return Ok(one);
+        }
+
+        let mut push_prefix = [0u8; 5];
+        let pattern = signature.map(|signature| {
+            let prefix = push_prefix_of(signature.len(), &mut push_prefix);
+            (prefix, signature)
+        });
+        let mut script_code_len = 0;
+        legacy_script_code(script_code, pattern, |part| script_code_len += part.len());
+
+        let mut engine = Sha256Engine::new();
+        engine.input(&self.tx.version().to_le_bytes());
+        if anyone_can_pay {
+            engine.input_len(1);
+        } else {
+            engine.input_len(self.inputs.n());
+        }
+        for (i, tx_in) in self.inputs.iter().enumerate() {
+            if anyone_can_pay && i != vin {
+                continue;
+            }
+            engine.input(tx_in.prevout().as_ref());
+            if i == vin {
+                engine.input_len(script_code_len);
+                legacy_script_code(script_code, pattern, |part| engine.input(part));
+            } else {
+                engine.input_len(0);
+            }
+            let sequence = if i != vin && (base_type == SIGHASH_NONE || base_type == SIGHASH_SINGLE)
+            {
+                0
+            } else {
+                tx_in.sequence()
+            };
+            engine.input(&sequence.to_le_bytes());
+        }
+        match base_type {
+            SIGHASH_NONE => engine.input_len(0),
+            SIGHASH_SINGLE => {
+                engine.input_len(vin + 1);
+                for (i, tx_out) in self.outputs.iter().enumerate().take(vin + 1) {
+                    if i == vin {
+                        engine.input(tx_out.as_ref());
+                    } else {
+                        // a null output: value -1 and empty script
+                        engine.input(&u64::MAX.to_le_bytes());
+                        engine.input_len(0);
+                    }
+                }
+            }
+            _ => engine.input(self.outputs.as_ref()),
+        }
+        engine.input(&self.tx.locktime().to_le_bytes());
+        engine.input(&(sighash_type as u32).to_le_bytes());
+        Ok(sha256(&[&engine.finalize()]))
+    }
+
+    /// Computes the signature hash of the input `vin` spending a segwit version 0 output as
+    /// defined in BIP143.
+    ///
+    /// `script_code` is the witness script from the last executed `OP_CODESEPARATOR` for pay to
+    /// witness script hash inputs, or the one returned by [`p2wpkh_script_code()`] for pay to
+    /// witness public key hash inputs. The value of the spent output is required.
+    pub fn segwit_v0_signature_hash(
+        &self,
+        vin: usize,
+        script_code: &[u8],
+        sighash_type: u8,
+    ) -> Result<[u8; 32], Error> {
+        let tx_in = self.input(vin)?;
+        let (value, _) = self
+            .spent_outputs
+            .spent_output(vin)
+            .ok_or(Error::MissingSpentOutput)?;
+        let base_type = sighash_type & 0x1f;
+        let anyone_can_pay = sighash_type & SIGHASH_ANYONECANPAY != 0;
+        let zero = [0u8; 32];
+
+        let mut engine = Sha256Engine::new();
+        engine.input(&self.tx.version().to_le_bytes());
+        if anyone_can_pay {
+            engine.input(&zero);
+        } else {
+            engine.input(&sha256(&[&self.sha_prevouts]));
+        }
+        if anyone_can_pay || base_type == SIGHASH_SINGLE || base_type == SIGHASH_NONE {
+            engine.input(&zero);
+        } else {
+            engine.input(&sha256(&[&self.sha_sequences]));
+        }
+        engine.input(tx_in.prevout().as_ref());
+        engine.input_len(script_code.len());
+        engine.input(script_code);
+        engine.input(&value.to_le_bytes());
+        engine.input(&tx_in.sequence().to_le_bytes());
+        if base_type != SIGHASH_SINGLE && base_type != SIGHASH_NONE {
+            engine.input(&sha256(&[&self.sha_outputs]));
+        } else if let (SIGHASH_SINGLE, Some(tx_out)) = (base_type, self.outputs.iter().nth(vin)) {
+            engine.input(&sha256(&[&sha256(&[tx_out.as_ref()])]));
+        } else {
+            engine.input(&zero);
+        }
+        engine.input(&self.tx.locktime().to_le_bytes());
+        engine.input(&(sighash_type as u32).to_le_bytes());
+        Ok(sha256(&[&engine.finalize()]))
+    }
+
+    /// Computes the signature hash of the input `vin` spending a taproot output through the key
+    /// path, as defined in BIP341.
+    ///
+    /// The `annex` is the last witness element if it starts with `0x50` and there are at least two
+    /// witness elements. All the spent outputs are required unless signing with
+    /// [`SIGHASH_ANYONECANPAY`].
+    pub fn taproot_key_spend_signature_hash(
+        &mut self,
+        vin: usize,
+        annex: Option<&[u8]>,
+        sighash_type: u8,
+    ) -> Result<[u8; 32], Error> {
+        self.taproot_signature_hash(vin, annex, None, sighash_type)
+    }
+
+    /// Computes the signature hash of the input `vin` spending a taproot output through the
+    /// script path, as defined in BIP341 and BIP342.
+    ///
+    /// `leaf_hash` is the tapleaf hash of the executed script and `code_separator_position` the
+    /// opcode position of the last executed `OP_CODESEPARATOR`, or `u32::MAX` if none.
+    /// See [`SighashCache::taproot_key_spend_signature_hash()`] for the other parameters.
+    pub fn taproot_script_spend_signature_hash(
+        &mut self,
+        vin: usize,
+        annex: Option<&[u8]>,
+        leaf_hash: &[u8; 32],
+        code_separator_position: u32,
+        sighash_type: u8,
+    ) -> Result<[u8; 32], Error> {
+        let script_path = Some((leaf_hash, code_separator_position));
+        self.taproot_signature_hash(vin, annex, script_path, sighash_type)
+    }
+
+    fn taproot_signature_hash(
+        &mut self,
+        vin: usize,
+        annex: Option<&[u8]>,
+        script_path: Option<(&[u8; 32], u32)>,
+        sighash_type: u8,
+    ) -> Result<[u8; 32], Error> {
+        if !matches!(sighash_type, 0x00..=0x03 | 0x81..=0x83) {
+            return Err(Error::InvalidSighashType);
+        }
+        let tx_in = self.input(vin)?;
+        let output_type = if sighash_type == SIGHASH_DEFAULT {
+            SIGHASH_ALL
+        } else {
+            sighash_type & 0x03
+        };
+        let anyone_can_pay = sighash_type & SIGHASH_ANYONECANPAY != 0;
+
+        let mut engine = Sha256Engine::tagged(b"TapSighash");
+        engine.input(&[0x00, sighash_type]); // epoch and hash type
+        engine.input(&self.tx.version().to_le_bytes());
+        engine.input(&self.tx.locktime().to_le_bytes());
+        if !anyone_can_pay {
+            let (sha_amounts, sha_script_pubkeys) = self.sha_spent()?;
+            engine.input(&self.sha_prevouts);
+            engine.input(&sha_amounts);
+            engine.input(&sha_script_pubkeys);
+            engine.input(&self.sha_sequences);
+        }
+        if output_type == SIGHASH_ALL {
+            engine.input(&self.sha_outputs);
+        }
+        let spend_type = (script_path.is_some() as u8) << 1 | annex.is_some() as u8;
+        engine.input(&[spend_type]);
+        if anyone_can_pay {
+            let (value, script_pubkey) = self
+                .spent_outputs
+                .spent_output(vin)
+                .ok_or(Error::MissingSpentOutput)?;
+            engine.input(tx_in.prevout().as_ref());
+            engine.input(&value.to_le_bytes());
+            engine.input_len(script_pubkey.len());
+            engine.input(script_pubkey);
+            engine.input(&tx_in.sequence().to_le_bytes());
+        } else {
+            engine.input(&(vin as u32).to_le_bytes());
+        }
+        if let Some(annex) = annex {
+            let mut annex_engine = Sha256Engine::new();
+            annex_engine.input_len(annex.len());
+            annex_engine.input(annex);
+            engine.input(&annex_engine.finalize());
+        }
+        if output_type == SIGHASH_SINGLE {
+            let tx_out = self
+                .outputs
+                .iter()
+                .nth(vin)
+                .ok_or(Error::SighashSingleWithoutOutput)?;
+            engine.input(&sha256(&[tx_out.as_ref()]));
+        }
+        if let Some((leaf_hash, code_separator_position)) = script_path {
+            engine.input(leaf_hash);
+            engine.input(&[0x00]); // key version
+            engine.input(&code_separator_position.to_le_bytes());
+        }
+        Ok(engine.finalize())
+    }
+
+    fn input(&self, vin: usize) -> Result<TxIn<'a>, Error> {
+        self.inputs
+            .iter()
+            .nth(vin)
+            .ok_or(Error::InputIndexOutOfRange)
+    }
+
+    fn sha_spent(&mut self) -> Result<([u8; 32], [u8; 32]), Error> {
+        if let Some(sha_spent) = self.sha_spent {
+            return Ok(sha_spent);
+        }
+        let mut amounts = Sha256Engine::new();
+        let mut script_pubkeys = Sha256Engine::new();
+        for vin in 0..self.inputs.n() {
+            let (value, script_pubkey) = self
+                .spent_outputs
+                .spent_output(vin)
+                .ok_or(Error::MissingSpentOutput)?;
+            amounts.input(&value.to_le_bytes());
+            script_pubkeys.input_len(script_pubkey.len());
+            script_pubkeys.input(script_pubkey);
+        }
+        let sha_spent = (amounts.finalize(), script_pubkeys.finalize());
+        self.sha_spent = Some(sha_spent);
+        Ok(sha_spent)
+    }
+}
+
+/// Writes in `buffer` the opcodes preceding a push of `len` bytes, as serialized by Bitcoin Core
+/// `CScript::operator<<`, and returns them.
+fn push_prefix_of(len: usize, buffer: &mut [u8; 5]) -> &[u8] {
+    match len {
+        0..=0x4b => {
+            buffer[0] = len as u8;
+            &buffer[..1]
+        }
+        0x4c..=0xff => {
+            buffer[..2].copy_from_slice(&[OP_PUSHDATA1, len as u8]);
+            &buffer[..2]
+        }
+        0x100..=0xffff => {
+            buffer[0] = OP_PUSHDATA2;
+            buffer[1..3].copy_from_slice(&(len as u16).to_le_bytes());
+            &buffer[..3]
+        }
+        _ => {
+            buffer[0] = OP_PUSHDATA4;
+            buffer[1..5].copy_from_slice(&(len as u32).to_le_bytes());
+            &buffer[..5]
+        }
+    }
+}
+
+/// Calls `f` with the consecutive parts of `script_code` remaining after removing the
+/// `OP_CODESEPARATOR`s and the occurrences of the push `pattern` found at instruction boundaries.
+///
+/// A push of the pattern is a whole instruction, so the remaining instructions are the same found
+/// parsing the result of `FindAndDelete` in Bitcoin Core. If the script ends with a truncated
+/// push, the remaining bytes are kept as they are.
+fn legacy_script_code(
+    script_code: &[u8],
+    pattern: Option<(&[u8], &[u8])>,
+    mut f: impl FnMut(&[u8]),
+) {
+    let mut offset = 0;
+    while offset < script_code.len() {
+        if let Some((prefix, data)) = pattern {
+            let remaining = &script_code[offset..];
+            if remaining.starts_with(prefix) && remaining[prefix.len()..].starts_with(data) {
+                offset += prefix.len() + data.len();
+                continue;
+            }
+        }
+        let mut instructions = Instructions::new(&script_code[offset..]);
+        match instructions.next() {
+            Some(Ok(instruction)) => {
+                let len = instructions.offset();
+                if instruction.opcode() != OP_CODESEPARATOR {
+                    f(&script_code[offset..offset + len]);
+                }
+                offset += len;
+            }
+            _ => {
+                f(&script_code[offset..]);
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "bitcoin"))]
+mod test {
+    use bitcoin::{
+        consensus::deserialize,
+        hashes::Hash,
+        sighash::{self, Annex, Prevouts, TapSighashType},
+        taproot::TapLeafHash,
+        Amount, EcdsaSighashType, ScriptBuf,
+    };
+
+    use super::*;
+    use crate::Parse;
+
+    const TYPES: [u8; 6] = [0x01, 0x02, 0x03, 0x81, 0x82, 0x83];
+
+    /// A transaction of block 702861 with more inputs than outputs and its spent outputs
+    fn test_tx() -> (
+        Transaction<'static>,
+        bitcoin::Transaction,
+        Vec<bitcoin::TxOut>,
+    ) {
+        let block = bitcoin_test_data::blocks::mainnet_702861();
+        let bitcoin_block: bitcoin::Block = deserialize(block).unwrap();
+        let index = bitcoin_block
+            .txdata
+            .iter()
+            .position(|tx| tx.output.len() >= 2 && tx.input.len() > tx.output.len())
+            .unwrap();
+        let mut consumed = 80;
+        scan_len(&block[80..], &mut consumed).unwrap();
+        let mut remaining = &block[consumed..];
+        for _ in 0..index {
+            remaining = Transaction::parse(remaining).unwrap().remaining();
+        }
+        let tx = Transaction::parse(remaining).unwrap().parsed_owned();
+        let bitcoin_tx = bitcoin_block.txdata[index].clone();
+        let spent = (0..bitcoin_tx.input.len())
+            .map(|i| bitcoin::TxOut {
+                value: Amount::from_sat(10_000 * (i as u64 + 1)),
+                script_pubkey: ScriptBuf::from_bytes(vec![0x51; i + 1]),
+            })
+            .collect();
+        (tx, bitcoin_tx, spent)
+    }
+
+    fn as_spent(spent: &[bitcoin::TxOut]) -> Vec<(u64, &[u8])> {
+        spent
+            .iter()
+            .map(|o| (o.value.to_sat(), o.script_pubkey.as_bytes()))
+            .collect()
+    }
+
+    #[test]
+    fn legacy() {
+        let (tx, bitcoin_tx, spent) = test_tx();
+        let spent_slices = as_spent(&spent);
+        let cache = SighashCache::new(&tx, &spent_slices[..]);
+        let bitcoin_cache = sighash::SighashCache::new(&bitcoin_tx);
+        let script = hex_lit::hex!("76a914751e76e8199196d454941c45d1b3a323f1433bd688ac");
+        let script_buf = ScriptBuf::from_bytes(script.to_vec());
+        for vin in 0..bitcoin_tx.input.len() {
+            for sighash_type in TYPES.iter().copied().chain([0x00, 0x41, 0xff]) {
+                let sighash = cache
+                    .legacy_signature_hash(vin, &script, None, sighash_type)
+                    .unwrap();
+                if sighash_type & 0x1f == 3 && vin >= bitcoin_tx.output.len() {
+                    // rust-bitcoin doesn't return one for SIGHASH_SINGLE|SIGHASH_ANYONECANPAY
+                    let mut one = [0u8; 32];
+                    one[0] = 1;
+                    assert_eq!(sighash, one);
+                    continue;
+                }
+                let expected = bitcoin_cache
+                    .legacy_signature_hash(vin, &script_buf, sighash_type as u32)
+                    .unwrap();
+                assert_eq!(sighash, expected.to_byte_array(), "{vin} {sighash_type}");
+            }
+        }
+        let n = bitcoin_tx.input.len();
+        assert_eq!(
+            cache.legacy_signature_hash(n, &script, None, 1),
+            Err(Error::InputIndexOutOfRange)
+        );
+    }
+
+    #[test]
+    fn legacy_script_code_cleanup() {
+        let (tx, _, spent) = test_tx();
+        let spent_slices = as_spent(&spent);
+        let cache = SighashCache::new(&tx, &spent_slices[..]);
+        let signature = [0x30u8; 71];
+        let mut push = vec![71u8];
+        push.extend(signature);
+
+        // OP_CODESEPARATOR are removed
+        let expected = cache
+            .legacy_signature_hash(0, &[0x51, 0xac], None, 1)
+            .unwrap();
+        let with_separators = [0xab, 0x51, 0xab, 0xab, 0xac, 0xab];
+        let sighash = cache
+            .legacy_signature_hash(0, &with_separators, None, 1)
+            .unwrap();
+        assert_eq!(sighash, expected);
+
+        // pushes of the signature are removed, only at instruction boundaries
+        let mut script = push.clone();
+        script.push(0x51);
+        script.extend(&push);
+        script.extend(&push);
+        script.push(0xac);
+        let sighash = cache
+            .legacy_signature_hash(0, &script, Some(&signature), 1)
+            .unwrap();
+        assert_eq!(sighash, expected);
+        let not_removed = cache.legacy_signature_hash(0, &script, None, 1).unwrap();
+        assert_ne!(not_removed, expected);
+
+        let mut script = vec![0x4c, 72, 71];
+        script.extend(signature);
+        let sighash = cache
+            .legacy_signature_hash(0, &script, Some(&signature), 1)
+            .unwrap();
+        let not_removed = cache.legacy_signature_hash(0, &script, None, 1).unwrap();
+        assert_eq!(sighash, not_removed);
+
+        // a truncated push is kept
+        let truncated = [0x51, 0x4c, 0x05, 0x00];
+        let bitcoin_cache =
+            sighash::SighashCache::new(deserialize::<bitcoin::Transaction>(tx.as_ref()).unwrap());
+        let expected = bitcoin_cache
+            .legacy_signature_hash(0, &ScriptBuf::from_bytes(truncated.to_vec()), 1)
+            .unwrap();
+        let sighash = cache.legacy_signature_hash(0, &truncated, None, 1).unwrap();
+        assert_eq!(sighash, expected.to_byte_array());
+    }
+
+    #[test]
+    fn segwit_v0() {
+        let (tx, bitcoin_tx, spent) = test_tx();
+        let spent_slices = as_spent(&spent);
+        let cache = SighashCache::new(&tx, &spent_slices[..]);
+        let mut bitcoin_cache = sighash::SighashCache::new(&bitcoin_tx);
+        let pubkey_hash = [7u8; 20];
+        let script_code = p2wpkh_script_code(&pubkey_hash);
+        let script_buf = ScriptBuf::from_bytes(script_code.to_vec());
+        let p2wpkh = ScriptBuf::new_p2wpkh(&bitcoin::WPubkeyHash::from_byte_array(pubkey_hash));
+        for vin in 0..bitcoin_tx.input.len() {
+            for sighash_type in TYPES {
+                let bitcoin_type = EcdsaSighashType::from_consensus(sighash_type as u32);
+                let expected = bitcoin_cache
+                    .p2wsh_signature_hash(vin, &script_buf, spent[vin].value, bitcoin_type)
+                    .unwrap();
+                let sighash = cache
+                    .segwit_v0_signature_hash(vin, &script_code, sighash_type)
+                    .unwrap();
+                assert_eq!(sighash, expected.to_byte_array(), "{vin} {sighash_type}");
+
+                let expected = bitcoin_cache
+                    .p2wpkh_signature_hash(vin, &p2wpkh, spent[vin].value, bitcoin_type)
+                    .unwrap();
+                assert_eq!(sighash, expected.to_byte_array());
+            }
+        }
+
+        let partial = SighashCache::new(&tx, &spent_slices[..1]);
+        assert!(partial.segwit_v0_signature_hash(0, &script_code, 1).is_ok());
+        assert_eq!(
+            partial.segwit_v0_signature_hash(1, &script_code, 1),
+            Err(Error::MissingSpentOutput)
+        );
+    }
+
+    #[test]
+    fn taproot() {
+        let (tx, bitcoin_tx, spent) = test_tx();
+        let spent_slices = as_spent(&spent);
+        let mut cache = SighashCache::new(&tx, &spent_slices[..]);
+        let mut bitcoin_cache = sighash::SighashCache::new(&bitcoin_tx);
+        let prevouts = Prevouts::All(&spent);
+        let annex_bytes = [0x50u8, 1, 2, 3];
+        let leaf_hash = [9u8; 32];
+        let n_outputs = bitcoin_tx.output.len();
+        for vin in 0..bitcoin_tx.input.len() {
+            for sighash_type in [0x00].iter().chain(&TYPES) {
+                let bitcoin_type = TapSighashType::from_consensus_u8(*sighash_type).unwrap();
+                for annex in [None, Some(&annex_bytes[..])] {
+                    let bitcoin_annex = annex.map(|a| Annex::new(a).unwrap());
+                    let key_spend =
+                        cache.taproot_key_spend_signature_hash(vin, annex, *sighash_type);
+                    let script_spend = cache.taproot_script_spend_signature_hash(
+                        vin,
+                        annex,
+                        &leaf_hash,
+                        5,
+                        *sighash_type,
+                    );
+                    if *sighash_type & 0x03 == SIGHASH_SINGLE && vin >= n_outputs {
+                        assert_eq!(key_spend, Err(Error::SighashSingleWithoutOutput));
+                        assert_eq!(script_spend, Err(Error::SighashSingleWithoutOutput));
+                        continue;
+                    }
+                    let expected = bitcoin_cache
+                        .taproot_signature_hash(
+                            vin,
+                            &prevouts,
+                            bitcoin_annex.clone(),
+                            None,
+                            bitcoin_type,
+                        )
+                        .unwrap();
+                    assert_eq!(key_spend.unwrap(), expected.to_byte_array());
+
+                    let leaf = Some((TapLeafHash::from_byte_array(leaf_hash), 5));
+                    let expected = bitcoin_cache
+                        .taproot_signature_hash(vin, &prevouts, bitcoin_annex, leaf, bitcoin_type)
+                        .unwrap();
+                    assert_eq!(script_spend.unwrap(), expected.to_byte_array());
+                }
+            }
+        }
+
+        // with anyonecanpay only the spent output of the signed input is needed
+        let mut partial = SighashCache::new(&tx, &spent_slices[..1]);
+        assert_eq!(
+            partial.taproot_key_spend_signature_hash(0, None, SIGHASH_ALL),
+            Err(Error::MissingSpentOutput)
+        );
+        let one = Prevouts::One(0, &spent[0]);
+        let expected = bitcoin_cache
+            .taproot_key_spend_signature_hash(0, &one, TapSighashType::AllPlusAnyoneCanPay)
+            .unwrap();
+        let sighash = partial
+            .taproot_key_spend_signature_hash(0, None, 0x81)
+            .unwrap();
+        assert_eq!(sighash, expected.to_byte_array());
+
+        assert_eq!(
+            cache.taproot_key_spend_signature_hash(0, None, 0x04),
+            Err(Error::InvalidSighashType)
+        );
+        assert_eq!(
+            cache.taproot_key_spend_signature_hash(0, None, 0x80),
+            Err(Error::InvalidSighashType)
+        );
+    }
+}
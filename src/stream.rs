@@ -0,0 +1,659 @@
+//! Parse blocks and transactions from a [`std::io::Read`], keeping in memory one transaction at a
+//! time instead of the whole block.
+//!
+//! With the `async` feature [`AsyncStreamReader`] does the same from a tokio `AsyncRead`.
+//!
+//! ```
+//! # use bitcoin_slices::{bsl, stream::StreamReader, Visitor};
+//! # use core::ops::ControlFlow;
+//! struct Sum(u64);
+//! impl Visitor for Sum {
+//!     fn visit_tx_out(&mut self, _vout: usize, tx_out: &bsl::TxOut) -> ControlFlow<()> {
+//!         self.0 += tx_out.value();
+//!         ControlFlow::Continue(())
+//!     }
+//! }
+//! let file = bitcoin_test_data::blocks::mainnet_702861(); // could be a `std::fs::File`
+//! let mut reader = StreamReader::new(file);
+//! let mut sum = Sum(0);
+//! reader.visit_block(&mut sum).unwrap();
+//! assert_eq!(sum.0, 2_883_682_728_990)
+//! ```
+
+use std::io;
+
+use crate::{
+    bsl::{
+        compact_size_len_of_prefix, scan_len, Block, BlockHeader, BlockParser, Transaction,
+        TransactionParser,
+    },
+    EmptyVisitor, Error, Parse, Visit, Visitor,
+};
+
+/// The default maximum size of the buffer, enough for any valid block
+pub const DEFAULT_MAX_BUFFER: usize = 4_000_000;
+
+/// The initial size of the buffer, grown when a transaction doesn't fit
+const INITIAL_BUFFER: usize = 64 * 1024;
+
+/// Error while reading from a stream
+#[derive(Debug)]
+pub enum StreamError {
+    /// Reading from the stream failed, [`io::ErrorKind::UnexpectedEof`] if the stream ended in the
+    /// middle of an object
+    Io(io::Error),
+
+    /// The data read is not valid, [`Error::BufferTooSmall`] if an object is bigger than the
+    /// maximum buffer size
+    Parse(Error),
+}
+
+impl From<io::Error> for StreamError {
+    fn from(value: io::Error) -> Self {
+        StreamError::Io(value)
+    }
+}
+
+impl From<Error> for StreamError {
+    fn from(value: Error) -> Self {
+        StreamError::Parse(value)
+    }
+}
+
+impl core::fmt::Display for StreamError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            StreamError::Io(e) => write!(f, "io error: {}", e),
+            StreamError::Parse(e) => write!(f, "parse error: {:?}", e),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StreamError::Io(e) => Some(e),
+            StreamError::Parse(_) => None,
+        }
+    }
+}
+
+/// The resumable parser of the object being read from a stream, keeping the progress between
+/// the reads
+#[derive(Debug)]
+pub(crate) enum ObjectParser {
+    Block(BlockParser),
+    Transaction(TransactionParser),
+}
+
+impl ObjectParser {
+    pub(crate) fn block() -> Self {
+        ObjectParser::Block(BlockParser::new())
+    }
+
+    pub(crate) fn transaction() -> Self {
+        ObjectParser::Transaction(TransactionParser::new())
+    }
+}
+
+/// A reusable buffer holding the bytes read from a stream and not yet consumed.
+#[derive(Debug)]
+pub(crate) struct Buffer {
+    data: Vec<u8>,
+    start: usize,
+    end: usize,
+    max_size: usize,
+
+    /// Bytes of the last object returned, consumed at the next call
+    pending: usize,
+}
+
+impl Buffer {
+    pub(crate) fn new(max_size: usize) -> Self {
+        Buffer {
+            data: Vec::new(),
+            start: 0,
+            end: 0,
+            max_size,
+            pending: 0,
+        }
+    }
+
+    /// The bytes read and not yet consumed
+    pub(crate) fn filled(&self) -> &[u8] {
+        &self.data[self.start..self.end]
+    }
+
+    pub(crate) fn consume(&mut self, len: usize) {
+        self.start += len;
+        if self.start == self.end {
+            self.start = 0;
+            self.end = 0;
+        }
+    }
+
+    /// Consumes the bytes of the object returned by the previous call
+    pub(crate) fn consume_pending(&mut self) {
+        let pending = core::mem::take(&mut self.pending);
+        self.consume(pending);
+    }
+
+    /// Marks the first `len` bytes as consumed at the next call to
+    /// [`Buffer::consume_pending()`]
+    pub(crate) fn set_pending(&mut self, len: usize) {
+        self.pending = len;
+    }
+
+    /// Returns the space where to read more bytes, so that [`Buffer::filled()`] can reach
+    /// `needed` bytes.
+    pub(crate) fn spare(&mut self, needed: usize) -> Result<&mut [u8], Error> {
+        if needed > self.max_size {
+            return Err(Error::BufferTooSmall);
+        }
+        if self.start + needed > self.data.len() {
+            self.data.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+            if needed > self.data.len() {
+                let len = needed.max(INITIAL_BUFFER).max(self.data.len() * 2);
+                self.data.resize(len.min(self.max_size), 0);
+            }
+        }
+        Ok(&mut self.data[self.end..])
+    }
+
+    /// Marks `len` bytes written in [`Buffer::spare()`] as read
+    pub(crate) fn advance(&mut self, len: usize) {
+        self.end += len;
+    }
+
+    /// Returns the length of the object at the start of the buffer, or the length of the buffer
+    /// needed at minimum to know it.
+    ///
+    /// `parser` must be the same between calls for the same object, so that the parts already
+    /// complete are not parsed again.
+    pub(crate) fn object_len(
+        &self,
+        parser: &mut ObjectParser,
+    ) -> Result<Result<usize, usize>, Error> {
+        let filled = self.filled();
+        let (result, needed) = match parser {
+            ObjectParser::Block(parser) => (
+                parser
+                    .resume(filled, &mut EmptyVisitor {})
+                    .map(|block| block.consumed()),
+                parser.needed(),
+            ),
+            ObjectParser::Transaction(parser) => (
+                parser
+                    .resume(filled, &mut EmptyVisitor {})
+                    .map(|tx| tx.consumed()),
+                parser.needed(),
+            ),
+        };
+        match result {
+            Ok(len) => Ok(Ok(len)),
+            Err(Error::MoreBytesNeeded) => Ok(Err(needed)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the length of the block header and of the number of transactions at the start of
+    /// the buffer, or the length of the buffer needed at minimum to know it
+    pub(crate) fn block_begin_len(&self) -> Result<usize, usize> {
+        let filled = self.filled();
+        let len = 80 + filled.get(80).map(compact_size_len_of_prefix).unwrap_or(1);
+        if filled.len() < len {
+            Err(len)
+        } else {
+            Ok(len)
+        }
+    }
+
+    /// Visits the block header and the number of transactions at the start of the buffer, of
+    /// length returned by [`Buffer::block_begin_len()`], returns the number of transactions
+    pub(crate) fn visit_block_begin<V: Visitor>(
+        &mut self,
+        len: usize,
+        visit: &mut V,
+    ) -> Result<usize, Error> {
+        let header = BlockHeader::visit(self.filled(), visit)?;
+        let total_txs = scan_len(header.remaining(), &mut 0)? as usize;
+        visit.visit_block_begin(total_txs);
+        self.consume(len);
+        Ok(total_txs)
+    }
+
+    /// Visits the transaction at the start of the buffer, of length returned by
+    /// [`Buffer::object_len()`]
+    pub(crate) fn visit_transaction<V: Visitor>(
+        &mut self,
+        len: usize,
+        visit: &mut V,
+    ) -> Result<(), Error> {
+        Transaction::visit(&self.filled()[..len], visit)?;
+        self.consume(len);
+        Ok(())
+    }
+
+    /// Returns the object at the start of the buffer, of length returned by
+    /// [`Buffer::object_len()`], consumed at the next call
+    pub(crate) fn object<'a, T: Visit<'a>>(&'a mut self, len: usize) -> T {
+        self.set_pending(len);
+        T::parse(&self.filled()[..len])
+            .expect("granted by object_len")
+            .parsed_owned()
+    }
+}
+
+/// Parses blocks and transactions read from `R`, calling the [`Visitor`] methods transaction by
+/// transaction.
+///
+/// Read bytes are kept in a buffer reused between objects: it grows to the size of the biggest
+/// transaction read, or the biggest object for [`StreamReader::read_transaction()`], up to a
+/// maximum. Bytes read after an object are kept for the next one, so a stream of consecutive
+/// objects can be parsed with multiple calls.
+///
+/// Every transaction is parsed twice: the first time to find where it ends, the second to visit
+/// it once complete.
+#[derive(Debug)]
+pub struct StreamReader<R> {
+    reader: R,
+    buffer: Buffer,
+}
+
+impl<R: io::Read> StreamReader<R> {
+    /// Creates the reader with a maximum buffer of [`DEFAULT_MAX_BUFFER`] bytes
+    pub fn new(reader: R) -> Self {
+        Self::with_max_buffer(reader, DEFAULT_MAX_BUFFER)
+    }
+
+    /// Creates the reader with a maximum buffer of `max_buffer` bytes, objects bigger than that
+    /// fail with [`Error::BufferTooSmall`]
+    pub fn with_max_buffer(reader: R, max_buffer: usize) -> Self {
+        StreamReader {
+            reader,
+            buffer: Buffer::new(max_buffer),
+        }
+    }
+
+    /// Reads a block, calling the methods on `visit` for the header and for every transaction
+    /// once it's complete.
+    ///
+    /// Returns the number of transactions in the block.
+    pub fn visit_block<V: Visitor>(&mut self, visit: &mut V) -> Result<usize, StreamError> {
+        self.buffer.consume_pending();
+        let len = loop {
+            match self.buffer.block_begin_len() {
+                Ok(len) => break len,
+                Err(needed) => self.fill(needed)?,
+            }
+        };
+        let total_txs = self.buffer.visit_block_begin(len, visit)?;
+        for _ in 0..total_txs {
+            let len = self.next_len(ObjectParser::transaction())?;
+            self.buffer.visit_transaction(len, visit)?;
+        }
+        Ok(total_txs)
+    }
+
+    /// Reads a transaction, calling the methods on `visit` once it's complete
+    pub fn visit_transaction<V: Visitor>(&mut self, visit: &mut V) -> Result<(), StreamError> {
+        self.buffer.consume_pending();
+        let len = self.next_len(ObjectParser::transaction())?;
+        self.buffer.visit_transaction(len, visit)?;
+        Ok(())
+    }
+
+    /// Reads a transaction and returns it, borrowing the buffer until the next call.
+    pub fn read_transaction(&mut self) -> Result<Transaction<'_>, StreamError> {
+        self.buffer.consume_pending();
+        let len = self.next_len(ObjectParser::transaction())?;
+        Ok(self.buffer.object(len))
+    }
+
+    /// Reads a whole block and returns it, borrowing the buffer until the next call.
+    ///
+    /// Differently from [`StreamReader::visit_block()`] the buffer must contain the block.
+    pub fn read_block(&mut self) -> Result<Block<'_>, StreamError> {
+        self.buffer.consume_pending();
+        let len = self.next_len(ObjectParser::block())?;
+        Ok(self.buffer.object(len))
+    }
+
+    /// Returns the underlying reader, bytes read and not yet parsed are lost
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn next_len(&mut self, mut parser: ObjectParser) -> Result<usize, StreamError> {
+        loop {
+            match self.buffer.object_len(&mut parser)? {
+                Ok(len) => return Ok(len),
+                Err(needed) => self.fill(needed)?,
+            }
+        }
+    }
+
+    /// Reads until the buffer contains at least `needed` bytes
+    fn fill(&mut self, needed: usize) -> Result<(), StreamError> {
+        while self.buffer.filled().len() < needed {
+            let spare = self.buffer.spare(needed)?;
+            match self.reader.read(spare) {
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+                Ok(read) => self.buffer.advance(read),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses blocks and transactions read from the asynchronous reader `R`, like [`StreamReader`].
+///
+/// Bytes are read only when the buffer doesn't contain the next object, so a slow consumer
+/// applies back-pressure to the stream.
+///
+/// # Cancel safety
+///
+/// Bytes read are kept in the buffer when a future is dropped, so the methods returning or
+/// visiting a single object once complete are cancel safe, the object is parsed again from its
+/// start at the next call. [`AsyncStreamReader::visit_block()`] is not, see its documentation.
+#[cfg(feature = "async")]
+#[derive(Debug)]
+pub struct AsyncStreamReader<R> {
+    reader: R,
+    buffer: Buffer,
+}
+
+#[cfg(feature = "async")]
+impl<R: tokio::io::AsyncRead + Unpin> AsyncStreamReader<R> {
+    /// Creates the reader with a maximum buffer of [`DEFAULT_MAX_BUFFER`] bytes
+    pub fn new(reader: R) -> Self {
+        Self::with_max_buffer(reader, DEFAULT_MAX_BUFFER)
+    }
+
+    /// Creates the reader with a maximum buffer of `max_buffer` bytes, objects bigger than that
+    /// fail with [`Error::BufferTooSmall`]
+    pub fn with_max_buffer(reader: R, max_buffer: usize) -> Self {
+        AsyncStreamReader {
+            reader,
+            buffer: Buffer::new(max_buffer),
+        }
+    }
+
+    /// Reads a block, calling the methods on `visit` for the header and for every transaction
+    /// once it's complete, see [`StreamReader::visit_block()`].
+    ///
+    /// Returns the number of transactions in the block.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is not cancel safe: if the future is dropped, for example in a
+    /// `tokio::select!`, after the header or some transactions have been visited and consumed,
+    /// the next call starts parsing from the middle of the block.
+    pub async fn visit_block<V: Visitor>(&mut self, visit: &mut V) -> Result<usize, StreamError> {
+        self.buffer.consume_pending();
+        let len = loop {
+            match self.buffer.block_begin_len() {
+                Ok(len) => break len,
+                Err(needed) => self.fill(needed).await?,
+            }
+        };
+        let total_txs = self.buffer.visit_block_begin(len, visit)?;
+        for _ in 0..total_txs {
+            let len = self.next_len(ObjectParser::transaction()).await?;
+            self.buffer.visit_transaction(len, visit)?;
+        }
+        Ok(total_txs)
+    }
+
+    /// Reads a transaction, calling the methods on `visit` once it's complete
+    pub async fn visit_transaction<V: Visitor>(
+        &mut self,
+        visit: &mut V,
+    ) -> Result<(), StreamError> {
+        self.buffer.consume_pending();
+        let len = self.next_len(ObjectParser::transaction()).await?;
+        self.buffer.visit_transaction(len, visit)?;
+        Ok(())
+    }
+
+    /// Reads a transaction and returns it, borrowing the buffer until the next call.
+    pub async fn read_transaction(&mut self) -> Result<Transaction<'_>, StreamError> {
+        self.buffer.consume_pending();
+        let len = self.next_len(ObjectParser::transaction()).await?;
+        Ok(self.buffer.object(len))
+    }
+
+    /// Reads a whole block and returns it, borrowing the buffer until the next call.
+    pub async fn read_block(&mut self) -> Result<Block<'_>, StreamError> {
+        self.buffer.consume_pending();
+        let len = self.next_len(ObjectParser::block()).await?;
+        Ok(self.buffer.object(len))
+    }
+
+    /// Returns the underlying reader, bytes read and not yet parsed are lost
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    async fn next_len(&mut self, mut parser: ObjectParser) -> Result<usize, StreamError> {
+        loop {
+            match self.buffer.object_len(&mut parser)? {
+                Ok(len) => return Ok(len),
+                Err(needed) => self.fill(needed).await?,
+            }
+        }
+    }
+
+    /// Reads until the buffer contains at least `needed` bytes
+    async fn fill(&mut self, needed: usize) -> Result<(), StreamError> {
+        use tokio::io::AsyncReadExt;
+        while self.buffer.filled().len() < needed {
+            let spare = self.buffer.spare(needed)?;
+            match self.reader.read(spare).await {
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+                Ok(read) => self.buffer.advance(read),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::ops::ControlFlow;
+    use std::io::{self, Read};
+
+    use bitcoin_test_data::blocks::mainnet_702861;
+
+    use super::*;
+    use crate::{
+        bsl::{Block, TxOut},
+        test_common::{GENESIS_BLOCK, GENESIS_TX},
+    };
+
+    /// Returns at most `chunk` bytes at every read
+    struct Chunked<'a> {
+        data: &'a [u8],
+        chunk: usize,
+    }
+    impl Read for Chunked<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = buf.len().min(self.chunk).min(self.data.len());
+            buf[..len].copy_from_slice(&self.data[..len]);
+            self.data = &self.data[len..];
+            Ok(len)
+        }
+    }
+
+    #[derive(Default, PartialEq, Eq, Debug)]
+    struct Count {
+        headers: usize,
+        txs: usize,
+        value: u64,
+    }
+    impl Visitor for Count {
+        fn visit_block_header(&mut self, _header: &BlockHeader) -> ControlFlow<()> {
+            self.headers += 1;
+            ControlFlow::Continue(())
+        }
+        fn visit_transaction(&mut self, _tx: &Transaction) -> ControlFlow<()> {
+            self.txs += 1;
+            ControlFlow::Continue(())
+        }
+        fn visit_tx_out(&mut self, _vout: usize, tx_out: &TxOut) -> ControlFlow<()> {
+            self.value += tx_out.value();
+            ControlFlow::Continue(())
+        }
+    }
+
+    #[test]
+    fn visit_block() {
+        let block = mainnet_702861();
+        let mut expected = Count::default();
+        Block::visit(block, &mut expected).unwrap();
+
+        for chunk in [1, 1000, 1_000_000] {
+            let mut reader = StreamReader::new(Chunked { data: block, chunk });
+            let mut count = Count::default();
+            assert_eq!(reader.visit_block(&mut count).unwrap(), 2500);
+            assert_eq!(count, expected);
+            assert!(reader.buffer.data.len() < block.len() / 4);
+        }
+
+        let mut reader = StreamReader::new(&block[..block.len() - 1]);
+        match reader.visit_block(&mut Count::default()) {
+            Err(StreamError::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof),
+            e => panic!("{:?}", e),
+        }
+
+        let mut reader = StreamReader::with_max_buffer(block, 1000);
+        match reader.visit_block(&mut Count::default()) {
+            Err(StreamError::Parse(e)) => assert_eq!(e, Error::BufferTooSmall),
+            e => panic!("{:?}", e),
+        }
+    }
+
+    #[test]
+    fn consecutive_objects() {
+        let mut data = GENESIS_BLOCK.to_vec();
+        data.extend(GENESIS_TX);
+        data.extend(GENESIS_TX);
+        data.extend(GENESIS_BLOCK);
+        let mut reader = StreamReader::new(Chunked {
+            data: &data,
+            chunk: 100,
+        });
+        let mut count = Count::default();
+        assert_eq!(reader.visit_block(&mut count).unwrap(), 1);
+        assert_eq!(reader.read_transaction().unwrap().as_ref(), &GENESIS_TX[..]);
+        reader.visit_transaction(&mut count).unwrap();
+        assert_eq!(reader.visit_block(&mut count).unwrap(), 1);
+        assert_eq!((count.headers, count.txs), (2, 3));
+        assert!(matches!(
+            reader.read_transaction(),
+            Err(StreamError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof
+        ));
+    }
+
+    #[test]
+    fn read_block() {
+        let mut data = GENESIS_BLOCK.to_vec();
+        data.extend(GENESIS_BLOCK);
+        let mut reader = StreamReader::new(Chunked {
+            data: &data,
+            chunk: 10,
+        });
+        for _ in 0..2 {
+            let block = reader.read_block().unwrap();
+            assert_eq!(block.as_ref(), &GENESIS_BLOCK[..]);
+            assert_eq!(block.total_transactions(), 1);
+        }
+
+        // complete transactions are not parsed again when more bytes are read
+        let block = mainnet_702861();
+        let mut reader = StreamReader::new(Chunked {
+            data: block,
+            chunk: 1,
+        });
+        assert_eq!(reader.read_block().unwrap().as_ref(), block);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn async_stream() {
+        use tokio::io::AsyncWriteExt;
+
+        let block = mainnet_702861();
+        let mut expected = Count::default();
+        Block::visit(block, &mut expected).unwrap();
+
+        // a small duplex buffer forces the writer to wait for the reader to parse
+        let (mut writer, reader) = tokio::io::duplex(1000);
+        let write = async move {
+            writer.write_all(block).await.unwrap();
+            writer.write_all(&GENESIS_TX).await.unwrap();
+            writer.write_all(&GENESIS_TX).await.unwrap();
+            writer.write_all(block).await.unwrap();
+        };
+        let read = async move {
+            let mut reader = AsyncStreamReader::new(reader);
+            let mut count = Count::default();
+            assert_eq!(reader.visit_block(&mut count).await.unwrap(), 2500);
+            assert_eq!(count, expected);
+            assert!(reader.buffer.data.len() < block.len() / 4);
+
+            let tx = reader.read_transaction().await.unwrap();
+            assert_eq!(tx.as_ref(), &GENESIS_TX[..]);
+            reader.visit_transaction(&mut count).await.unwrap();
+            assert_eq!(count.txs, 2501);
+
+            let parsed = reader.read_block().await.unwrap();
+            assert_eq!(parsed.as_ref(), block);
+            assert!(matches!(
+                reader.read_transaction().await,
+                Err(StreamError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof
+            ));
+        };
+        tokio::join!(write, read);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn async_cancelled_read() {
+        use tokio::io::AsyncWriteExt;
+
+        let (mut writer, reader) = tokio::io::duplex(1000);
+        let mut reader = AsyncStreamReader::new(reader);
+        writer.write_all(&GENESIS_BLOCK[..100]).await.unwrap();
+
+        // the read is dropped waiting for the rest of the block, the bytes read are not lost
+        tokio::select! {
+            biased;
+            _ = reader.read_block() => panic!("block is not complete"),
+            _ = async {} => {}
+        }
+        assert_eq!(reader.buffer.filled(), &GENESIS_BLOCK[..100]);
+
+        writer.write_all(&GENESIS_BLOCK[100..]).await.unwrap();
+        let block = reader.read_block().await.unwrap();
+        assert_eq!(block.as_ref(), &GENESIS_BLOCK[..]);
+    }
+
+    #[test]
+    fn invalid() {
+        let mut tx = GENESIS_TX;
+        tx[4] = 0;
+        tx[5] = 2; // unknown segwit flag
+        let mut reader = StreamReader::new(&tx[..]);
+        match reader.read_transaction() {
+            Err(StreamError::Parse(e)) => assert_eq!(e, Error::UnknownSegwitFlag(2)),
+            e => panic!("{:?}", e),
+        }
+    }
+}
@@ -0,0 +1,284 @@
+use core::cmp::Ordering;
+use core::ops::{Add, Div, Not, Shl, Shr, Sub};
+
+use crate::Error;
+
+/// An unsigned 256 bits integer, as used for proof of work targets and chain work.
+///
+/// Arithmetic operations wrap around on overflow, like the Bitcoin Core `arith_uint256`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct U256([u64; 4]); // least significant limb first
+
+impl U256 {
+    /// The zero value
+    pub const ZERO: U256 = U256([0; 4]);
+    /// The one value
+    pub const ONE: U256 = U256([1, 0, 0, 0]);
+    /// The maximum value
+    pub const MAX: U256 = U256([u64::MAX; 4]);
+
+    /// Creates the value from 32 bytes in little endian order, the order of hashes in
+    /// serialized data
+    pub fn from_le_bytes(bytes: [u8; 32]) -> Self {
+        let mut limbs = [0u64; 4];
+        for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks_exact(8)) {
+            *limb = u64::from_le_bytes(chunk.try_into().expect("chunks of 8"));
+        }
+        U256(limbs)
+    }
+
+    /// Returns the value as 32 bytes in little endian order
+    pub fn to_le_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (chunk, limb) in bytes.chunks_exact_mut(8).zip(self.0.iter()) {
+            chunk.copy_from_slice(&limb.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Returns the value as 32 bytes in big endian order, the order used to display hashes
+    pub fn to_be_bytes(&self) -> [u8; 32] {
+        let mut bytes = self.to_le_bytes();
+        bytes.reverse();
+        bytes
+    }
+
+    /// Returns the value if it fits in a `u128`
+    pub fn to_u128(&self) -> Option<u128> {
+        (self.0[2] == 0 && self.0[3] == 0).then(|| (self.0[1] as u128) << 64 | self.0[0] as u128)
+    }
+
+    /// Returns the number of bits needed to represent the value
+    pub fn bits(&self) -> u32 {
+        for (i, limb) in self.0.iter().enumerate().rev() {
+            if *limb != 0 {
+                return 64 * i as u32 + 64 - limb.leading_zeros();
+            }
+        }
+        0
+    }
+
+    /// Decodes a target in the compact format used by the `bits` field of block headers.
+    ///
+    /// Returns [`Error::InvalidTarget`] for negative or overflowing encodings, like the
+    /// `fNegative` and `fOverflow` checks of Bitcoin Core. A zero target is returned as is.
+    pub fn from_compact(compact: u32) -> Result<Self, Error> {
+        let size = compact >> 24;
+        let mut word = compact & 0x007f_ffff;
+        if size <= 3 {
+            word >>= 8 * (3 - size);
+        }
+        if word != 0
+            && ((compact & 0x0080_0000) != 0
+                || size > 34
+                || (word > 0xff && size > 33)
+                || (word > 0xffff && size > 32))
+        {
+            return Err(Error::InvalidTarget);
+        }
+        let value = U256::from(word as u64);
+        Ok(if size <= 3 {
+            value
+        } else {
+            value << (8 * (size - 3))
+        })
+    }
+
+    /// Encodes the value in the compact format used by the `bits` field of block headers
+    pub fn to_compact(&self) -> u32 {
+        let mut size = (self.bits() + 7) / 8;
+        let mut compact = if size <= 3 {
+            (self.0[0] << (8 * (3 - size))) as u32
+        } else {
+            (*self >> (8 * (size - 3))).0[0] as u32
+        };
+        if compact & 0x0080_0000 != 0 {
+            compact >>= 8;
+            size += 1;
+        }
+        compact | size << 24
+    }
+
+    /// Multiply by `other`, wrapping around on overflow
+    pub fn mul_u64(self, other: u64) -> Self {
+        let mut result = [0u64; 4];
+        let mut carry = 0u128;
+        for (r, limb) in result.iter_mut().zip(self.0.iter()) {
+            let product = *limb as u128 * other as u128 + carry;
+            *r = product as u64;
+            carry = product >> 64;
+        }
+        U256(result)
+    }
+
+    /// Returns the work represented by this target, the expected number of hashes needed to
+    /// find a hash not greater than it: `2^256 / (target + 1)`
+    pub fn target_to_work(&self) -> Self {
+        // 2^256 is not representable, but 2^256 / (target + 1) == ~target / (target + 1) + 1
+        if *self == U256::MAX {
+            return U256::ONE;
+        }
+        (!*self / (*self + U256::ONE)) + U256::ONE
+    }
+}
+
+impl From<u64> for U256 {
+    fn from(value: u64) -> Self {
+        U256([value, 0, 0, 0])
+    }
+}
+
+impl Ord for U256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for U256 {
+    type Output = U256;
+    fn add(self, other: U256) -> U256 {
+        let mut result = [0u64; 4];
+        let mut carry = false;
+        for (r, (a, b)) in result.iter_mut().zip(self.0.iter().zip(other.0.iter())) {
+            let (sum, c1) = a.overflowing_add(*b);
+            let (sum, c2) = sum.overflowing_add(carry as u64);
+            *r = sum;
+            carry = c1 || c2;
+        }
+        U256(result)
+    }
+}
+
+impl Sub for U256 {
+    type Output = U256;
+    fn sub(self, other: U256) -> U256 {
+        self + (!other + U256::ONE)
+    }
+}
+
+impl Not for U256 {
+    type Output = U256;
+    fn not(self) -> U256 {
+        U256(self.0.map(|limb| !limb))
+    }
+}
+
+impl Shl<u32> for U256 {
+    type Output = U256;
+    fn shl(self, shift: u32) -> U256 {
+        let mut result = [0u64; 4];
+        let limbs = (shift / 64) as usize;
+        let bits = shift % 64;
+        for (i, r) in result.iter_mut().enumerate().skip(limbs) {
+            *r = self.0[i - limbs] << bits;
+            if bits > 0 && i > limbs {
+                *r |= self.0[i - limbs - 1] >> (64 - bits);
+            }
+        }
+        U256(result)
+    }
+}
+
+impl Shr<u32> for U256 {
+    type Output = U256;
+    fn shr(self, shift: u32) -> U256 {
+        let mut result = [0u64; 4];
+        let limbs = (shift / 64) as usize;
+        let bits = shift % 64;
+        for (i, r) in result
+            .iter_mut()
+            .enumerate()
+            .take(4usize.saturating_sub(limbs))
+        {
+            *r = self.0[i + limbs] >> bits;
+            if bits > 0 && i + limbs + 1 < 4 {
+                *r |= self.0[i + limbs + 1] << (64 - bits);
+            }
+        }
+        U256(result)
+    }
+}
+
+impl Div for U256 {
+    type Output = U256;
+
+    /// Long division, dividing by zero returns zero
+    fn div(self, divisor: U256) -> U256 {
+        if divisor == U256::ZERO || divisor > self {
+            return U256::ZERO;
+        }
+        let mut remainder = self;
+        let mut quotient = U256::ZERO;
+        let mut shift = self.bits() - divisor.bits();
+        let mut shifted = divisor << shift;
+        loop {
+            if remainder >= shifted {
+                remainder = remainder - shifted;
+                quotient.0[(shift / 64) as usize] |= 1 << (shift % 64);
+            }
+            if shift == 0 {
+                break;
+            }
+            shifted = shifted >> 1;
+            shift -= 1;
+        }
+        quotient
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::U256;
+    use crate::Error;
+    use bitcoin::pow::{CompactTarget, Target};
+
+    #[test]
+    fn compact() {
+        for compact in [
+            0x1d00ffff, 0x1b0404cb, 0x170e2632, 0x207fffff, 0x01003456, 0x01123456, 0x02008000,
+            0x05009234, 0x04123456, 0x03000000, 0x22000001, 0x2100ff00,
+        ] {
+            let ours = U256::from_compact(compact).unwrap();
+            let theirs = Target::from_compact(CompactTarget::from_consensus(compact));
+            assert_eq!(ours.to_le_bytes(), theirs.to_le_bytes(), "{:x}", compact);
+            assert_eq!(ours.to_compact(), theirs.to_compact_lossy().to_consensus());
+        }
+        // from bitcoin core arith_uint256_tests
+        assert_eq!(U256::from_compact(0x01fedcba), Err(Error::InvalidTarget));
+        assert_eq!(U256::from_compact(0x04923456), Err(Error::InvalidTarget));
+        assert_eq!(U256::from_compact(0xff123456), Err(Error::InvalidTarget));
+        assert_eq!(U256::from_compact(0x00923456), Ok(U256::ZERO));
+        assert_eq!(
+            U256::from_compact(0x05009234).unwrap().to_compact(),
+            0x05009234
+        );
+        assert_eq!(U256::from(0x80u64).to_compact(), 0x02008000);
+    }
+
+    #[test]
+    fn arithmetic() {
+        let a = U256::from_le_bytes([0xAB; 32]);
+        let b = U256::from(12345u64) << 100;
+        assert_eq!((a + b) - b, a);
+        assert_eq!(U256::MAX + U256::ONE, U256::ZERO);
+        assert_eq!((b >> 100).to_u128(), Some(12345));
+        assert_eq!(b.bits(), 114);
+        assert!((a / b).mul_u64(12345) << 100 <= a);
+        assert_eq!(a / U256::ONE, a);
+        assert_eq!(U256::from(100u64) / U256::from(7u64), U256::from(14u64));
+        assert_eq!(U256::ZERO.to_compact(), 0);
+
+        let target = U256::from_compact(0x1d00ffff).unwrap();
+        assert_eq!(target.target_to_work().to_u128(), Some(0x100010001));
+        let theirs = Target::from_compact(CompactTarget::from_consensus(0x1b0404cb)).to_work();
+        let ours = U256::from_compact(0x1b0404cb).unwrap().target_to_work();
+        assert_eq!(ours.to_le_bytes(), theirs.to_le_bytes());
+        assert_eq!(U256::MAX.target_to_work(), U256::ONE);
+    }
+}
@@ -58,7 +58,8 @@ impl<'a, T: Visit<'a>> Parse<'a> for T {
 /// looks a single trait with many functions is more perfomant.
 #[allow(unused)]
 pub trait Visitor {
-    /// Visit the block header, called from [`bsl::Block::visit()`] and [`bsl::BlockHeader::visit()`]
+    /// Visit the block header, called from [`bsl::Block::visit()`], [`bsl::BlockHeader::visit()`]
+    /// and [`bsl::p2p::Headers::visit()`]
     fn visit_block_header(&mut self, header: &bsl::BlockHeader) -> core::ops::ControlFlow<()> {
         core::ops::ControlFlow::Continue(())
     }
@@ -96,6 +97,70 @@ pub trait Visitor {
     fn visit_witness_element(&mut self, witness_i: usize, witness_element: &[u8]) {}
     /// Finishing visiting this witness
     fn visit_witness_end(&mut self) {}
+
+    /// Visit a network message, called from [`bsl::p2p::Message::visit()`]
+    fn visit_message(&mut self, message: &bsl::p2p::Message) -> core::ops::ControlFlow<()> {
+        core::ops::ControlFlow::Continue(())
+    }
+
+    /// We are going to visit `total_invs` inventory vectors, called from [`bsl::p2p::Invs::visit()`]
+    fn visit_invs(&mut self, total_invs: usize) {}
+    /// Visit the `i`th inventory vector of type `kind` (see [`bsl::p2p::inv_kind`]) and `hash`
+    fn visit_inv(&mut self, i: usize, kind: u32, hash: &[u8]) -> core::ops::ControlFlow<()> {
+        core::ops::ControlFlow::Continue(())
+    }
+    /// We are going to visit `total_headers` block headers, called from [`bsl::p2p::Headers::visit()`]
+    /// before calling [`Visitor::visit_block_header()`] for each one of them
+    fn visit_headers(&mut self, total_headers: usize) {}
+
+    /// We are going to visit `total_addrs` network addresses, called from
+    /// [`bsl::p2p::Addrs::visit()`] and [`bsl::p2p::AddrsV2::visit()`]
+    fn visit_addrs(&mut self, total_addrs: usize) {}
+    /// Visit the `i`th network address of an `addr` message
+    fn visit_addr(&mut self, i: usize, address: &bsl::p2p::Address) -> core::ops::ControlFlow<()> {
+        core::ops::ControlFlow::Continue(())
+    }
+    /// Visit the `i`th network address of an `addrv2` message
+    fn visit_addr_v2(
+        &mut self,
+        i: usize,
+        address: &bsl::p2p::AddressV2,
+    ) -> core::ops::ControlFlow<()> {
+        core::ops::ControlFlow::Continue(())
+    }
+
+    /// We are going to visit the prefilled transaction at position `index` in the block, called
+    /// from [`bsl::p2p::CompactBlock::visit()`] before [`Visitor::visit_transaction()`]
+    fn visit_prefilled_transaction(&mut self, index: usize) -> core::ops::ControlFlow<()> {
+        core::ops::ControlFlow::Continue(())
+    }
+
+    /// We are going to visit the undo data of `total_txs` transactions, called from
+    /// [`bsl::BlockUndo::visit()`]
+    fn visit_block_undo(&mut self, total_txs: usize) {}
+    /// We are going to visit the undo data of `total_spent` outputs spent by a transaction, called
+    /// from [`bsl::TxUndo::visit()`] and [`bsl::BlockUndo::visit_block()`]
+    fn visit_tx_undo(&mut self, total_spent: usize) {}
+    /// Visit the undo data of the output spent by input `vin`. When called from
+    /// [`bsl::BlockUndo::visit_block()`] it follows the [`Visitor::visit_tx_in()`] of the same input
+    fn visit_tx_in_undo(
+        &mut self,
+        vin: usize,
+        tx_in_undo: &bsl::TxInUndo,
+    ) -> core::ops::ControlFlow<()> {
+        core::ops::ControlFlow::Continue(())
+    }
+
+    /// Visit a coin of a UTXO snapshot, called from [`bsl::SnapshotTxCoins::visit()`]
+    fn visit_snapshot_coin(&mut self, coin: &bsl::SnapshotCoin) -> core::ops::ControlFlow<()> {
+        core::ops::ControlFlow::Continue(())
+    }
+
+    /// Visit the transaction at `position` in the block matched by a merkle block, called from
+    /// [`bsl::MerkleBlock::verify()`]
+    fn visit_merkle_match(&mut self, position: usize, txid: &[u8]) -> core::ops::ControlFlow<()> {
+        core::ops::ControlFlow::Continue(())
+    }
 }
 
 /// A visitor with all empty function.